@@ -0,0 +1,126 @@
+//! Parsing and diff throughput with one thread vs. all threads.
+//!
+//! Generates CSV and Parquet fixtures in a temp dir and times parse + diff
+//! for each thread count. Run with:
+//!
+//! ```bash
+//! cargo bench --bench parallel
+//! DATADIFF_BENCH_ROWS=5000000 cargo bench --bench parallel
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arrow::array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+
+use datadiff::config::Config;
+use datadiff::diff::compute_diff;
+use datadiff::parser::ParserFactory;
+
+const DEFAULT_ROWS: usize = 2_000_000;
+const BATCH_ROWS: usize = 100_000;
+
+/// Value of row `i` in the old (`version` 0) or new (`version` 1) fixture;
+/// every 100th row differs between versions
+fn row(i: usize, version: usize) -> (i64, String, f64) {
+    let changed = version == 1 && i.is_multiple_of(100);
+    let region = ["EU", "US", "AP"][i % 3];
+    let amount = (i % 1000) as f64 * 1.5 + if changed { 1.0 } else { 0.0 };
+    (i as i64, format!("{}-{}", region, i % 97), amount)
+}
+
+fn write_csv(path: &Path, rows: usize, version: usize) {
+    let mut out = BufWriter::new(File::create(path).unwrap());
+    writeln!(out, "id,label,amount").unwrap();
+    for i in 0..rows {
+        let (id, label, amount) = row(i, version);
+        writeln!(out, "{},{},{}", id, label, amount).unwrap();
+    }
+}
+
+fn write_parquet(path: &Path, rows: usize, version: usize) {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("label", DataType::Utf8, false),
+        Field::new("amount", DataType::Float64, false),
+    ]));
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(BATCH_ROWS)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(File::create(path).unwrap(), schema.clone(), Some(props)).unwrap();
+
+    for start in (0..rows).step_by(BATCH_ROWS) {
+        let batch: Vec<_> = (start..rows.min(start + BATCH_ROWS))
+            .map(|i| row(i, version))
+            .collect();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(batch.iter().map(|r| r.0))),
+            Arc::new(StringArray::from_iter_values(batch.iter().map(|r| r.1.as_str()))),
+            Arc::new(Float64Array::from_iter_values(batch.iter().map(|r| r.2))),
+        ];
+        writer
+            .write(&RecordBatch::try_new(schema.clone(), columns).unwrap())
+            .unwrap();
+    }
+    writer.close().unwrap();
+}
+
+/// Parse both files and diff them, returning (parse time, diff time)
+fn run(old: &Path, new: &Path) -> (Duration, Duration) {
+    let config = Config::new(old.to_path_buf(), new.to_path_buf())
+        .with_key_columns(vec!["id".to_string()]);
+    let factory = ParserFactory::new();
+
+    let start = Instant::now();
+    let old_table = factory.parse(old, &config).unwrap();
+    let new_table = factory.parse(new, &config).unwrap();
+    let parsed = start.elapsed();
+
+    let start = Instant::now();
+    let diff = compute_diff(&old_table, &new_table, &config);
+    assert_eq!(diff.stats.rows_modified, old_table.row_count().div_ceil(100));
+
+    (parsed, start.elapsed())
+}
+
+fn main() {
+    let rows = std::env::var("DATADIFF_BENCH_ROWS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_ROWS);
+    let dir = tempfile::tempdir().unwrap();
+
+    println!("Generating fixtures with {} rows...", rows);
+    let fixtures = [("csv", write_csv as fn(&Path, usize, usize)), ("parquet", write_parquet)];
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1];
+    if max_threads > 1 {
+        thread_counts.push(max_threads);
+    }
+
+    for (ext, write) in fixtures {
+        let old = dir.path().join(format!("old.{}", ext));
+        let new = dir.path().join(format!("new.{}", ext));
+        write(&old, rows, 0);
+        write(&new, rows, 1);
+
+        for &threads in &thread_counts {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let (parse, diff) = pool.install(|| run(&old, &new));
+            println!(
+                "{:<8} threads={:<3} parse={:>8.2?} diff={:>8.2?}",
+                ext, threads, parse, diff
+            );
+        }
+    }
+}
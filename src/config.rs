@@ -2,6 +2,12 @@
 
 use std::path::PathBuf;
 
+use crate::diff::{ColumnMapping, OutputLimits};
+use crate::filter::{ColumnSelector, FilterExpr};
+use crate::model::KeyNormalization;
+use crate::normalize::{ColumnTransform, Transform};
+use crate::parser::{FixedWidthLayout, RecordPath};
+
 /// Output format for diff results
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum OutputFormat {
@@ -10,6 +16,7 @@ pub enum OutputFormat {
     Json,
     Html,
     Unified,
+    Template,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -21,11 +28,111 @@ impl std::str::FromStr for OutputFormat {
             "json" => Ok(OutputFormat::Json),
             "html" => Ok(OutputFormat::Html),
             "unified" => Ok(OutputFormat::Unified),
+            "template" => Ok(OutputFormat::Template),
             _ => Err(format!("Unknown output format: {}", s)),
         }
     }
 }
 
+/// Which row of an Excel sheet or range holds the column names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderRow {
+    /// The first row of the data range
+    #[default]
+    First,
+    /// The first row that looks like a header, skipping title rows
+    Auto,
+    /// This sheet row (1-based)
+    Row(usize),
+}
+
+impl std::str::FromStr for HeaderRow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(HeaderRow::Auto),
+            n => match n.parse::<usize>() {
+                Ok(row) if row >= 1 => Ok(HeaderRow::Row(row)),
+                _ => Err(format!("Expected a row number or 'auto', got: {}", s)),
+            },
+        }
+    }
+}
+
+/// Granularity of inline diffs for changed string cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InlineDiff {
+    /// Show old and new values whole
+    None,
+    /// Highlight inserted and deleted words
+    #[default]
+    Word,
+    /// Highlight inserted and deleted characters
+    Char,
+}
+
+impl std::str::FromStr for InlineDiff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(InlineDiff::None),
+            "word" => Ok(InlineDiff::Word),
+            "char" => Ok(InlineDiff::Char),
+            _ => Err(format!("Unknown inline diff mode: {}", s)),
+        }
+    }
+}
+
+/// When to colour terminal output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colour when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("Unknown color mode: {}", s)),
+        }
+    }
+}
+
+/// How rows are laid out in terminal output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RowLayout {
+    /// Tables, switching to records when a table does not fit the terminal
+    #[default]
+    Auto,
+    /// Always tables, shrinking columns to fit
+    Table,
+    /// One record per row, a line per column
+    Vertical,
+}
+
+impl std::str::FromStr for RowLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(RowLayout::Auto),
+            "table" => Ok(RowLayout::Table),
+            "vertical" => Ok(RowLayout::Vertical),
+            _ => Err(format!("Unknown row layout: {}", s)),
+        }
+    }
+}
+
 /// Configuration for diff operations
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -37,20 +144,84 @@ pub struct Config {
     pub key_columns: Vec<String>,
     /// Output format
     pub output_format: OutputFormat,
+    /// Template for the template format: a built-in name (html, markdown,
+    /// text) or a path to a Tera template
+    pub template: Option<String>,
+    /// Inline diff granularity for changed string cells
+    pub inline_diff: InlineDiff,
+    /// Unchanged rows shown around changes in unified output
+    pub context_lines: usize,
+    /// When to colour terminal output
+    pub color: ColorMode,
+    /// Row layout in terminal output
+    pub layout: RowLayout,
+    /// Show only key columns and columns with changes in terminal output
+    pub changed_columns_only: bool,
+    /// Truncate terminal table cells to this many characters (0 = no limit)
+    pub max_cell_width: usize,
+    /// Terminal width (None = detect)
+    pub terminal_width: Option<usize>,
+    /// Page long terminal output through `$PAGER`
+    pub pager: bool,
+    /// Show at most this many row changes
+    pub max_rows: Option<usize>,
+    /// Show at most this many added, removed and modified rows each
+    pub max_changes_per_section: Option<usize>,
+    /// Show a random sample of this many row changes
+    pub sample: Option<usize>,
+    /// Seed for `sample`
+    pub sample_seed: u64,
+    /// Show only this many modified rows, largest numeric change first
+    pub top_changes: Option<usize>,
     /// Ignore case when comparing string values
     pub ignore_case: bool,
     /// Tolerance for numeric comparisons
     pub numeric_tolerance: Option<f64>,
     /// Ignore leading/trailing whitespace in string values
     pub ignore_whitespace: bool,
+    /// Compare list values as sets (ignore element order)
+    pub unordered_lists: bool,
+    /// Cast both sides of a comparison to a shared type (`"42"` = `42`)
+    pub coerce_types: bool,
+    /// Match keys by numeric value (`"007"`, `7` and `7.0` are the same key)
+    pub numeric_keys: bool,
     /// Columns to ignore in comparison
     pub ignore_columns: Vec<String>,
+    /// Only compare these columns (plus key columns); empty = all
+    pub include_columns: Vec<ColumnSelector>,
+    /// How columns are matched when names differ between the files
+    pub column_mapping: ColumnMapping,
+    /// Normalisation steps applied to values before comparison, in order
+    pub normalize: Vec<ColumnTransform>,
     /// Column to sort by before diffing (normalizes order)
     pub sort_by: Option<String>,
+    /// Only compare rows matching this filter
+    pub filter: Option<FilterExpr>,
     /// For Excel files: which sheet to compare
     pub sheet_name: Option<String>,
+    /// For Excel files: compare every sheet
+    pub all_sheets: bool,
+    /// Key columns for individual sheets, overriding `key_columns`
+    pub sheet_keys: Vec<(String, Vec<String>)>,
+    /// For Excel files: where the column names are
+    pub header_row: HeaderRow,
+    /// For Excel files: number of header rows, joined into one name
+    pub header_rows: usize,
+    /// For Excel files: cell range, Excel Table or defined name to read
+    pub data_range: Option<String>,
+    /// For Excel files: compare formula text as well as cached values
+    pub excel_formulas: bool,
+    /// For xlsx files: also compare formatting, comments, data validation
+    /// and hidden rows and columns
+    pub excel_formatting: bool,
+    /// For fixed-width files: where each column is
+    pub fixed_width_layout: Option<FixedWidthLayout>,
+    /// For XML, YAML and JSON files: where the records are
+    pub record_path: Option<RecordPath>,
     /// Only show statistics, not detailed changes
     pub stats_only: bool,
+    /// Number of worker threads (None = one per CPU)
+    pub threads: Option<usize>,
     /// Git diff driver mode
     pub git_driver_mode: bool,
 }
@@ -62,13 +233,44 @@ impl Default for Config {
             new_file: PathBuf::new(),
             key_columns: Vec::new(),
             output_format: OutputFormat::default(),
+            template: None,
+            inline_diff: InlineDiff::default(),
+            context_lines: 3,
+            color: ColorMode::default(),
+            layout: RowLayout::default(),
+            changed_columns_only: false,
+            max_cell_width: 40,
+            terminal_width: None,
+            pager: true,
+            max_rows: None,
+            max_changes_per_section: None,
+            sample: None,
+            sample_seed: 0,
+            top_changes: None,
             ignore_case: false,
             numeric_tolerance: None,
             ignore_whitespace: false,
+            unordered_lists: false,
+            coerce_types: false,
+            numeric_keys: false,
             ignore_columns: Vec::new(),
+            include_columns: Vec::new(),
+            column_mapping: ColumnMapping::default(),
+            normalize: Vec::new(),
             sort_by: None,
+            filter: None,
             sheet_name: None,
+            all_sheets: false,
+            sheet_keys: Vec::new(),
+            header_row: HeaderRow::default(),
+            header_rows: 1,
+            data_range: None,
+            excel_formulas: false,
+            excel_formatting: false,
+            fixed_width_layout: None,
+            record_path: None,
             stats_only: false,
+            threads: None,
             git_driver_mode: false,
         }
     }
@@ -96,6 +298,86 @@ impl Config {
         self
     }
 
+    /// Render output through a template (built-in name or file path)
+    pub fn with_template(mut self, template: String) -> Self {
+        self.output_format = OutputFormat::Template;
+        self.template = Some(template);
+        self
+    }
+
+    /// Set inline diff granularity for changed string cells
+    pub fn with_inline_diff(mut self, inline_diff: InlineDiff) -> Self {
+        self.inline_diff = inline_diff;
+        self
+    }
+
+    /// Set the number of context rows in unified output
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Set when to colour terminal output
+    pub fn with_color(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the terminal row layout
+    pub fn with_layout(mut self, layout: RowLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Show only key and changed columns in terminal output
+    pub fn with_changed_columns_only(mut self, changed_only: bool) -> Self {
+        self.changed_columns_only = changed_only;
+        self
+    }
+
+    /// Set the maximum width of terminal table cells (0 = no limit)
+    pub fn with_max_cell_width(mut self, width: usize) -> Self {
+        self.max_cell_width = width;
+        self
+    }
+
+    /// Set the terminal width instead of detecting it
+    pub fn with_terminal_width(mut self, width: usize) -> Self {
+        self.terminal_width = Some(width);
+        self
+    }
+
+    /// Enable or disable the pager for long terminal output
+    pub fn with_pager(mut self, pager: bool) -> Self {
+        self.pager = pager;
+        self
+    }
+
+    /// Show at most this many row changes
+    pub fn with_max_rows(mut self, max: usize) -> Self {
+        self.max_rows = Some(max);
+        self
+    }
+
+    /// Show at most this many row changes per section
+    pub fn with_max_changes_per_section(mut self, max: usize) -> Self {
+        self.max_changes_per_section = Some(max);
+        self
+    }
+
+    /// Show a random sample of row changes, picked by seed
+    pub fn with_sample(mut self, size: usize, seed: u64) -> Self {
+        self.sample = Some(size);
+        self.sample_seed = seed;
+        self
+    }
+
+    /// Show only the modified rows with the largest numeric changes
+    pub fn with_top_changes(mut self, top: usize) -> Self {
+        self.top_changes = Some(top);
+        self
+    }
+
     /// Enable case-insensitive comparison
     pub fn with_ignore_case(mut self, ignore: bool) -> Self {
         self.ignore_case = ignore;
@@ -114,27 +396,174 @@ impl Config {
         self
     }
 
+    /// Enable order-insensitive comparison of list values
+    pub fn with_unordered_lists(mut self, unordered: bool) -> Self {
+        self.unordered_lists = unordered;
+        self
+    }
+
+    /// Enable type-coercing comparison of values
+    pub fn with_coerce_types(mut self, coerce: bool) -> Self {
+        self.coerce_types = coerce;
+        self
+    }
+
+    /// Enable numeric canonicalisation of key values
+    pub fn with_numeric_keys(mut self, numeric: bool) -> Self {
+        self.numeric_keys = numeric;
+        self
+    }
+
     /// Set columns to ignore
     pub fn with_ignore_columns(mut self, columns: Vec<String>) -> Self {
         self.ignore_columns = columns;
         self
     }
 
+    /// Only compare columns matching these selectors, plus key columns
+    pub fn with_include_columns(mut self, columns: Vec<ColumnSelector>) -> Self {
+        self.include_columns = columns;
+        self
+    }
+
+    /// Set how columns are matched between the files
+    pub fn with_column_mapping(mut self, mapping: ColumnMapping) -> Self {
+        self.column_mapping = mapping;
+        self
+    }
+
+    /// Set the normalisation steps applied before comparison
+    pub fn with_normalize(mut self, steps: Vec<ColumnTransform>) -> Self {
+        self.normalize = steps;
+        self
+    }
+
     /// Set sort column for normalization
     pub fn with_sort_by(mut self, column: String) -> Self {
         self.sort_by = Some(column);
         self
     }
 
+    /// Set the row filter
+    pub fn with_filter(mut self, filter: FilterExpr) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
     /// Set Excel sheet name
     pub fn with_sheet_name(mut self, name: String) -> Self {
         self.sheet_name = Some(name);
         self
     }
 
+    /// Compare every sheet of two workbooks
+    pub fn with_all_sheets(mut self, all_sheets: bool) -> Self {
+        self.all_sheets = all_sheets;
+        self
+    }
+
+    /// Set key columns for a sheet
+    pub fn with_sheet_keys(mut self, sheet: String, keys: Vec<String>) -> Self {
+        self.sheet_keys.push((sheet, keys));
+        self
+    }
+
+    /// Set where the Excel header row is
+    pub fn with_header_row(mut self, header_row: HeaderRow) -> Self {
+        self.header_row = header_row;
+        self
+    }
+
+    /// Set the number of Excel header rows
+    pub fn with_header_rows(mut self, rows: usize) -> Self {
+        self.header_rows = rows.max(1);
+        self
+    }
+
+    /// Set the Excel range, table or defined name to read
+    pub fn with_data_range(mut self, range: String) -> Self {
+        self.data_range = Some(range);
+        self
+    }
+
+    /// Compare Excel formulas as well as values
+    pub fn with_excel_formulas(mut self, formulas: bool) -> Self {
+        self.excel_formulas = formulas;
+        self
+    }
+
+    /// Compare xlsx formatting as well as values
+    pub fn with_excel_formatting(mut self, formatting: bool) -> Self {
+        self.excel_formatting = formatting;
+        self
+    }
+
+    /// Read fixed-width files with this column layout
+    pub fn with_fixed_width_layout(mut self, layout: FixedWidthLayout) -> Self {
+        self.fixed_width_layout = Some(layout);
+        self
+    }
+
+    /// Read the records of XML, YAML and JSON files at this path
+    pub fn with_record_path(mut self, path: RecordPath) -> Self {
+        self.record_path = Some(path);
+        self
+    }
+
     /// Enable stats-only mode
     pub fn with_stats_only(mut self, stats_only: bool) -> Self {
         self.stats_only = stats_only;
         self
     }
+
+    /// Set the number of worker threads
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Whether a column is compared under the include list; key columns
+    /// always are
+    pub fn includes_column(&self, name: &str) -> bool {
+        self.include_columns.is_empty()
+            || self.key_columns.iter().any(|k| k == name)
+            || self.include_columns.iter().any(|s| s.matches(name))
+    }
+
+    /// Normalisation steps for a column, in the order given
+    pub fn normalization_for(&self, name: &str) -> Vec<&Transform> {
+        self.normalize
+            .iter()
+            .filter(|t| t.selector.matches(name))
+            .map(|t| &t.transform)
+            .collect()
+    }
+
+    /// Key columns for a sheet: its own, or the global ones
+    pub fn key_columns_for_sheet(&self, sheet: &str) -> &[String] {
+        self.sheet_keys
+            .iter()
+            .find(|(name, _)| name == sheet)
+            .map_or(&self.key_columns, |(_, keys)| keys)
+    }
+
+    /// Which row changes to show in output
+    pub fn output_limits(&self) -> OutputLimits {
+        OutputLimits {
+            max_rows: self.max_rows,
+            max_per_section: self.max_changes_per_section,
+            sample: self.sample,
+            seed: self.sample_seed,
+            top: self.top_changes,
+        }
+    }
+
+    /// How key values are normalised before rows are matched
+    pub fn key_normalization(&self) -> KeyNormalization {
+        KeyNormalization {
+            fold_case: self.ignore_case,
+            trim: self.ignore_whitespace,
+            canonical_numbers: self.numeric_keys,
+        }
+    }
 }
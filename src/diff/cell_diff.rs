@@ -2,11 +2,23 @@
 
 use crate::model::CellValue;
 
+/// A difference found inside a nested (list or struct) cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedChange {
+    /// Path to the changed value, e.g. `address.city` or `tags[0]`
+    pub path: String,
+    /// Old value (Null if the element was added)
+    pub old_value: CellValue,
+    /// New value (Null if the element was removed)
+    pub new_value: CellValue,
+}
+
 /// Cell comparator with configurable options
 pub struct CellComparator {
     ignore_case: bool,
     ignore_whitespace: bool,
     numeric_tolerance: Option<f64>,
+    unordered_lists: bool,
 }
 
 impl CellComparator {
@@ -16,11 +28,29 @@ impl CellComparator {
             ignore_case,
             ignore_whitespace,
             numeric_tolerance,
+            unordered_lists: false,
         }
     }
 
+    /// Compare lists as multisets, ignoring element order
+    pub fn with_unordered_lists(mut self, unordered: bool) -> Self {
+        self.unordered_lists = unordered;
+        self
+    }
+
     /// Compare two cell values for equality
     pub fn equal(&self, a: &CellValue, b: &CellValue) -> bool {
+        match (a, b) {
+            (CellValue::List(a), CellValue::List(b)) => return self.lists_equal(a, b),
+            (CellValue::Struct(a), CellValue::Struct(b)) => {
+                return a.len() == b.len()
+                    && a.iter().all(|(name, value)| {
+                        b.get(name).is_some_and(|other| self.equal(value, other))
+                    });
+            }
+            _ => {}
+        }
+
         // Handle tolerance for numeric values
         if let Some(tolerance) = self.numeric_tolerance {
             if a.equals_with_tolerance(b, tolerance) {
@@ -29,22 +59,136 @@ impl CellComparator {
         }
 
         // Handle case-insensitive comparison
-        if self.ignore_case {
-            if a.equals_ignore_case(b) {
-                return true;
-            }
+        if self.ignore_case && a.equals_ignore_case(b) {
+            return true;
         }
 
         // Handle whitespace-insensitive comparison
-        if self.ignore_whitespace {
-            if a.equals_ignore_whitespace(b) {
-                return true;
-            }
+        if self.ignore_whitespace && a.equals_ignore_whitespace(b) {
+            return true;
         }
 
         // Standard equality
         a == b
     }
+
+    fn lists_equal(&self, a: &[CellValue], b: &[CellValue]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        if self.unordered_lists {
+            self.unmatched_elements(a, b).is_empty()
+        } else {
+            a.iter().zip(b).all(|(x, y)| self.equal(x, y))
+        }
+    }
+
+    /// Pair up equal elements of two lists regardless of order and return the
+    /// leftovers: (index, from_old) for every element without a partner
+    fn unmatched_elements(&self, a: &[CellValue], b: &[CellValue]) -> Vec<(usize, bool)> {
+        let mut used = vec![false; b.len()];
+        let mut unmatched = Vec::new();
+
+        for (i, x) in a.iter().enumerate() {
+            let partner = b
+                .iter()
+                .enumerate()
+                .position(|(j, y)| !used[j] && self.equal(x, y));
+            match partner {
+                Some(j) => used[j] = true,
+                None => unmatched.push((i, true)),
+            }
+        }
+        unmatched.extend(used.iter().enumerate().filter(|(_, u)| !**u).map(|(j, _)| (j, false)));
+        unmatched
+    }
+
+    /// Find the individual differences between two nested values.
+    ///
+    /// Struct fields are addressed as `prefix.field` and list elements as
+    /// `prefix[i]`. With unordered lists, elements without an equal partner on
+    /// the other side are reported as `prefix[]` against Null.
+    pub fn diff_nested(&self, prefix: &str, a: &CellValue, b: &CellValue) -> Vec<NestedChange> {
+        let mut changes = Vec::new();
+        self.collect_nested(prefix, a, b, &mut changes);
+        changes
+    }
+
+    fn collect_nested(
+        &self,
+        path: &str,
+        a: &CellValue,
+        b: &CellValue,
+        changes: &mut Vec<NestedChange>,
+    ) {
+        match (a, b) {
+            (CellValue::Struct(old), CellValue::Struct(new)) => {
+                for (name, old_value) in old {
+                    let field_path = format!("{}.{}", path, name);
+                    let new_value = new.get(name).unwrap_or(&CellValue::Null);
+                    self.collect_nested(&field_path, old_value, new_value, changes);
+                }
+                for (name, new_value) in new.iter().filter(|(name, _)| !old.contains_key(*name)) {
+                    let field_path = format!("{}.{}", path, name);
+                    self.collect_nested(&field_path, &CellValue::Null, new_value, changes);
+                }
+            }
+            (CellValue::List(old), CellValue::List(new)) if self.unordered_lists => {
+                let element_path = format!("{}[]", path);
+                for (idx, from_old) in self.unmatched_elements(old, new) {
+                    let (old_value, new_value) = if from_old {
+                        (old[idx].clone(), CellValue::Null)
+                    } else {
+                        (CellValue::Null, new[idx].clone())
+                    };
+                    changes.push(NestedChange {
+                        path: element_path.clone(),
+                        old_value,
+                        new_value,
+                    });
+                }
+            }
+            (CellValue::List(old), CellValue::List(new)) => {
+                for (idx, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                    let element_path = format!("{}[{}]", path, idx);
+                    self.collect_nested(&element_path, old_value, new_value, changes);
+                }
+                let shared = old.len().min(new.len());
+                let extra = if old.len() > shared { &old[shared..] } else { &new[shared..] };
+                // A null element of only one list is told apart from a missing
+                // one by showing both lists
+                if extra.iter().any(CellValue::is_null) {
+                    changes.push(NestedChange {
+                        path: path.to_string(),
+                        old_value: a.clone(),
+                        new_value: b.clone(),
+                    });
+                    return;
+                }
+                for (idx, value) in extra.iter().enumerate() {
+                    let (old_value, new_value) = if old.len() > shared {
+                        (value.clone(), CellValue::Null)
+                    } else {
+                        (CellValue::Null, value.clone())
+                    };
+                    changes.push(NestedChange {
+                        path: format!("{}[{}]", path, shared + idx),
+                        old_value,
+                        new_value,
+                    });
+                }
+            }
+            _ => {
+                if !self.equal(a, b) {
+                    changes.push(NestedChange {
+                        path: path.to_string(),
+                        old_value: a.clone(),
+                        new_value: b.clone(),
+                    });
+                }
+            }
+        }
+    }
 }
 
 impl Default for CellComparator {
@@ -58,12 +202,14 @@ pub fn percentage_change(old: &CellValue, new: &CellValue) -> Option<f64> {
     let old_num = match old {
         CellValue::Int(i) => *i as f64,
         CellValue::Float(f) => *f,
+        CellValue::Decimal(d) => d.to_f64(),
         _ => return None,
     };
 
     let new_num = match new {
         CellValue::Int(i) => *i as f64,
         CellValue::Float(f) => *f,
+        CellValue::Decimal(d) => d.to_f64(),
         _ => return None,
     };
 
@@ -114,6 +260,59 @@ mod tests {
         assert!(!comparator.equal(&CellValue::Float(1.0), &CellValue::Float(1.02)));
     }
 
+    fn address(city: &str, tags: &[&str]) -> CellValue {
+        let mut fields = indexmap::IndexMap::new();
+        fields.insert("city".to_string(), CellValue::from(city));
+        fields.insert(
+            "tags".to_string(),
+            CellValue::List(tags.iter().map(|t| CellValue::from(*t)).collect()),
+        );
+        CellValue::Struct(fields)
+    }
+
+    #[test]
+    fn test_nested_changes_have_paths() {
+        let comparator = CellComparator::default();
+        let changes = comparator.diff_nested(
+            "address",
+            &address("Oslo", &["a", "b"]),
+            &address("Bergen", &["a", "c"]),
+        );
+
+        let paths: Vec<_> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["address.city", "address.tags[1]"]);
+        assert_eq!(changes[0].new_value, CellValue::from("Bergen"));
+
+        // A null appended to a list is a change too, shown as the whole lists
+        let old = CellValue::List(vec![CellValue::Int(1)]);
+        let new = CellValue::List(vec![CellValue::Int(1), CellValue::Null]);
+        assert!(!comparator.equal(&old, &new));
+        let changes = comparator.diff_nested("ids", &old, &new);
+        assert_eq!(
+            changes,
+            vec![NestedChange {
+                path: "ids".to_string(),
+                old_value: old,
+                new_value: new,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unordered_lists() {
+        let old = address("Oslo", &["a", "b"]);
+        let new = address("Oslo", &["b", "a"]);
+
+        assert!(!CellComparator::default().equal(&old, &new));
+
+        let comparator = CellComparator::default().with_unordered_lists(true);
+        assert!(comparator.equal(&old, &new));
+
+        let changes = comparator.diff_nested("address", &old, &address("Oslo", &["b", "c"]));
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| c.path == "address.tags[]"));
+    }
+
     #[test]
     fn test_percentage_change() {
         assert_eq!(
@@ -125,4 +324,78 @@ mod tests {
             Some(-20.0)
         );
     }
+
+    #[test]
+    fn test_eq_and_hash_agree() {
+        use rustc_hash::FxHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |v: &CellValue| {
+            let mut hasher = FxHasher::default();
+            v.hash(&mut hasher);
+            hasher.finish()
+        };
+        let pairs = [
+            (CellValue::Int(1), CellValue::Float(1.0)),
+            (CellValue::Int(0), CellValue::Float(-0.0)),
+            (CellValue::Float(f64::NAN), CellValue::Float(-f64::NAN)),
+            (
+                CellValue::Struct(
+                    [("a", CellValue::Int(1)), ("b", CellValue::Int(2))]
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), v))
+                        .collect(),
+                ),
+                CellValue::Struct(
+                    [("b", CellValue::Float(2.0)), ("a", CellValue::Int(1))]
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), v))
+                        .collect(),
+                ),
+            ),
+        ];
+        for (a, b) in &pairs {
+            assert_eq!(a, b);
+            assert_eq!(hash(a), hash(b));
+        }
+        // Beyond 2^53 an i64 does not round-trip through f64
+        assert_ne!(CellValue::Int((1 << 53) + 1), CellValue::Float((1u64 << 53) as f64));
+    }
+
+    #[test]
+    fn test_coerce_types() {
+        use crate::config::Config;
+        use crate::diff::compute_diff;
+        use crate::model::{CellType, Column, Table};
+
+        let table = |types: [CellType; 3], cells: Vec<CellValue>| {
+            let mut columns = vec![Column::with_type("id", 0, CellType::Int)];
+            columns.extend(
+                ["count", "day", "flag"]
+                    .iter()
+                    .zip(types)
+                    .enumerate()
+                    .map(|(i, (name, t))| Column::with_type(*name, i + 1, t)),
+            );
+            let mut table = Table::new(columns);
+            table.set_key_columns(&["id".to_string()]);
+            let mut row = vec![CellValue::Int(1)];
+            row.extend(cells);
+            table.add_row(row, 2);
+            table
+        };
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let old = table(
+            [CellType::String, CellType::String, CellType::Bool],
+            vec!["42".into(), "2024-01-01".into(), CellValue::Bool(true)],
+        );
+        let new = table(
+            [CellType::Int, CellType::Date, CellType::Int],
+            vec![CellValue::Int(42), CellValue::Date(day), CellValue::Int(1)],
+        );
+
+        assert_eq!(compute_diff(&old, &new, &Config::default()).stats.cells_changed, 3);
+        let coerced = compute_diff(&old, &new, &Config::default().with_coerce_types(true));
+        assert_eq!(coerced.stats.cells_changed, 0);
+    }
 }
@@ -0,0 +1,273 @@
+//! Matching columns between tables whose names differ
+
+use anyhow::{Context, Result};
+use rustc_hash::FxHashMap;
+
+use crate::model::Table;
+
+/// How columns of the old table are matched to columns of the new one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnMapping {
+    /// Explicit (old name, new name) pairs
+    pub pairs: Vec<(String, String)>,
+    /// Also match names that differ only in case, spaces, underscores and
+    /// hyphens (`Customer ID` and `customer_id`)
+    pub loose: bool,
+}
+
+impl ColumnMapping {
+    /// Parse an `OLD=NEW` pair
+    pub fn parse_pair(input: &str) -> Result<(String, String)> {
+        let (old, new) = input
+            .split_once('=')
+            .with_context(|| format!("Expected OLD=NEW column names, got: {}", input))?;
+        Ok((old.trim().to_string(), new.trim().to_string()))
+    }
+
+    /// Whether columns are only matched by equal names
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty() && !self.loose
+    }
+
+    /// Pair up (old index, new index) of matching columns, in old-table
+    /// order. Explicit pairs are matched first, then equal names, then
+    /// loosely equal names where exactly one column on each side has them.
+    pub fn resolve(&self, old_table: &Table, new_table: &Table) -> Vec<(usize, usize)> {
+        let mut partner: Vec<Option<usize>> = vec![None; old_table.columns.len()];
+        let mut new_used = vec![false; new_table.columns.len()];
+        let mut pair = |partner: &mut Vec<Option<usize>>, old_idx: usize, new_idx: usize| {
+            if partner[old_idx].is_none() && !new_used[new_idx] {
+                partner[old_idx] = Some(new_idx);
+                new_used[new_idx] = true;
+            }
+        };
+
+        for (old_name, new_name) in &self.pairs {
+            if let (Some(old_idx), Some(new_idx)) = (
+                old_table.column_index(old_name),
+                new_table.column_index(new_name),
+            ) {
+                pair(&mut partner, old_idx, new_idx);
+            }
+        }
+
+        for (old_idx, column) in old_table.columns.iter().enumerate() {
+            if let Some(new_idx) = new_table.column_index(&column.name) {
+                pair(&mut partner, old_idx, new_idx);
+            }
+        }
+
+        if self.loose {
+            // Loose name -> (unmatched old columns, unmatched new columns)
+            let mut candidates: FxHashMap<String, (Vec<usize>, Vec<usize>)> = FxHashMap::default();
+            for (old_idx, column) in old_table.columns.iter().enumerate() {
+                if partner[old_idx].is_none() {
+                    candidates
+                        .entry(loose_name(&column.name))
+                        .or_default()
+                        .0
+                        .push(old_idx);
+                }
+            }
+            for (new_idx, column) in new_table.columns.iter().enumerate() {
+                if let Some(entry) = candidates.get_mut(&loose_name(&column.name)) {
+                    entry.1.push(new_idx);
+                }
+            }
+            for (olds, news) in candidates.values() {
+                if let ([old_idx], [new_idx]) = (olds.as_slice(), news.as_slice()) {
+                    pair(&mut partner, *old_idx, *new_idx);
+                }
+            }
+        }
+
+        partner
+            .into_iter()
+            .enumerate()
+            .filter_map(|(old_idx, new_idx)| Some((old_idx, new_idx?)))
+            .collect()
+    }
+
+    /// Rename mapped columns of the new table to their old names, so each
+    /// pair is shown under one name. A rename that would give the new table
+    /// two columns of the same name is left out, and that pair keeps both
+    /// names. Returns the mapping that pairs the columns of the renamed
+    /// tables, and the (new name, old name) renames.
+    pub fn apply(
+        &self,
+        old_table: &Table,
+        new_table: &mut Table,
+    ) -> (ColumnMapping, Vec<(String, String)>) {
+        let pairs = self.resolve(old_table, new_table);
+        let mut names: Vec<String> = new_table.columns.iter().map(|c| c.name.clone()).collect();
+        for &(old_idx, new_idx) in &pairs {
+            names[new_idx] = old_table.columns[old_idx].name.clone();
+        }
+        // Undoing one rename can make another collide, so repeat until none do
+        loop {
+            let mut reverted = false;
+            for &(_, new_idx) in &pairs {
+                let original = &new_table.columns[new_idx].name;
+                if names[new_idx] != *original
+                    && names.iter().filter(|name| **name == names[new_idx]).count() > 1
+                {
+                    names[new_idx] = original.clone();
+                    reverted = true;
+                }
+            }
+            if !reverted {
+                break;
+            }
+        }
+
+        let mut renames = Vec::new();
+        for (column, name) in new_table.columns.iter_mut().zip(names) {
+            if column.name != name {
+                renames.push((std::mem::replace(&mut column.name, name.clone()), name));
+            }
+        }
+        let mapping = ColumnMapping {
+            pairs: pairs
+                .into_iter()
+                .map(|(old_idx, new_idx)| {
+                    (
+                        old_table.columns[old_idx].name.clone(),
+                        new_table.columns[new_idx].name.clone(),
+                    )
+                })
+                .collect(),
+            loose: false,
+        };
+        (mapping, renames)
+    }
+}
+
+/// Column name with case, spaces, underscores and hyphens ignored
+fn loose_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Column;
+
+    fn table(names: &[&str]) -> Table {
+        Table::new(
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, n)| Column::new(*n, i))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_resolve() {
+        let old = table(&["Customer ID", "Amount", "Region", "Note"]);
+        let new = table(&["region", "customer_id", "total", "notes"]);
+
+        assert_eq!(ColumnMapping::default().resolve(&old, &new), vec![]);
+
+        let mapping = ColumnMapping {
+            pairs: vec![("Amount".to_string(), "total".to_string())],
+            loose: true,
+        };
+        assert_eq!(mapping.resolve(&old, &new), vec![(0, 1), (1, 2), (2, 0)]);
+
+        let mut new = new;
+        let (applied, renames) = mapping.apply(&old, &mut new);
+        assert_eq!(renames.len(), 3);
+        let names: Vec<&str> = new.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Region", "Customer ID", "Amount", "notes"]);
+        assert_eq!(applied.resolve(&old, &new), vec![(0, 1), (1, 2), (2, 0)]);
+
+        // The new file also has a column with the old name: no rename
+        let old = table(&["id", "Amount"]);
+        let mut new = table(&["id", "Amount", "total"]);
+        let mapping = ColumnMapping {
+            pairs: vec![("Amount".to_string(), "total".to_string())],
+            loose: false,
+        };
+        let (applied, renames) = mapping.apply(&old, &mut new);
+        assert!(renames.is_empty());
+        assert_eq!(applied.resolve(&old, &new), vec![(0, 0), (1, 2)]);
+
+        // Swapped names are renamed both ways
+        let old = table(&["a", "b"]);
+        let mut new = table(&["b", "a"]);
+        let mapping = ColumnMapping {
+            pairs: vec![
+                ("a".to_string(), "b".to_string()),
+                ("b".to_string(), "a".to_string()),
+            ],
+            loose: false,
+        };
+        let (applied, renames) = mapping.apply(&old, &mut new);
+        assert_eq!(renames.len(), 2);
+        assert_eq!(applied.resolve(&old, &new), vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_selectors_name_old_columns() {
+        use crate::config::Config;
+        use crate::diff::{compute_diff, DiffStats};
+        use crate::filter::{parse_column_selectors, FilterExpr};
+        use crate::parser::ParserFactory;
+
+        let dir = tempfile::tempdir().unwrap();
+        let old_path = dir.path().join("old.csv");
+        let new_path = dir.path().join("new.csv");
+        std::fs::write(&old_path, "cust,region,amt\n1,EU,10\n2,US,20\n3,EU,30\n").unwrap();
+        std::fs::write(
+            &new_path,
+            "customer,area,amount\n1,EU,10\n2,US,25\n3,EU,31\n",
+        )
+        .unwrap();
+        let mapping = ColumnMapping {
+            pairs: vec![
+                ("cust".to_string(), "customer".to_string()),
+                ("region".to_string(), "area".to_string()),
+                ("amt".to_string(), "amount".to_string()),
+            ],
+            loose: false,
+        };
+        let diff_stats = |config: Config| -> DiffStats {
+            let factory = ParserFactory::new();
+            let parse_config = ParserFactory::parse_config(&config);
+            let old = factory.parse(&old_path, &parse_config).unwrap();
+            let new = factory.parse(&new_path, &parse_config).unwrap();
+            let (old, new, mapping) =
+                ParserFactory::prepare_pair(old, new, &config.key_columns, &config);
+            compute_diff(&old, &new, &config.with_column_mapping(mapping)).stats
+        };
+        let keyed = |key: &str| {
+            Config::default()
+                .with_key_columns(vec![key.to_string()])
+                .with_column_mapping(mapping.clone())
+        };
+
+        // Keys by either name
+        for key in ["cust", "customer"] {
+            let stats = diff_stats(keyed(key));
+            assert_eq!((stats.rows_modified, stats.rows_unchanged), (2, 1));
+        }
+
+        let stats = diff_stats(Config {
+            filter: Some(FilterExpr::parse("region = 'EU'").unwrap()),
+            ..keyed("cust")
+        });
+        assert_eq!((stats.old_row_count, stats.new_row_count), (2, 2));
+        assert_eq!((stats.rows_modified, stats.rows_unchanged), (1, 1));
+
+        let stats = diff_stats(Config {
+            include_columns: parse_column_selectors(&["region".to_string()]).unwrap(),
+            ..keyed("cust")
+        });
+        assert!(!stats.has_changes());
+        assert_eq!(stats.rows_unchanged, 3);
+    }
+}
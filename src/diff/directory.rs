@@ -0,0 +1,386 @@
+//! Comparing every data file of two directories
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::model::Table;
+use crate::parser::{content_hash, ParserFactory};
+
+use super::{compute_diff, DiffResult};
+
+/// Share of column names two files must have in common to count as a rename
+const SCHEMA_SIMILARITY: f64 = 0.5;
+
+/// How a renamed file was recognised
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameMatch {
+    /// Byte-for-byte the same file
+    Content,
+    /// Mostly the same column names
+    Schema,
+}
+
+/// A file present in only one directory, or under another path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FileChange {
+    Added {
+        path: String,
+    },
+    Removed {
+        path: String,
+    },
+    Renamed {
+        old_path: String,
+        new_path: String,
+        matched_by: RenameMatch,
+    },
+}
+
+impl std::fmt::Display for FileChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileChange::Added { path } => write!(f, "+ {} (new file)", path),
+            FileChange::Removed { path } => write!(f, "- {} (removed file)", path),
+            FileChange::Renamed {
+                old_path,
+                new_path,
+                matched_by,
+            } => {
+                let reason = match matched_by {
+                    RenameMatch::Content => "same content",
+                    RenameMatch::Schema => "similar columns",
+                };
+                write!(f, "~ {} → {} (renamed, {})", old_path, new_path, reason)
+            }
+        }
+    }
+}
+
+/// Row diff of a file present in both directories
+#[derive(Debug)]
+pub struct FileDiff {
+    /// Path relative to the old directory, with `/` separators
+    pub old_path: String,
+    /// Path relative to the new directory
+    pub new_path: String,
+    pub old_table: Table,
+    pub new_table: Table,
+    pub diff: DiffResult,
+}
+
+/// Result of comparing two directories file by file
+#[derive(Debug, Default)]
+pub struct DirectoryDiff {
+    /// Files added, removed or renamed
+    pub file_changes: Vec<FileChange>,
+    /// Files present in both directories, by old path
+    pub files: Vec<FileDiff>,
+}
+
+impl DirectoryDiff {
+    /// Whether any file was added, removed, renamed or changed
+    pub fn has_changes(&self) -> bool {
+        !self.file_changes.is_empty() || self.files.iter().any(|f| f.diff.has_changes())
+    }
+}
+
+/// Compare the data files of two directories.
+///
+/// Files are paired by path relative to each directory; files in neither
+/// supported format are skipped. A removed and an added file are taken to be
+/// a rename if their bytes are equal, or failing that if they share most
+/// column names. Pairs are parsed and compared in parallel.
+pub fn diff_directories(
+    old_dir: &Path,
+    new_dir: &Path,
+    factory: &ParserFactory,
+    config: &Config,
+) -> Result<DirectoryDiff> {
+    let old_files = data_files(old_dir, factory)?;
+    let new_files = data_files(new_dir, factory)?;
+
+    let new_set: FxHashSet<&String> = new_files.iter().collect();
+    let old_set: FxHashSet<&String> = old_files.iter().collect();
+    let mut pairs: Vec<(String, String)> = old_files
+        .iter()
+        .filter(|path| new_set.contains(path))
+        .map(|path| (path.clone(), path.clone()))
+        .collect();
+    let mut removed: Vec<String> = old_files
+        .iter()
+        .filter(|p| !new_set.contains(p))
+        .cloned()
+        .collect();
+    let mut added: Vec<String> = new_files
+        .iter()
+        .filter(|p| !old_set.contains(p))
+        .cloned()
+        .collect();
+
+    let mut file_changes = Vec::new();
+    let mut renamed = |old_path: String, new_path: String, matched_by| {
+        file_changes.push(FileChange::Renamed {
+            old_path: old_path.clone(),
+            new_path: new_path.clone(),
+            matched_by,
+        });
+        pairs.push((old_path, new_path));
+    };
+
+    // Renames by content
+    let hashes = |dir: &Path, paths: &[String]| -> Result<Vec<u64>> {
+        paths
+            .par_iter()
+            .map(|path| content_hash(&dir.join(path)))
+            .collect()
+    };
+    let removed_hashes = hashes(old_dir, &removed)?;
+    let added_hashes = hashes(new_dir, &added)?;
+    let mut taken = vec![false; added.len()];
+    let mut unmatched = Vec::new();
+    for (old_path, hash) in removed.drain(..).zip(removed_hashes) {
+        let partner = (0..added.len()).find(|&i| !taken[i] && added_hashes[i] == hash);
+        match partner {
+            Some(i) => {
+                taken[i] = true;
+                renamed(old_path, added[i].clone(), RenameMatch::Content);
+            }
+            None => unmatched.push(old_path),
+        }
+    }
+    let mut remaining_added: Vec<String> = added
+        .drain(..)
+        .zip(taken)
+        .filter(|(_, taken)| !taken)
+        .map(|(path, _)| path)
+        .collect();
+    let mut removed = unmatched;
+
+    // Renames by schema, best matches first
+    let mut parsed: FxHashMap<PathBuf, Table> = FxHashMap::default();
+    if !removed.is_empty() && !remaining_added.is_empty() {
+        let parse_all = |dir: &Path, paths: &[String]| -> Result<Vec<Table>> {
+            paths
+                .par_iter()
+                .map(|path| parse(factory, &dir.join(path), config))
+                .collect()
+        };
+        let old_tables = parse_all(old_dir, &removed)?;
+        let new_tables = parse_all(new_dir, &remaining_added)?;
+
+        let mut candidates = Vec::new();
+        for (i, old_table) in old_tables.iter().enumerate() {
+            for (j, new_table) in new_tables.iter().enumerate() {
+                let score = column_similarity(old_table, new_table);
+                if score >= SCHEMA_SIMILARITY {
+                    candidates.push((score, i, j));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut old_taken = vec![false; removed.len()];
+        let mut new_taken = vec![false; remaining_added.len()];
+        for (_, i, j) in candidates {
+            if !old_taken[i] && !new_taken[j] {
+                old_taken[i] = true;
+                new_taken[j] = true;
+                renamed(
+                    removed[i].clone(),
+                    remaining_added[j].clone(),
+                    RenameMatch::Schema,
+                );
+            }
+        }
+
+        // Keep the parsed tables of renamed files for the diff
+        for ((path, table), taken) in removed.iter().zip(old_tables).zip(&old_taken) {
+            if *taken {
+                parsed.insert(old_dir.join(path), table);
+            }
+        }
+        for ((path, table), taken) in remaining_added.iter().zip(new_tables).zip(&new_taken) {
+            if *taken {
+                parsed.insert(new_dir.join(path), table);
+            }
+        }
+        removed = removed
+            .into_iter()
+            .zip(old_taken)
+            .filter_map(|(path, taken)| (!taken).then_some(path))
+            .collect();
+        remaining_added = remaining_added
+            .into_iter()
+            .zip(new_taken)
+            .filter_map(|(path, taken)| (!taken).then_some(path))
+            .collect();
+    }
+
+    file_changes.extend(removed.into_iter().map(|path| FileChange::Removed { path }));
+    file_changes.extend(
+        remaining_added
+            .into_iter()
+            .map(|path| FileChange::Added { path }),
+    );
+
+    pairs.sort();
+    let parsed = std::sync::Mutex::new(parsed);
+    let take = |path: PathBuf| -> Result<Table> {
+        let table = parsed.lock().expect("parsed tables lock").remove(&path);
+        match table {
+            Some(table) => Ok(table),
+            None => parse(factory, &path, config),
+        }
+    };
+    let files = pairs
+        .into_par_iter()
+        .map(|(old_path, new_path)| {
+            let old_table = take(old_dir.join(&old_path))?;
+            let new_table = take(new_dir.join(&new_path))?;
+            let (old_table, new_table, column_mapping) =
+                ParserFactory::prepare_pair(old_table, new_table, &config.key_columns, config);
+            let config = config.clone().with_column_mapping(column_mapping);
+            Ok(FileDiff {
+                diff: compute_diff(&old_table, &new_table, &config),
+                old_path,
+                new_path,
+                old_table,
+                new_table,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(DirectoryDiff {
+        file_changes,
+        files,
+    })
+}
+
+fn parse(factory: &ParserFactory, path: &Path, config: &Config) -> Result<Table> {
+    factory
+        .parse(path, &ParserFactory::parse_config(config))
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Relative paths of files any parser supports, sorted, with `/` separators
+fn data_files(dir: &Path, factory: &ParserFactory) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory: {}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if factory.is_data_file(&path) {
+                let relative = path.strip_prefix(dir).unwrap_or(&path);
+                let parts: Vec<_> = relative.iter().map(|p| p.to_string_lossy()).collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Shared column names as a share of all column names of both tables
+fn column_similarity(a: &Table, b: &Table) -> f64 {
+    let a: FxHashSet<&str> = a.columns.iter().map(|c| c.name.as_str()).collect();
+    let b: FxHashSet<&str> = b.columns.iter().map(|c| c.name.as_str()).collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs_and_renames() {
+        let old = tempfile::tempdir().unwrap();
+        let new = tempfile::tempdir().unwrap();
+        let write = |dir: &Path, path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(old.path(), "sales/q1.csv", "id,total\n1,10\n2,20\n");
+        write(new.path(), "sales/q1.csv", "id,total\n1,10\n2,25\n");
+        write(old.path(), "ref/regions.csv", "code,name\nEU,Europe\n");
+        write(new.path(), "ref/region_codes.csv", "code,name\nEU,Europe\n");
+        write(old.path(), "people.csv", "id,name,email\n1,Ana,a@x\n");
+        write(
+            new.path(),
+            "staff.csv",
+            "id,name,email,team\n1,Ana,a@x,Ops\n",
+        );
+        write(old.path(), "old_only.csv", "x\n1\n");
+        write(new.path(), "new_only.csv", "y,z\n1,2\n");
+        write(new.path(), "README.md", "not data");
+
+        let config = Config::default().with_key_columns(vec!["id".to_string()]);
+        let result =
+            diff_directories(old.path(), new.path(), &ParserFactory::new(), &config).unwrap();
+
+        let changes: Vec<String> = result.file_changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "~ ref/regions.csv → ref/region_codes.csv (renamed, same content)",
+                "~ people.csv → staff.csv (renamed, similar columns)",
+                "- old_only.csv (removed file)",
+                "+ new_only.csv (new file)",
+            ]
+        );
+        let files: Vec<(&str, bool)> = result
+            .files
+            .iter()
+            .map(|f| (f.old_path.as_str(), f.diff.has_changes()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("people.csv", true),
+                ("ref/regions.csv", false),
+                ("sales/q1.csv", true)
+            ]
+        );
+
+        // Keys name mapped columns by their old names
+        let config = Config::default()
+            .with_key_columns(vec!["id".to_string()])
+            .with_column_mapping(crate::diff::ColumnMapping {
+                pairs: vec![
+                    ("id".to_string(), "ref".to_string()),
+                    ("total".to_string(), "amount".to_string()),
+                ],
+                loose: false,
+            });
+        write(new.path(), "sales/q1.csv", "amount,ref\n25,2\n10,1\n");
+        let result =
+            diff_directories(old.path(), new.path(), &ParserFactory::new(), &config).unwrap();
+        let sales = result.files.iter().find(|f| f.old_path == "sales/q1.csv");
+        let stats = &sales.unwrap().diff.stats;
+        assert_eq!(
+            (stats.rows_added, stats.rows_removed, stats.rows_modified),
+            (0, 0, 1)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Changes to Excel cell formatting, comments, validation and visibility
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::parser::{CellStyle, SheetFormat};
+
+/// What about a cell or range changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatProperty {
+    NumberFormat,
+    Font,
+    Fill,
+    Comment,
+    Validation,
+    Hidden,
+}
+
+impl fmt::Display for FormatProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FormatProperty::NumberFormat => "number format",
+            FormatProperty::Font => "font",
+            FormatProperty::Fill => "fill",
+            FormatProperty::Comment => "comment",
+            FormatProperty::Validation => "validation",
+            FormatProperty::Hidden => "hidden",
+        })
+    }
+}
+
+/// A formatting change at a sheet position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FormatChange {
+    /// Cell (`B2`), range (`B2:B9`), rows (`rows 4:6`) or columns (`columns C:D`)
+    pub location: String,
+    pub property: FormatProperty,
+    /// None if the old sheet had none (no comment, no validation)
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl fmt::Display for FormatChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "(none)".to_string());
+        write!(
+            f,
+            "{} {}: {} → {}",
+            self.location,
+            self.property,
+            show(&self.old_value),
+            show(&self.new_value)
+        )
+    }
+}
+
+/// Compare the formatting of two sheets cell by cell, by position: cell B2
+/// of the old sheet is compared with B2 of the new one
+pub fn diff_formats(old: &SheetFormat, new: &SheetFormat) -> Vec<FormatChange> {
+    // Cell changes sorted by position, then property
+    let mut cell_changes: Vec<((u32, u32), FormatChange)> = Vec::new();
+    let mut push = |pos: (u32, u32), property, old_value: Option<&str>, new_value: Option<&str>| {
+        if old_value != new_value {
+            cell_changes.push((
+                pos,
+                FormatChange {
+                    location: cell_name(pos),
+                    property,
+                    old_value: old_value.map(str::to_string),
+                    new_value: new_value.map(str::to_string),
+                },
+            ));
+        }
+    };
+
+    let styled: BTreeSet<&(u32, u32)> = old.styles.keys().chain(new.styles.keys()).collect();
+    for &pos in styled {
+        let (a, b): (&CellStyle, &CellStyle) = (old.style(pos), new.style(pos));
+        push(
+            pos,
+            FormatProperty::NumberFormat,
+            Some(&a.number_format),
+            Some(&b.number_format),
+        );
+        push(pos, FormatProperty::Font, Some(&a.font), Some(&b.font));
+        push(pos, FormatProperty::Fill, Some(&a.fill), Some(&b.fill));
+    }
+    let commented: BTreeSet<&(u32, u32)> = old.comments.keys().chain(new.comments.keys()).collect();
+    for &pos in commented {
+        push(
+            pos,
+            FormatProperty::Comment,
+            old.comments.get(&pos).map(String::as_str),
+            new.comments.get(&pos).map(String::as_str),
+        );
+    }
+    cell_changes.sort_by_key(|(pos, change)| (*pos, change.property));
+    let mut changes: Vec<FormatChange> =
+        cell_changes.into_iter().map(|(_, change)| change).collect();
+
+    let ranges: BTreeSet<&String> = old
+        .validations
+        .keys()
+        .chain(new.validations.keys())
+        .collect();
+    for range in ranges {
+        let (a, b) = (old.validations.get(range), new.validations.get(range));
+        if a != b {
+            changes.push(FormatChange {
+                location: range.clone(),
+                property: FormatProperty::Validation,
+                old_value: a.cloned(),
+                new_value: b.cloned(),
+            });
+        }
+    }
+
+    changes.extend(visibility_changes(
+        &old.hidden_rows,
+        &new.hidden_rows,
+        "row",
+        |row| (row + 1).to_string(),
+    ));
+    changes.extend(visibility_changes(
+        &old.hidden_columns,
+        &new.hidden_columns,
+        "column",
+        column_name,
+    ));
+    changes
+}
+
+/// Rows or columns hidden or shown, with runs of neighbours merged
+fn visibility_changes(
+    old: &BTreeSet<u32>,
+    new: &BTreeSet<u32>,
+    noun: &str,
+    name: impl Fn(u32) -> String,
+) -> Vec<FormatChange> {
+    let mut changes = Vec::new();
+    for (flipped, now_hidden) in [(new.difference(old), true), (old.difference(new), false)] {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for &index in flipped {
+            match runs.last_mut() {
+                Some(run) if run.1 + 1 == index => run.1 = index,
+                _ => runs.push((index, index)),
+            }
+        }
+        for (first, last) in runs {
+            let location = if first == last {
+                format!("{} {}", noun, name(first))
+            } else {
+                format!("{}s {}:{}", noun, name(first), name(last))
+            };
+            let (old_value, new_value) = if now_hidden {
+                ("visible", "hidden")
+            } else {
+                ("hidden", "visible")
+            };
+            changes.push(FormatChange {
+                location,
+                property: FormatProperty::Hidden,
+                old_value: Some(old_value.to_string()),
+                new_value: Some(new_value.to_string()),
+            });
+        }
+    }
+    changes
+}
+
+/// Spreadsheet name of a 0-based column: 0 is `A`, 26 is `AA`
+fn column_name(col: u32) -> String {
+    let mut name = Vec::new();
+    let mut n = col + 1;
+    while n > 0 {
+        name.push(b'A' + ((n - 1) % 26) as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// A1 name of a 0-based (row, column) position
+fn cell_name((row, col): (u32, u32)) -> String {
+    format!("{}{}", column_name(col), row + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_formats() {
+        let style = |fill: &str| CellStyle {
+            number_format: "General".to_string(),
+            font: "Calibri 11".to_string(),
+            fill: fill.to_string(),
+        };
+        let mut old = SheetFormat {
+            default_style: style("none"),
+            ..Default::default()
+        };
+        let mut new = old.clone();
+        new.styles.insert((1, 27), style("solid #FFFF00"));
+        old.comments.insert((0, 0), "check".to_string());
+        old.hidden_columns.extend([2, 3, 4]);
+        new.hidden_columns.insert(3);
+        new.hidden_rows.insert(9);
+
+        let changes: Vec<String> = diff_formats(&old, &new)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "A1 comment: check → (none)",
+                "AB2 fill: none → solid #FFFF00",
+                "row 10 hidden: visible → hidden",
+                "column C hidden: hidden → visible",
+                "column E hidden: hidden → visible",
+            ]
+        );
+    }
+}
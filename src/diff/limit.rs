@@ -0,0 +1,238 @@
+//! Limiting and sampling the row changes shown in output
+//!
+//! Limits only drop entries from `row_changes`; `stats` keeps the full
+//! counts, so formatters can report how many changes were left out.
+
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::FxHasher;
+use serde::Serialize;
+
+use crate::model::CellValue;
+
+use super::{CellChange, DiffResult, RowChange};
+
+/// Which row changes to show
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Row changes to show in total
+    pub max_rows: Option<usize>,
+    /// Row changes to show in each section (added, removed, modified)
+    pub max_per_section: Option<usize>,
+    /// Show a random sample of this many row changes
+    pub sample: Option<usize>,
+    /// Sampling seed; a seed picks the same rows on every run
+    pub seed: u64,
+    /// Show only this many modified rows, largest numeric change first
+    pub top: Option<usize>,
+}
+
+impl OutputLimits {
+    /// Whether all row changes are shown
+    pub fn is_unlimited(&self) -> bool {
+        self.max_rows.is_none()
+            && self.max_per_section.is_none()
+            && self.sample.is_none()
+            && self.top.is_none()
+    }
+}
+
+/// Row changes counted in the stats but left out of `row_changes`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OmittedRows {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+}
+
+impl OmittedRows {
+    pub fn total(&self) -> usize {
+        self.added + self.removed + self.modified
+    }
+}
+
+/// Drop row changes beyond the limits.
+///
+/// Applied in order: the top-N view keeps only the modified rows with the
+/// largest numeric changes, then the sample, the per-section
+/// limit and the total limit. Rows otherwise keep their order.
+pub fn apply_limits(diff: &mut DiffResult, limits: &OutputLimits) {
+    if limits.is_unlimited() {
+        return;
+    }
+    let mut changes = std::mem::take(&mut diff.row_changes);
+
+    if let Some(top) = limits.top {
+        let mut ranked: Vec<(f64, usize)> = changes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, change)| match change {
+                RowChange::Modified { changes, .. } => Some((magnitude(changes)?, i)),
+                _ => None,
+            })
+            .collect();
+        // Stable, so equal magnitudes stay in file order
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut keep = vec![false; changes.len()];
+        for &(_, i) in ranked.iter().take(top) {
+            keep[i] = true;
+        }
+        let mut keep = keep.into_iter();
+        changes.retain(|c| {
+            let kept = keep.next().unwrap_or(false);
+            kept || !matches!(c, RowChange::Modified { .. })
+        });
+    }
+
+    if let Some(sample) = limits.sample {
+        if sample < changes.len() {
+            // Ties between equal ranks are broken by position
+            let mut ranked: Vec<(u64, usize)> = changes
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (sample_rank(c, limits.seed), i))
+                .collect();
+            ranked.select_nth_unstable(sample);
+            let mut keep = vec![false; changes.len()];
+            for &(_, i) in &ranked[..sample] {
+                keep[i] = true;
+            }
+            let mut keep = keep.into_iter();
+            changes.retain(|_| keep.next().unwrap_or(false));
+        }
+    }
+
+    if let Some(max) = limits.max_per_section {
+        let mut counts = [0usize; 3];
+        changes.retain(|c| {
+            let count = &mut counts[section(c)];
+            *count += 1;
+            *count <= max
+        });
+    }
+
+    if let Some(max) = limits.max_rows {
+        changes.truncate(max);
+    }
+
+    diff.row_changes = changes;
+}
+
+fn section(change: &RowChange) -> usize {
+    match change {
+        RowChange::Added { .. } => 0,
+        RowChange::Removed { .. } => 1,
+        RowChange::Modified { .. } => 2,
+    }
+}
+
+/// Largest absolute numeric change in a row, if any cell changed numerically
+fn magnitude(changes: &[CellChange]) -> Option<f64> {
+    changes
+        .iter()
+        .filter_map(|c| Some((number(&c.new_value)? - number(&c.old_value)?).abs()))
+        .filter(|m| !m.is_nan())
+        .max_by(f64::total_cmp)
+}
+
+fn number(value: &CellValue) -> Option<f64> {
+    match value {
+        CellValue::Int(i) => Some(*i as f64),
+        CellValue::Float(f) => Some(*f),
+        CellValue::Decimal(d) => Some(d.to_f64()),
+        _ => None,
+    }
+}
+
+/// Pseudo-random rank of a change, from its type and key
+fn sample_rank(change: &RowChange, seed: u64) -> u64 {
+    let mut hasher = FxHasher::default();
+    section(change).hash(&mut hasher);
+    change.key().hash(&mut hasher);
+    // SplitMix64 finaliser spreads the hash evenly over the range
+    let mut x = hasher.finish() ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::{Column, Table};
+
+    fn table(rows: &[(i64, i64)]) -> Table {
+        let mut table = Table::new(vec![Column::new("id", 0), Column::new("amount", 1)]);
+        table.set_key_columns(&["id".to_string()]);
+        for (i, (id, amount)) in rows.iter().enumerate() {
+            table.add_row(vec![CellValue::Int(*id), CellValue::Int(*amount)], i + 2);
+        }
+        table
+    }
+
+    fn diff() -> DiffResult {
+        let old = table(&(1..=20).map(|i| (i, 100)).collect::<Vec<_>>());
+        let mut rows: Vec<(i64, i64)> = (3..=20).map(|i| (i, 100 + i % 7)).collect();
+        rows.extend((21..=25).map(|i| (i, 0)));
+        compute_diff(&old, &table(&rows), &Config::default())
+    }
+
+    fn keys(diff: &DiffResult) -> Vec<&str> {
+        diff.row_changes.iter().map(|c| c.key()).collect()
+    }
+
+    #[test]
+    fn test_limits_keep_stats() {
+        let mut limited = diff();
+        let stats = limited.stats.clone();
+        apply_limits(
+            &mut limited,
+            &OutputLimits {
+                max_per_section: Some(2),
+                max_rows: Some(5),
+                ..Default::default()
+            },
+        );
+        assert_eq!(limited.row_changes.len(), 5);
+        assert_eq!(limited.stats.rows_modified, stats.rows_modified);
+        let omitted = limited.omitted();
+        assert_eq!(omitted.total(), stats.rows_added + stats.rows_removed + stats.rows_modified - 5);
+    }
+
+    #[test]
+    fn test_top_changes_by_magnitude() {
+        let mut limited = diff();
+        apply_limits(&mut limited, &OutputLimits { top: Some(3), ..Default::default() });
+        let modified: Vec<&str> = limited
+            .row_changes
+            .iter()
+            .filter(|c| matches!(c, RowChange::Modified { .. }))
+            .map(|c| c.key())
+            .collect();
+        // i % 7 == 6 gives the largest change; ties keep file order
+        assert_eq!(modified, vec!["6", "13", "20"]);
+
+        // The kept rows stay where they were among the added and removed rows
+        let mut expected = diff();
+        expected.row_changes.retain(|c| {
+            !matches!(c, RowChange::Modified { .. }) || modified.contains(&c.key())
+        });
+        assert_eq!(keys(&limited), keys(&expected));
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic() {
+        let limits = OutputLimits { sample: Some(4), seed: 7, ..Default::default() };
+        let (mut a, mut b) = (diff(), diff());
+        apply_limits(&mut a, &limits);
+        apply_limits(&mut b, &limits);
+        assert_eq!(keys(&a).len(), 4);
+        assert_eq!(keys(&a), keys(&b));
+
+        let mut c = diff();
+        apply_limits(&mut c, &OutputLimits { seed: 8, ..limits });
+        assert_eq!(keys(&c).len(), 4);
+    }
+}
@@ -1,17 +1,33 @@
 //! Diff engine for comparing tables
 
 pub mod cell_diff;
+mod column_map;
+mod directory;
+mod format_diff;
+mod limit;
+mod partition;
 mod row_diff;
 mod schema_diff;
+mod text_diff;
+mod workbook;
 
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
-use crate::model::{CellValue, Row, Table};
+use crate::config::{Config, InlineDiff};
+use crate::model::{CellType, CellValue, Row, Table};
+use crate::normalize::{normalize, Transform};
 
-pub use cell_diff::CellComparator;
+pub use cell_diff::{CellComparator, NestedChange};
+pub use column_map::ColumnMapping;
+pub use directory::{diff_directories, DirectoryDiff, FileChange, FileDiff, RenameMatch};
+pub use format_diff::{diff_formats, FormatChange, FormatProperty};
+pub use limit::{apply_limits, OmittedRows, OutputLimits};
+pub use partition::{diff_partitions, PartitionChange, PartitionDiff, PartitionStatus};
 pub use row_diff::RowMatcher;
 pub use schema_diff::{SchemaChange, SchemaDiff};
+pub use text_diff::{diff_text, SpanKind, TextSpan};
+pub use workbook::{diff_workbooks, SheetChange, SheetDiff, WorkbookDiff};
 
 /// A change to a single cell
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,12 +36,27 @@ pub struct CellChange {
     pub column: String,
     /// Column index
     pub column_index: usize,
+    /// Path inside a nested cell (e.g. `address.city`), if the change is nested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
     /// Old value
     pub old_value: CellValue,
     /// New value
     pub new_value: CellValue,
 }
 
+impl CellChange {
+    /// Name to show for this change: the nested path if any, else the column
+    pub fn label(&self) -> &str {
+        self.path.as_deref().unwrap_or(&self.column)
+    }
+
+    /// Inline diff of the old and new values, for related string changes
+    pub fn inline_diff(&self, mode: InlineDiff) -> Option<Vec<TextSpan>> {
+        text_diff::inline_diff(&self.old_value, &self.new_value, mode)
+    }
+}
+
 /// A change to a row
 #[derive(Debug)]
 pub enum RowChange {
@@ -70,6 +101,14 @@ impl DiffStats {
     pub fn has_changes(&self) -> bool {
         self.rows_added > 0 || self.rows_removed > 0 || self.rows_modified > 0
     }
+
+    /// Count rows known to be the same on both sides without comparing
+    /// them, such as those of skipped partitions
+    pub fn add_unchanged(&mut self, rows: usize) {
+        self.old_row_count += rows;
+        self.new_row_count += rows;
+        self.rows_unchanged += rows;
+    }
 }
 
 /// Result of comparing two tables
@@ -81,6 +120,10 @@ pub struct DiffResult {
     pub row_changes: Vec<RowChange>,
     /// Statistics
     pub stats: DiffStats,
+    /// Excel formatting changes, if formatting was compared
+    pub format_changes: Vec<FormatChange>,
+    /// Changed partitions, if partitioned datasets were compared
+    pub partitions: Option<PartitionDiff>,
 }
 
 impl DiffResult {
@@ -90,12 +133,34 @@ impl DiffResult {
             schema_changes: Vec::new(),
             row_changes: Vec::new(),
             stats: DiffStats::default(),
+            format_changes: Vec::new(),
+            partitions: None,
         }
     }
 
     /// Check if there are any changes
     pub fn has_changes(&self) -> bool {
-        !self.schema_changes.is_empty() || self.stats.has_changes()
+        !self.schema_changes.is_empty()
+            || self.stats.has_changes()
+            || !self.format_changes.is_empty()
+    }
+
+    /// Row changes counted in the stats but not listed, because output
+    /// limits dropped them
+    pub fn omitted(&self) -> OmittedRows {
+        let mut listed = [0usize; 3];
+        for change in &self.row_changes {
+            match change {
+                RowChange::Added { .. } => listed[0] += 1,
+                RowChange::Removed { .. } => listed[1] += 1,
+                RowChange::Modified { .. } => listed[2] += 1,
+            }
+        }
+        OmittedRows {
+            added: self.stats.rows_added.saturating_sub(listed[0]),
+            removed: self.stats.rows_removed.saturating_sub(listed[1]),
+            modified: self.stats.rows_modified.saturating_sub(listed[2]),
+        }
     }
 
     /// Get only added rows
@@ -134,6 +199,38 @@ impl Default for DiffResult {
     }
 }
 
+/// How the cells of a column pair are prepared before comparison
+struct ColumnRules<'a> {
+    /// Normalisation steps, applied to both sides first
+    steps: Vec<&'a Transform>,
+    /// Shared type to cast both sides to (Mixed: decide per value)
+    coerce_to: Option<CellType>,
+}
+
+impl ColumnRules<'_> {
+    fn is_empty(&self) -> bool {
+        self.steps.is_empty() && self.coerce_to.is_none()
+    }
+
+    fn prepare(&self, old_value: &CellValue, new_value: &CellValue) -> (CellValue, CellValue) {
+        let (old_value, new_value) = (
+            normalize(old_value, &self.steps),
+            normalize(new_value, &self.steps),
+        );
+        let target = match self.coerce_to {
+            None => return (old_value, new_value),
+            Some(CellType::Mixed) => old_value.cell_type().common(new_value.cell_type()),
+            Some(t) => t,
+        };
+        if target == CellType::Mixed {
+            return (old_value, new_value);
+        }
+        // Values that don't convert are compared as they are
+        let cast = |v: CellValue| if v.is_null() { v } else { v.cast(target).unwrap_or(v) };
+        (cast(old_value), cast(new_value))
+    }
+}
+
 /// Main diff engine
 pub struct DiffEngine {
     config: Config,
@@ -147,7 +244,8 @@ impl DiffEngine {
             config.ignore_case,
             config.ignore_whitespace,
             config.numeric_tolerance,
-        );
+        )
+        .with_unordered_lists(config.unordered_lists);
         Self {
             config,
             cell_comparator,
@@ -163,23 +261,46 @@ impl DiffEngine {
         result.stats.new_row_count = new_table.row_count();
 
         // Compare schemas
-        result.schema_changes = SchemaDiff::compare(old_table, new_table);
+        let matched_columns = self.config.column_mapping.resolve(old_table, new_table);
+        result.schema_changes = SchemaDiff::compare(old_table, new_table, &matched_columns);
 
         // Match rows
         let row_matcher = RowMatcher::new(&self.config.ignore_columns);
         let matches = row_matcher.match_rows(old_table, new_table);
 
-        // Process matches
-        for (old_row_opt, new_row_opt) in matches {
-            match (old_row_opt, new_row_opt) {
+        // Compare matched row pairs in parallel; collecting keeps match order
+        let column_pairs = self.column_pairs(old_table, &matched_columns);
+        let common_types = SchemaDiff::common_types(old_table, new_table, &column_pairs);
+        let rules: Vec<ColumnRules> = column_pairs
+            .iter()
+            .zip(common_types)
+            .map(|(&(old_idx, _), common_type)| ColumnRules {
+                steps: self
+                    .config
+                    .normalization_for(&old_table.columns[old_idx].name),
+                coerce_to: self.config.coerce_types.then_some(common_type),
+            })
+            .collect();
+        let cell_changes: Vec<Option<Vec<CellChange>>> = matches
+            .par_iter()
+            .map(|pair| match pair {
                 (Some(old_row), Some(new_row)) => {
+                    Some(self.compare_row_cells(old_row, new_row, &column_pairs, &rules, old_table))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Process matches
+        for ((old_row_opt, new_row_opt), changes) in matches.into_iter().zip(cell_changes) {
+            match (old_row_opt, new_row_opt, changes) {
+                (Some(old_row), Some(new_row), Some(changes)) => {
                     // Check if row is modified
-                    let changes = self.compare_row_cells(old_row, new_row, old_table, new_table);
                     if !changes.is_empty() {
                         result.stats.rows_modified += 1;
                         result.stats.cells_changed += changes.len();
                         result.row_changes.push(RowChange::Modified {
-                            key: old_row.key.clone(),
+                            key: old_row.key.to_string(),
                             old_row: old_row.clone(),
                             new_row: new_row.clone(),
                             changes,
@@ -188,66 +309,92 @@ impl DiffEngine {
                         result.stats.rows_unchanged += 1;
                     }
                 }
-                (Some(old_row), None) => {
+                (Some(old_row), None, _) => {
                     result.stats.rows_removed += 1;
                     result.row_changes.push(RowChange::Removed {
-                        key: old_row.key.clone(),
+                        key: old_row.key.to_string(),
                         row: old_row.clone(),
                     });
                 }
-                (None, Some(new_row)) => {
+                (None, Some(new_row), _) => {
                     result.stats.rows_added += 1;
                     result.row_changes.push(RowChange::Added {
-                        key: new_row.key.clone(),
+                        key: new_row.key.to_string(),
                         row: new_row.clone(),
                     });
                 }
-                (None, None) => unreachable!(),
+                _ => unreachable!(),
             }
         }
 
         result
     }
 
-    /// Compare cells between two rows
+    /// Matched (old index, new index) column pairs to compare, skipping
+    /// ignored columns
+    fn column_pairs(&self, old_table: &Table, matched: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        matched
+            .iter()
+            .copied()
+            .filter(|&(old_idx, _)| {
+                !self
+                    .config
+                    .ignore_columns
+                    .contains(&old_table.columns[old_idx].name)
+            })
+            .collect()
+    }
+
+    /// Compare cells between two rows; values are compared after the
+    /// column's rules are applied, but reported as they were
     fn compare_row_cells(
         &self,
         old_row: &Row,
         new_row: &Row,
+        column_pairs: &[(usize, usize)],
+        rules: &[ColumnRules],
         old_table: &Table,
-        new_table: &Table,
     ) -> Vec<CellChange> {
         let mut changes = Vec::new();
 
-        // Build column name mapping for both tables
-        let old_columns: Vec<_> = old_table.columns.iter().map(|c| &c.name).collect();
-        let new_columns: Vec<_> = new_table.columns.iter().map(|c| &c.name).collect();
-
-        // Compare columns that exist in both tables
-        for (old_idx, old_col_name) in old_columns.iter().enumerate() {
-            // Skip ignored columns
-            if self.config.ignore_columns.contains(*old_col_name) {
-                continue;
-            }
+        for (&(old_idx, new_idx), rules) in column_pairs.iter().zip(rules) {
+            let old_col_name = &old_table.columns[old_idx].name;
+            let old_value = old_row.cells.get(old_idx).cloned().unwrap_or(CellValue::Null);
+            let new_value = new_row.cells.get(new_idx).cloned().unwrap_or(CellValue::Null);
 
-            // Find matching column in new table
-            if let Some(new_idx) = new_columns.iter().position(|n| n == old_col_name) {
-                let old_value = old_row.cells.get(old_idx).cloned().unwrap_or(CellValue::Null);
-                let new_value = new_row.cells.get(new_idx).cloned().unwrap_or(CellValue::Null);
-
-                if !self.cell_comparator.equal(&old_value, &new_value) {
-                    changes.push(CellChange {
-                        column: (*old_col_name).clone(),
-                        column_index: old_idx,
-                        old_value,
-                        new_value,
-                    });
-                }
+            if old_value.is_nested() && new_value.is_nested() {
+                // Report each differing leaf with its path
+                let nested = self
+                    .cell_comparator
+                    .diff_nested(old_col_name, &old_value, &new_value);
+                changes.extend(nested.into_iter().map(|n| CellChange {
+                    column: old_col_name.clone(),
+                    column_index: old_idx,
+                    path: Some(n.path),
+                    old_value: n.old_value,
+                    new_value: n.new_value,
+                }));
+            } else if !self.values_equal(&old_value, &new_value, rules) {
+                changes.push(CellChange {
+                    column: old_col_name.clone(),
+                    column_index: old_idx,
+                    path: None,
+                    old_value,
+                    new_value,
+                });
             }
         }
 
         changes
     }
+
+    fn values_equal(&self, old_value: &CellValue, new_value: &CellValue, rules: &ColumnRules) -> bool {
+        if rules.is_empty() {
+            return self.cell_comparator.equal(old_value, new_value);
+        }
+        let (old_value, new_value) = rules.prepare(old_value, new_value);
+        self.cell_comparator.equal(&old_value, &new_value)
+    }
 }
 
 /// Convenience function to compute diff
@@ -0,0 +1,185 @@
+//! Which partitions of a partitioned dataset hold changes
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::model::{Row, Table};
+use crate::parser::partition_name;
+
+use super::{DiffResult, RowChange};
+
+/// Whether a partition is in both datasets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionStatus {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Row changes in one partition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PartitionChange {
+    /// Partition path, e.g. `dt=2024-01-01/region=eu`
+    pub partition: String,
+    pub status: PartitionStatus,
+    pub rows_added: usize,
+    pub rows_removed: usize,
+    pub rows_modified: usize,
+}
+
+impl fmt::Display for PartitionChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: +{} added, -{} removed, ~{} modified",
+            self.partition, self.rows_added, self.rows_removed, self.rows_modified
+        )?;
+        match self.status {
+            PartitionStatus::Added => write!(f, " (new partition)"),
+            PartitionStatus::Removed => write!(f, " (removed partition)"),
+            PartitionStatus::Changed => Ok(()),
+        }
+    }
+}
+
+/// Partitions of two datasets that changed, and how many were skipped
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PartitionDiff {
+    pub changes: Vec<PartitionChange>,
+    /// Partitions whose files were identical, and so were not compared
+    pub unchanged: usize,
+}
+
+/// Attribute the row changes of a dataset diff to partitions.
+///
+/// `columns` are the partition columns; a modified row that moved between
+/// partitions counts in both. `old_partitions` and `new_partitions` name the
+/// partitions each side holds, so partitions with no rows can be reported.
+pub fn diff_partitions(
+    diff: &DiffResult,
+    old_table: &Table,
+    new_table: &Table,
+    columns: &[String],
+    old_partitions: &BTreeSet<String>,
+    new_partitions: &BTreeSet<String>,
+) -> Vec<PartitionChange> {
+    let old_indices = partition_indices(old_table, columns);
+    let new_indices = partition_indices(new_table, columns);
+    let mut counts: BTreeMap<String, [usize; 3]> = BTreeMap::new();
+    let mut count = |row: &Row, indices: &Option<Vec<(&str, usize)>>, slot: usize| {
+        if let Some(name) = row_partition(row, indices) {
+            counts.entry(name).or_default()[slot] += 1;
+        }
+    };
+    for change in &diff.row_changes {
+        match change {
+            RowChange::Added { row, .. } => count(row, &new_indices, 0),
+            RowChange::Removed { row, .. } => count(row, &old_indices, 1),
+            RowChange::Modified {
+                old_row, new_row, ..
+            } => {
+                let old_name = row_partition(old_row, &old_indices);
+                count(old_row, &old_indices, 2);
+                if row_partition(new_row, &new_indices) != old_name {
+                    count(new_row, &new_indices, 2);
+                }
+            }
+        }
+    }
+    for name in old_partitions.symmetric_difference(new_partitions) {
+        counts.entry(name.clone()).or_default();
+    }
+
+    counts
+        .into_iter()
+        .map(|(partition, [added, removed, modified])| PartitionChange {
+            status: match (
+                old_partitions.contains(&partition),
+                new_partitions.contains(&partition),
+            ) {
+                (false, true) => PartitionStatus::Added,
+                (true, false) => PartitionStatus::Removed,
+                _ => PartitionStatus::Changed,
+            },
+            partition,
+            rows_added: added,
+            rows_removed: removed,
+            rows_modified: modified,
+        })
+        .collect()
+}
+
+/// Positions of the partition columns, or None if a column was dropped
+fn partition_indices<'a>(table: &Table, columns: &'a [String]) -> Option<Vec<(&'a str, usize)>> {
+    columns
+        .iter()
+        .map(|name| Some((name.as_str(), table.column_index(name)?)))
+        .collect()
+}
+
+fn row_partition(row: &Row, indices: &Option<Vec<(&str, usize)>>) -> Option<String> {
+    let indices = indices.as_ref()?;
+    let values: Vec<(&str, &_)> = indices
+        .iter()
+        .map(|&(name, i)| Some((name, row.get(i)?)))
+        .collect::<Option<_>>()?;
+    Some(partition_name(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::{CellValue, Column};
+
+    #[test]
+    fn test_diff_partitions() {
+        let table = |rows: &[(i64, &str, i64)]| {
+            let mut table = Table::new(vec![
+                Column::new("id", 0),
+                Column::new("region", 1),
+                Column::new("total", 2),
+            ]);
+            table.set_key_columns(&["id".to_string()]);
+            for &(id, region, total) in rows {
+                table.add_row(
+                    vec![
+                        CellValue::Int(id),
+                        CellValue::String(region.to_string().into()),
+                        CellValue::Int(total),
+                    ],
+                    0,
+                );
+            }
+            table
+        };
+        let old = table(&[(1, "eu", 10), (2, "eu", 20), (3, "us", 30)]);
+        let new = table(&[(1, "eu", 11), (2, "us", 20), (4, "apac", 40)]);
+        let diff = compute_diff(&old, &new, &Config::default());
+        let set = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+
+        let changes: Vec<String> = diff_partitions(
+            &diff,
+            &old,
+            &new,
+            &["region".to_string()],
+            &set(&["region=eu", "region=us"]),
+            &set(&["region=apac", "region=eu", "region=us"]),
+        )
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "region=apac: +1 added, -0 removed, ~0 modified (new partition)",
+                "region=eu: +0 added, -0 removed, ~2 modified",
+                "region=us: +0 added, -1 removed, ~1 modified",
+            ]
+        );
+    }
+}
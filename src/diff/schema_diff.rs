@@ -2,7 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::model::Table;
+use crate::model::{CellType, Table};
 
 /// Types of schema changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,7 +11,7 @@ pub enum SchemaChange {
     ColumnAdded { name: String, index: usize },
     /// Column was removed
     ColumnRemoved { name: String, index: usize },
-    /// Column was renamed (detected by position if types match)
+    /// Column was matched to a column with a different name
     ColumnRenamed {
         old_name: String,
         new_name: String,
@@ -69,59 +69,90 @@ impl std::fmt::Display for SchemaChange {
 pub struct SchemaDiff;
 
 impl SchemaDiff {
-    /// Compare schemas of two tables
-    pub fn compare(old_table: &Table, new_table: &Table) -> Vec<SchemaChange> {
+    /// Compare schemas of two tables, given the (old index, new index)
+    /// pairs of matching columns
+    pub fn compare(
+        old_table: &Table,
+        new_table: &Table,
+        column_pairs: &[(usize, usize)],
+    ) -> Vec<SchemaChange> {
         let mut changes = Vec::new();
 
-        let old_names: Vec<_> = old_table.columns.iter().map(|c| &c.name).collect();
-        let new_names: Vec<_> = new_table.columns.iter().map(|c| &c.name).collect();
+        let mut old_matched = vec![false; old_table.columns.len()];
+        let mut new_matched = vec![false; new_table.columns.len()];
+        for &(old_idx, new_idx) in column_pairs {
+            old_matched[old_idx] = true;
+            new_matched[new_idx] = true;
+        }
 
         // Find removed columns
-        for (old_idx, old_name) in old_names.iter().enumerate() {
-            if !new_names.contains(old_name) {
+        for (old_idx, column) in old_table.columns.iter().enumerate() {
+            if !old_matched[old_idx] {
                 changes.push(SchemaChange::ColumnRemoved {
-                    name: (*old_name).clone(),
+                    name: column.name.clone(),
                     index: old_idx,
                 });
             }
         }
 
         // Find added columns
-        for (new_idx, new_name) in new_names.iter().enumerate() {
-            if !old_names.contains(new_name) {
+        for (new_idx, column) in new_table.columns.iter().enumerate() {
+            if !new_matched[new_idx] {
                 changes.push(SchemaChange::ColumnAdded {
-                    name: (*new_name).clone(),
+                    name: column.name.clone(),
                     index: new_idx,
                 });
             }
         }
 
-        // Find moved columns
-        for (old_idx, old_name) in old_names.iter().enumerate() {
-            if let Some(new_idx) = new_names.iter().position(|n| n == old_name) {
-                if old_idx != new_idx {
-                    changes.push(SchemaChange::ColumnMoved {
-                        name: (*old_name).clone(),
-                        from_index: old_idx,
-                        to_index: new_idx,
-                    });
-                }
+        // Find renamed (mapped) and moved columns
+        for &(old_idx, new_idx) in column_pairs {
+            let (old_col, new_col) = (&old_table.columns[old_idx], &new_table.columns[new_idx]);
+            if old_col.name != new_col.name {
+                changes.push(SchemaChange::ColumnRenamed {
+                    old_name: old_col.name.clone(),
+                    new_name: new_col.name.clone(),
+                    index: new_idx,
+                });
+            }
+            if old_idx != new_idx {
+                changes.push(SchemaChange::ColumnMoved {
+                    name: old_col.name.clone(),
+                    from_index: old_idx,
+                    to_index: new_idx,
+                });
             }
         }
 
         // Find type changes
-        for old_col in &old_table.columns {
-            if let Some(new_col) = new_table.columns.iter().find(|c| c.name == old_col.name) {
-                if old_col.inferred_type != new_col.inferred_type {
-                    changes.push(SchemaChange::ColumnTypeChanged {
-                        name: old_col.name.clone(),
-                        old_type: old_col.inferred_type.to_string(),
-                        new_type: new_col.inferred_type.to_string(),
-                    });
-                }
+        for &(old_idx, new_idx) in column_pairs {
+            let (old_col, new_col) = (&old_table.columns[old_idx], &new_table.columns[new_idx]);
+            if old_col.inferred_type != new_col.inferred_type {
+                changes.push(SchemaChange::ColumnTypeChanged {
+                    name: old_col.name.clone(),
+                    old_type: old_col.inferred_type.to_string(),
+                    new_type: new_col.inferred_type.to_string(),
+                });
             }
         }
 
         changes
     }
+
+    /// Type each (old, new) column pair is cast to when coercing types;
+    /// Mixed where the column types have nothing in common
+    pub fn common_types(
+        old_table: &Table,
+        new_table: &Table,
+        column_pairs: &[(usize, usize)],
+    ) -> Vec<CellType> {
+        column_pairs
+            .iter()
+            .map(|&(old_idx, new_idx)| {
+                old_table.columns[old_idx]
+                    .inferred_type
+                    .common(new_table.columns[new_idx].inferred_type)
+            })
+            .collect()
+    }
 }
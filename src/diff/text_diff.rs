@@ -0,0 +1,104 @@
+//! Inline diffs of changed string values
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::config::InlineDiff;
+use crate::model::CellValue;
+
+/// Whether a span of text is shared, deleted or inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A run of text in an inline diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub kind: SpanKind,
+    pub text: String,
+}
+
+/// Diff two strings into spans; adjacent tokens of the same kind are merged
+pub fn diff_text(old: &str, new: &str, mode: InlineDiff) -> Vec<TextSpan> {
+    let diff = match mode {
+        InlineDiff::Char => TextDiff::from_chars(old, new),
+        _ => TextDiff::from_words(old, new),
+    };
+
+    let mut spans: Vec<TextSpan> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => SpanKind::Equal,
+            ChangeTag::Delete => SpanKind::Delete,
+            ChangeTag::Insert => SpanKind::Insert,
+        };
+        match spans.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(change.value()),
+            _ => spans.push(TextSpan {
+                kind,
+                text: change.value().to_string(),
+            }),
+        }
+    }
+    spans
+}
+
+/// Inline diff of a changed cell, if both values are strings that share
+/// some text (otherwise showing the whole values reads better)
+pub fn inline_diff(old: &CellValue, new: &CellValue, mode: InlineDiff) -> Option<Vec<TextSpan>> {
+    if mode == InlineDiff::None {
+        return None;
+    }
+    let (CellValue::String(old), CellValue::String(new)) = (old, new) else {
+        return None;
+    };
+    let spans = diff_text(old, new, mode);
+    spans
+        .iter()
+        .any(|s| s.kind == SpanKind::Equal && !s.text.trim().is_empty())
+        .then_some(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(spans: &[TextSpan]) -> String {
+        spans
+            .iter()
+            .map(|s| match s.kind {
+                SpanKind::Equal => s.text.clone(),
+                SpanKind::Delete => format!("[-{}-]", s.text),
+                SpanKind::Insert => format!("{{+{}+}}", s.text),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_word_and_char_diffs() {
+        let old = "the quick brown fox";
+        let new = "the quick red fox";
+        assert_eq!(
+            render(&diff_text(old, new, InlineDiff::Word)),
+            "the quick [-brown-]{+red+} fox"
+        );
+        assert_eq!(
+            render(&diff_text("colour", "color", InlineDiff::Char)),
+            "colo[-u-]r"
+        );
+    }
+
+    #[test]
+    fn test_inline_diff_only_for_related_strings() {
+        let a = CellValue::from("alpha beta");
+        let b = CellValue::from("alpha gamma");
+        assert!(inline_diff(&a, &b, InlineDiff::Word).is_some());
+        assert!(inline_diff(&a, &b, InlineDiff::None).is_none());
+        assert!(inline_diff(&a, &CellValue::from("delta"), InlineDiff::Word).is_none());
+        assert!(inline_diff(&CellValue::Int(1), &CellValue::Int(2), InlineDiff::Word).is_none());
+    }
+}
@@ -0,0 +1,224 @@
+//! Comparing every sheet of two workbooks
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::model::Table;
+use crate::parser::ParserFactory;
+
+use super::{compute_diff, DiffResult};
+
+/// A sheet present in only one workbook, or under another name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SheetChange {
+    Added {
+        name: String,
+    },
+    Removed {
+        name: String,
+    },
+    /// Same columns under a new sheet name
+    Renamed {
+        old_name: String,
+        new_name: String,
+    },
+}
+
+impl std::fmt::Display for SheetChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SheetChange::Added { name } => write!(f, "+ {} (new sheet)", name),
+            SheetChange::Removed { name } => write!(f, "- {} (removed sheet)", name),
+            SheetChange::Renamed { old_name, new_name } => {
+                write!(f, "~ {} → {} (renamed sheet)", old_name, new_name)
+            }
+        }
+    }
+}
+
+/// Row diff of a sheet present in both workbooks
+#[derive(Debug)]
+pub struct SheetDiff {
+    pub old_name: String,
+    pub new_name: String,
+    pub old_table: Table,
+    pub new_table: Table,
+    pub diff: DiffResult,
+}
+
+/// Result of comparing two workbooks sheet by sheet
+#[derive(Debug, Default)]
+pub struct WorkbookDiff {
+    /// Sheets added, removed or renamed
+    pub sheet_changes: Vec<SheetChange>,
+    /// Sheets present in both workbooks, in old-workbook order
+    pub sheets: Vec<SheetDiff>,
+}
+
+impl WorkbookDiff {
+    /// Whether any sheet was added, removed, renamed or changed
+    pub fn has_changes(&self) -> bool {
+        !self.sheet_changes.is_empty() || self.sheets.iter().any(|s| s.diff.has_changes())
+    }
+}
+
+/// Compare two workbooks given as (sheet name, table) lists.
+///
+/// Sheets are matched by name. A removed and an added sheet with the same
+/// column names are taken to be a rename and are compared as well, using the
+/// old sheet's key columns.
+pub fn diff_workbooks(
+    old_sheets: Vec<(String, Table)>,
+    new_sheets: Vec<(String, Table)>,
+    config: &Config,
+) -> WorkbookDiff {
+    let mut new_sheets: Vec<Option<(String, Table)>> = new_sheets.into_iter().map(Some).collect();
+    let mut pairs = Vec::new();
+    let mut unmatched_old = Vec::new();
+
+    for (name, table) in old_sheets {
+        let partner = new_sheets
+            .iter()
+            .position(|s| s.as_ref().is_some_and(|(n, _)| *n == name));
+        match partner.and_then(|i| new_sheets[i].take()) {
+            Some(new) => pairs.push(((name, table), new)),
+            None => unmatched_old.push((name, table)),
+        }
+    }
+
+    let mut sheet_changes = Vec::new();
+    for (name, table) in unmatched_old {
+        let renamed = new_sheets.iter().position(|s| {
+            s.as_ref()
+                .is_some_and(|(_, new)| !table.columns.is_empty() && same_columns(&table, new))
+        });
+        match renamed.and_then(|i| new_sheets[i].take()) {
+            Some((new_name, mut new_table)) => {
+                sheet_changes.push(SheetChange::Renamed {
+                    old_name: name.clone(),
+                    new_name: new_name.clone(),
+                });
+                // Keys given for the old sheet name apply to the renamed sheet
+                let keys: Vec<String> = table
+                    .key_columns
+                    .iter()
+                    .map(|&i| table.columns[i].name.clone())
+                    .collect();
+                new_table.set_key_columns(&keys);
+                pairs.push(((name, table), (new_name, new_table)));
+            }
+            None => sheet_changes.push(SheetChange::Removed { name }),
+        }
+    }
+    sheet_changes.extend(
+        new_sheets
+            .into_iter()
+            .flatten()
+            .map(|(name, _)| SheetChange::Added { name }),
+    );
+
+    let sheets = pairs
+        .into_iter()
+        .map(|((old_name, old_table), (new_name, new_table))| {
+            let keys = config.key_columns_for_sheet(&old_name);
+            let (old_table, new_table, column_mapping) =
+                ParserFactory::prepare_pair(old_table, new_table, keys, config);
+            let config = config.clone().with_column_mapping(column_mapping);
+            SheetDiff {
+                diff: compute_diff(&old_table, &new_table, &config),
+                old_name,
+                new_name,
+                old_table,
+                new_table,
+            }
+        })
+        .collect();
+
+    WorkbookDiff {
+        sheet_changes,
+        sheets,
+    }
+}
+
+fn same_columns(a: &Table, b: &Table) -> bool {
+    a.columns.len() == b.columns.len()
+        && a.columns
+            .iter()
+            .zip(&b.columns)
+            .all(|(x, y)| x.name == y.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::ColumnMapping;
+    use crate::model::{CellValue, Column};
+
+    fn sheet(name: &str, columns: &[&str], value: i64) -> (String, Table) {
+        let mut table = Table::new(
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, c)| Column::new(*c, i))
+                .collect(),
+        );
+        table.set_key_columns(&[columns[0].to_string()]);
+        table.add_row(vec![CellValue::Int(1), CellValue::Int(value)], 2);
+        (name.to_string(), table)
+    }
+
+    #[test]
+    fn test_sheet_changes() {
+        let old = vec![
+            sheet("Orders", &["id", "qty"], 1),
+            sheet("Q1", &["region", "total"], 5),
+            sheet("Notes", &["note", "by"], 0),
+        ];
+        let new = vec![
+            sheet("Q1 2024", &["region", "total"], 5),
+            sheet("Orders", &["id", "qty"], 2),
+            sheet("Summary", &["metric", "value"], 0),
+        ];
+        let workbook = diff_workbooks(old, new, &Config::default());
+
+        assert_eq!(
+            workbook.sheet_changes,
+            vec![
+                SheetChange::Renamed {
+                    old_name: "Q1".to_string(),
+                    new_name: "Q1 2024".to_string()
+                },
+                SheetChange::Removed {
+                    name: "Notes".to_string()
+                },
+                SheetChange::Added {
+                    name: "Summary".to_string()
+                },
+            ]
+        );
+        let changed: Vec<(&str, bool)> = workbook
+            .sheets
+            .iter()
+            .map(|s| (s.old_name.as_str(), s.diff.has_changes()))
+            .collect();
+        assert_eq!(changed, vec![("Orders", true), ("Q1", false)]);
+        assert!(workbook.has_changes());
+
+        // Mapped columns are renamed before the sheet's keys are set
+        let old = vec![sheet("Orders", &["id", "qty"], 1)];
+        let new = vec![sheet("Orders", &["order_id", "qty"], 2)];
+        let config = Config::default()
+            .with_key_columns(vec!["id".to_string()])
+            .with_column_mapping(ColumnMapping {
+                pairs: vec![("id".to_string(), "order_id".to_string())],
+                loose: false,
+            });
+        let workbook = diff_workbooks(old, new, &config);
+        let stats = &workbook.sheets[0].diff.stats;
+        assert_eq!(
+            (stats.rows_added, stats.rows_removed, stats.rows_modified),
+            (0, 0, 1)
+        );
+    }
+}
@@ -0,0 +1,640 @@
+//! Row filter expressions (e.g. `region = 'EU' and amount >= 100`) and
+//! column selectors
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+
+use crate::model::{CellValue, Column, Table};
+
+/// Statistics of a column over a set of rows, such as a Parquet row group
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Smallest non-null value
+    pub min: CellValue,
+    /// Largest non-null value
+    pub max: CellValue,
+    /// Number of nulls, if known
+    pub null_count: Option<u64>,
+}
+
+/// Comparison operator in a filter expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    /// Check whether an ordering between a cell and a literal satisfies the operator
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// A parsed row filter
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    /// `column <op> literal`
+    Compare {
+        column: String,
+        op: CompareOp,
+        value: CellValue,
+    },
+    /// `column [not] in (literal, ...)`
+    In {
+        column: String,
+        values: Vec<CellValue>,
+        negated: bool,
+    },
+    /// `column is [not] null`
+    IsNull { column: String, negated: bool },
+    /// `column ~ 'regex'` or `column !~ 'regex'`, matched against the cell's text
+    Matches {
+        column: String,
+        pattern: Pattern,
+        negated: bool,
+    },
+    /// Both sides must match
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    /// Either side must match
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    /// The inner expression must not match
+    Not(Box<FilterExpr>),
+}
+
+/// A compiled regular expression, compared by its source
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        Regex::new(pattern)
+            .map(Pattern)
+            .with_context(|| format!("Invalid regular expression: {}", pattern))
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+
+    /// Replace every match; `$1`, `$name` refer to capture groups
+    pub fn replace_all<'t>(&self, text: &'t str, replacement: &str) -> Cow<'t, str> {
+        self.0.replace_all(text, replacement)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl FilterExpr {
+    /// Parse a filter expression
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected {:?} in filter expression", token);
+        }
+        Ok(expr)
+    }
+
+    /// Names of all columns referenced by the expression
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            FilterExpr::Compare { column, .. }
+            | FilterExpr::In { column, .. }
+            | FilterExpr::IsNull { column, .. }
+            | FilterExpr::Matches { column, .. } => vec![column.as_str()],
+            FilterExpr::And(a, b) | FilterExpr::Or(a, b) => {
+                let mut columns = a.columns();
+                columns.extend(b.columns());
+                columns
+            }
+            FilterExpr::Not(inner) => inner.columns(),
+        }
+    }
+
+    /// Evaluate the expression against a row's cells
+    pub fn matches(&self, columns: &[Column], cells: &[CellValue]) -> bool {
+        let cell = |column: &str| {
+            columns
+                .iter()
+                .position(|c| c.name == column)
+                .and_then(|idx| cells.get(idx))
+                .unwrap_or(&CellValue::Null)
+        };
+        match self {
+            FilterExpr::Compare { column, op, value } => {
+                let cell = cell(column);
+                if cell.is_null() || value.is_null() {
+                    // `= null` matches nulls, `!= null` matches everything else
+                    return match op {
+                        CompareOp::Eq => cell.is_null() && value.is_null(),
+                        CompareOp::Ne => cell.is_null() != value.is_null(),
+                        _ => false,
+                    };
+                }
+                cell.compare(value).is_some_and(|ord| op.accepts(ord))
+            }
+            FilterExpr::In {
+                column,
+                values,
+                negated,
+            } => {
+                let cell = cell(column);
+                let found = values.iter().any(|value| {
+                    if cell.is_null() || value.is_null() {
+                        cell.is_null() && value.is_null()
+                    } else {
+                        cell.compare(value) == Some(Ordering::Equal)
+                    }
+                });
+                found != *negated
+            }
+            FilterExpr::IsNull { column, negated } => cell(column).is_null() != *negated,
+            FilterExpr::Matches {
+                column,
+                pattern,
+                negated,
+            } => {
+                // Nulls match no pattern
+                let cell = cell(column);
+                let found = !cell.is_null() && pattern.is_match(&cell.display());
+                found != *negated
+            }
+            FilterExpr::And(a, b) => a.matches(columns, cells) && b.matches(columns, cells),
+            FilterExpr::Or(a, b) => a.matches(columns, cells) || b.matches(columns, cells),
+            FilterExpr::Not(inner) => !inner.matches(columns, cells),
+        }
+    }
+
+    /// Decide from a column's min/max statistics whether any row could match.
+    ///
+    /// `stats` returns the statistics of a column, or None if unknown.
+    /// Returns true whenever the statistics cannot rule a match out.
+    pub fn may_match<F>(&self, stats: &F) -> bool
+    where
+        F: Fn(&str) -> Option<ColumnStats>,
+    {
+        match self {
+            FilterExpr::Compare { column, op, value } => {
+                let Some(stats) = stats(column) else {
+                    return true;
+                };
+                let (Some(lo), Some(hi)) = (stats.min.compare(value), stats.max.compare(value))
+                else {
+                    return true;
+                };
+                match op {
+                    CompareOp::Eq => lo != Ordering::Greater && hi != Ordering::Less,
+                    // Nulls are not in min/max, and `!=` matches them
+                    CompareOp::Ne => {
+                        !(lo == Ordering::Equal && hi == Ordering::Equal)
+                            || stats.null_count != Some(0)
+                    }
+                    CompareOp::Lt => lo == Ordering::Less,
+                    CompareOp::Le => lo != Ordering::Greater,
+                    CompareOp::Gt => hi == Ordering::Greater,
+                    CompareOp::Ge => hi != Ordering::Less,
+                }
+            }
+            FilterExpr::In {
+                column,
+                values,
+                negated: false,
+            } => {
+                let Some(stats) = stats(column) else {
+                    return true;
+                };
+                values.iter().any(|value| {
+                    match (stats.min.compare(value), stats.max.compare(value)) {
+                        (Some(lo), Some(hi)) => lo != Ordering::Greater && hi != Ordering::Less,
+                        _ => true,
+                    }
+                })
+            }
+            // Null counts and text patterns are not in the statistics
+            FilterExpr::In { .. }
+            | FilterExpr::IsNull { .. }
+            | FilterExpr::Matches { .. }
+            | FilterExpr::Not(_) => true,
+            FilterExpr::And(a, b) => a.may_match(stats) && b.may_match(stats),
+            FilterExpr::Or(a, b) => a.may_match(stats) || b.may_match(stats),
+        }
+    }
+
+    /// Drop all rows of a table that do not match
+    pub fn apply(&self, table: &mut Table) {
+        let columns = table.columns.clone();
+        table.retain_rows(|row| self.matches(&columns, &row.cells));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(CellValue),
+    Op(CompareOp),
+    /// `~` (or `=~`), or `!~` when negated
+    Match { negated: bool },
+    And,
+    Or,
+    Not,
+    In,
+    Is,
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' || c == '"' {
+            // Quoted string literal
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, ch)) if ch == c => break,
+                    Some((_, ch)) => s.push(ch),
+                    None => bail!("Unterminated string starting at position {}", start),
+                }
+            }
+            tokens.push(Token::Literal(CellValue::String(Cow::Owned(s))));
+        } else if c == '`' {
+            // Quoted column name
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '`')) => break,
+                    Some((_, ch)) => s.push(ch),
+                    None => bail!("Unterminated column name starting at position {}", start),
+                }
+            }
+            tokens.push(Token::Ident(s));
+        } else if c == '~' {
+            chars.next();
+            tokens.push(Token::Match { negated: false });
+        } else if "(),".contains(c) {
+            chars.next();
+            tokens.push(match c {
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                _ => Token::Comma,
+            });
+        } else if "=!<>".contains(c) {
+            chars.next();
+            if "=!".contains(c) && chars.next_if(|&(_, ch)| ch == '~').is_some() {
+                tokens.push(Token::Match { negated: c == '!' });
+                continue;
+            }
+            let next_eq = chars.next_if(|&(_, ch)| ch == '=').is_some();
+            let op = match (c, next_eq) {
+                ('=', _) => CompareOp::Eq,
+                ('!', true) => CompareOp::Ne,
+                ('<', true) => CompareOp::Le,
+                ('<', false) if chars.next_if(|&(_, ch)| ch == '>').is_some() => CompareOp::Ne,
+                ('<', false) => CompareOp::Lt,
+                ('>', true) => CompareOp::Ge,
+                ('>', false) => CompareOp::Gt,
+                _ => bail!("Unexpected '{}' at position {}", c, start),
+            };
+            tokens.push(Token::Op(op));
+        } else if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            let mut word = String::new();
+            while let Some((_, ch)) =
+                chars.next_if(|&(_, ch)| ch.is_alphanumeric() || "_-.".contains(ch))
+            {
+                word.push(ch);
+            }
+            tokens.push(word_token(word));
+        } else {
+            bail!("Unexpected '{}' at position {}", c, start);
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Classify a bare word as keyword, literal or column name
+fn word_token(word: String) -> Token {
+    for (keyword, token) in [
+        ("and", Token::And),
+        ("or", Token::Or),
+        ("not", Token::Not),
+        ("in", Token::In),
+        ("is", Token::Is),
+    ] {
+        if word.eq_ignore_ascii_case(keyword) {
+            return token;
+        }
+    }
+    if word.eq_ignore_ascii_case("null") {
+        return Token::Literal(CellValue::Null);
+    }
+    if word.eq_ignore_ascii_case("true") || word.eq_ignore_ascii_case("false") {
+        return Token::Literal(CellValue::Bool(word.eq_ignore_ascii_case("true")));
+    }
+    if let Ok(i) = word.parse::<i64>() {
+        return Token::Literal(CellValue::Int(i));
+    }
+    if let Ok(f) = word.parse::<f64>() {
+        return Token::Literal(CellValue::Float(f));
+    }
+    Token::Ident(word)
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consume the next token if it equals `token`
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr> {
+        let mut expr = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            let rhs = self.parse_and()?;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr> {
+        let mut expr = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            let rhs = self.parse_unary()?;
+            expr = FilterExpr::And(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr> {
+        if self.eat(&Token::Not) {
+            return Ok(FilterExpr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.parse_or()?;
+            if !self.eat(&Token::RParen) {
+                bail!("Expected ')', found {:?}", self.peek());
+            }
+            return Ok(expr);
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<FilterExpr> {
+        let column = match self.next() {
+            Some(Token::Ident(name)) => name,
+            other => bail!("Expected column name, found {:?}", other),
+        };
+        match self.next() {
+            Some(Token::Op(op)) => {
+                let value = self.parse_value(&column)?;
+                Ok(FilterExpr::Compare { column, op, value })
+            }
+            Some(Token::In) => self.parse_in(column, false),
+            Some(Token::Not) if self.eat(&Token::In) => self.parse_in(column, true),
+            Some(Token::Is) => {
+                let negated = self.eat(&Token::Not);
+                match self.next() {
+                    Some(Token::Literal(CellValue::Null)) => Ok(FilterExpr::IsNull { column, negated }),
+                    other => bail!("Expected null after '{} is', found {:?}", column, other),
+                }
+            }
+            Some(Token::Match { negated }) => {
+                let pattern = match self.parse_value(&column)? {
+                    CellValue::String(s) => Pattern::new(&s)?,
+                    other => Pattern::new(&other.display())?,
+                };
+                Ok(FilterExpr::Matches {
+                    column,
+                    pattern,
+                    negated,
+                })
+            }
+            other => bail!("Expected comparison operator after '{}', found {:?}", column, other),
+        }
+    }
+
+    /// Parse `(literal, ...)` after `in`
+    fn parse_in(&mut self, column: String, negated: bool) -> Result<FilterExpr> {
+        if !self.eat(&Token::LParen) {
+            bail!("Expected '(' after '{} in', found {:?}", column, self.peek());
+        }
+        let mut values = vec![self.parse_value(&column)?];
+        while self.eat(&Token::Comma) {
+            values.push(self.parse_value(&column)?);
+        }
+        if !self.eat(&Token::RParen) {
+            bail!("Expected ')' to close the list for '{}', found {:?}", column, self.peek());
+        }
+        Ok(FilterExpr::In {
+            column,
+            values,
+            negated,
+        })
+    }
+
+    fn parse_value(&mut self, column: &str) -> Result<CellValue> {
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(value),
+            // Unquoted words on the right-hand side are string literals
+            Some(Token::Ident(word)) => Ok(CellValue::String(Cow::Owned(word))),
+            other => bail!("Expected value after '{}', found {:?}", column, other),
+        }
+    }
+}
+
+/// Selects columns by exact name, glob (`price_*`) or regex (`/^q[1-4]$/`)
+#[derive(Debug, Clone)]
+pub enum ColumnSelector {
+    Name(String),
+    Glob(glob::Pattern),
+    Regex(Pattern),
+}
+
+impl ColumnSelector {
+    /// Parse a selector: `/.../` is a regex, a name with `*`, `?` or `[` a glob
+    pub fn parse(input: &str) -> Result<Self> {
+        if let Some(regex) = input
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            return Ok(ColumnSelector::Regex(Pattern::new(regex)?));
+        }
+        if input.contains(['*', '?', '[']) {
+            let glob = glob::Pattern::new(input)
+                .with_context(|| format!("Invalid column pattern: {}", input))?;
+            return Ok(ColumnSelector::Glob(glob));
+        }
+        Ok(ColumnSelector::Name(input.to_string()))
+    }
+
+    /// Check whether a column name is selected
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            ColumnSelector::Name(n) => n == name,
+            ColumnSelector::Glob(glob) => glob.matches(name),
+            ColumnSelector::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Parse column selectors from command-line strings
+pub fn parse_column_selectors(inputs: &[String]) -> Result<Vec<ColumnSelector>> {
+    inputs.iter().map(|s| ColumnSelector::parse(s)).collect()
+}
+
+/// Parse a filter from a command-line string
+pub fn parse_filter(input: &str) -> Result<FilterExpr> {
+    FilterExpr::parse(input).with_context(|| format!("Invalid filter expression: {}", input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<Column> {
+        vec![Column::new("region", 0), Column::new("amount", 1)]
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let expr = FilterExpr::parse("region = 'EU' and amount >= 100").unwrap();
+        assert_eq!(expr.columns(), vec!["region", "amount"]);
+
+        let row = |region: &str, amount: i64| vec![CellValue::from(region), CellValue::Int(amount)];
+        assert!(expr.matches(&columns(), &row("EU", 100)));
+        assert!(!expr.matches(&columns(), &row("EU", 99)));
+        assert!(!expr.matches(&columns(), &row("US", 500)));
+    }
+
+    #[test]
+    fn test_may_match_statistics() {
+        let expr = FilterExpr::parse("amount > 50").unwrap();
+        let stats = |lo: i64, hi: i64, null_count: Option<u64>| {
+            move |_: &str| {
+                Some(ColumnStats {
+                    min: CellValue::Int(lo),
+                    max: CellValue::Int(hi),
+                    null_count,
+                })
+            }
+        };
+        assert!(expr.may_match(&stats(0, 60, Some(0))));
+        assert!(!expr.may_match(&stats(0, 50, Some(0))));
+
+        let expr = FilterExpr::parse("region = EU").unwrap();
+        let strings = |_: &str| {
+            Some(ColumnStats {
+                min: CellValue::from("AP"),
+                max: CellValue::from("DE"),
+                null_count: None,
+            })
+        };
+        assert!(!expr.may_match(&strings));
+
+        // A constant column only rules `!=` out if it has no nulls
+        let expr = FilterExpr::parse("amount != 5").unwrap();
+        assert!(!expr.may_match(&stats(5, 5, Some(0))));
+        assert!(expr.may_match(&stats(5, 5, Some(1))));
+        assert!(expr.may_match(&stats(5, 5, None)));
+    }
+
+    #[test]
+    fn test_or_in_null_and_regex() {
+        let columns = vec![
+            Column::new("region", 0),
+            Column::new("status", 1),
+            Column::new("email", 2),
+        ];
+        let row = |region: &str, status: Option<&str>, email: &str| {
+            vec![CellValue::from(region), CellValue::from(status), CellValue::from(email)]
+        };
+
+        let expr = FilterExpr::parse(
+            "(region in ('EU', 'UK') or region = US) and status is not null and status != 'archived'",
+        )
+        .unwrap();
+        assert!(expr.matches(&columns, &row("UK", Some("open"), "")));
+        assert!(expr.matches(&columns, &row("US", Some("open"), "")));
+        assert!(!expr.matches(&columns, &row("AP", Some("open"), "")));
+        assert!(!expr.matches(&columns, &row("EU", None, "")));
+        assert!(!expr.matches(&columns, &row("EU", Some("archived"), "")));
+
+        let expr = FilterExpr::parse("email ~ '@example\\.com$' and region not in (US)").unwrap();
+        assert!(expr.matches(&columns, &row("EU", None, "a@example.com")));
+        assert!(!expr.matches(&columns, &row("US", None, "a@example.com")));
+        assert!(!expr.matches(&columns, &row("EU", None, "a@example.org")));
+        assert!(FilterExpr::parse("email !~ '['").is_err());
+    }
+
+    #[test]
+    fn test_column_selectors() {
+        let selectors = parse_column_selectors(&[
+            "id".to_string(),
+            "price_*".to_string(),
+            "/^q[1-4]$/".to_string(),
+        ])
+        .unwrap();
+        let selected = |name: &str| selectors.iter().any(|s| s.matches(name));
+        assert!(selected("id") && selected("price_eur") && selected("q3"));
+        assert!(!selected("identifier") && !selected("q5") && !selected("unit_price"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(FilterExpr::parse("region =").is_err());
+        assert!(FilterExpr::parse("region = 'EU").is_err());
+        assert!(FilterExpr::parse("= 3").is_err());
+        assert!(FilterExpr::parse("region in ('EU'").is_err());
+        assert!(FilterExpr::parse("(region = 'EU'").is_err());
+        assert!(FilterExpr::parse("status is 3").is_err());
+    }
+}
@@ -65,7 +65,7 @@ pub fn run_git_driver(args: &GitDriverArgs) -> Result<()> {
         &new_table,
         &args.old_file,
         &args.new_file,
-        OutputFormat::Unified,
+        &config,
     )?;
 
     Ok(())
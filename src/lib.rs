@@ -5,8 +5,10 @@
 
 pub mod config;
 pub mod diff;
+pub mod filter;
 pub mod git;
 pub mod model;
+pub mod normalize;
 pub mod output;
 pub mod parser;
 
@@ -1,16 +1,26 @@
 //! datadiff - Semantic diff for tabular data
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 use anyhow::{Context, Result};
-use clap::{Parser, ValueEnum};
+use clap::{CommandFactory, Parser, ValueEnum};
 
-use datadiff::config::{Config, OutputFormat};
-use datadiff::diff::compute_diff;
+use datadiff::config::{ColorMode, Config, HeaderRow, InlineDiff, OutputFormat, RowLayout};
+use datadiff::diff::{
+    apply_limits, compute_diff, diff_directories, diff_formats, diff_partitions, diff_workbooks,
+    ColumnMapping, DiffStats, FormatChange, PartitionDiff,
+};
+use datadiff::filter::{parse_column_selectors, parse_filter};
+use datadiff::normalize::parse_normalizations;
 use datadiff::git::{run_git_driver, GitDriverArgs};
-use datadiff::output::render_to_stdout;
-use datadiff::parser::ParserFactory;
+use datadiff::output::{
+    render_directory_to_stdout, render_to_stdout, render_workbook_to_stdout,
+};
+use datadiff::parser::{
+    Dataset, ExcelParser, FixedWidthLayout, Input, ParserFactory, RecordPath, SheetFormat,
+    SqliteParser,
+};
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum CliOutputFormat {
@@ -18,6 +28,58 @@ enum CliOutputFormat {
     Json,
     Html,
     Unified,
+    Template,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliInlineDiff {
+    None,
+    Word,
+    Char,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliColor {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<CliColor> for ColorMode {
+    fn from(c: CliColor) -> Self {
+        match c {
+            CliColor::Auto => ColorMode::Auto,
+            CliColor::Always => ColorMode::Always,
+            CliColor::Never => ColorMode::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliLayout {
+    Auto,
+    Table,
+    Vertical,
+}
+
+impl From<CliLayout> for RowLayout {
+    fn from(l: CliLayout) -> Self {
+        match l {
+            CliLayout::Auto => RowLayout::Auto,
+            CliLayout::Table => RowLayout::Table,
+            CliLayout::Vertical => RowLayout::Vertical,
+        }
+    }
+}
+
+impl From<CliInlineDiff> for InlineDiff {
+    fn from(d: CliInlineDiff) -> Self {
+        match d {
+            CliInlineDiff::None => InlineDiff::None,
+            CliInlineDiff::Word => InlineDiff::Word,
+            CliInlineDiff::Char => InlineDiff::Char,
+        }
+    }
 }
 
 impl From<CliOutputFormat> for OutputFormat {
@@ -27,6 +89,7 @@ impl From<CliOutputFormat> for OutputFormat {
             CliOutputFormat::Json => OutputFormat::Json,
             CliOutputFormat::Html => OutputFormat::Html,
             CliOutputFormat::Unified => OutputFormat::Unified,
+            CliOutputFormat::Template => OutputFormat::Template,
         }
     }
 }
@@ -35,15 +98,29 @@ impl From<CliOutputFormat> for OutputFormat {
 #[derive(Parser, Debug)]
 #[command(name = "datadiff")]
 #[command(author, version, about, long_about = None)]
+#[command(after_help = "To compare every data file of two directories: datadiff dir OLD_DIR NEW_DIR [OPTIONS]\n\
+Directories of name=value partitions and quoted glob patterns are read as one table.\n\
+SQLite inputs name a table (db.sqlite:TABLE) or a query (\"db.sqlite?query=SELECT ...\");\n\
+two whole databases are compared table by table.")]
 struct Cli {
-    /// Old/original file to compare
+    /// Old/original file to compare ("-" for stdin; or directory, quoted glob,
+    /// or SQLite table)
     #[arg(required_unless_present = "git_driver")]
     old_file: Option<PathBuf>,
 
-    /// New file to compare
+    /// New file to compare (or directory, quoted glob, or SQLite table)
     #[arg(required_unless_present = "git_driver")]
     new_file: Option<PathBuf>,
 
+    /// Format of the old file (csv, json, parquet, xlsx, ...) when its
+    /// extension is missing or wrong; by default it is guessed from the content
+    #[arg(long, value_name = "FORMAT")]
+    old_format: Option<String>,
+
+    /// Format of the new file, like --old-format
+    #[arg(long, value_name = "FORMAT")]
+    new_format: Option<String>,
+
     /// Column(s) to use as primary key for row matching (comma-separated)
     #[arg(short, long, value_delimiter = ',')]
     key: Vec<String>,
@@ -52,6 +129,64 @@ struct Cli {
     #[arg(short, long, value_enum, default_value = "terminal")]
     format: CliOutputFormat,
 
+    /// Template for the output: html, markdown, text or a Tera template file
+    /// (implies --format=template)
+    #[arg(long, value_name = "NAME|FILE")]
+    template: Option<String>,
+
+    /// Highlight changes inside string cells by word, by character, or not at all
+    #[arg(long, value_enum, default_value = "word")]
+    inline_diff: CliInlineDiff,
+
+    /// Unchanged rows of context around changes in unified output
+    #[arg(short = 'U', long = "context", value_name = "ROWS", default_value_t = 3)]
+    context: usize,
+
+    /// When to colour terminal output (auto honours NO_COLOR)
+    #[arg(long, value_enum, default_value = "auto")]
+    color: CliColor,
+
+    /// Show rows as tables, as one record per row, or tables that switch to
+    /// records when too wide for the terminal
+    #[arg(long, value_enum, default_value = "auto")]
+    layout: CliLayout,
+
+    /// Show only key columns and columns with changes in terminal output
+    #[arg(long)]
+    changed_columns: bool,
+
+    /// Truncate terminal table cells to this many characters (0 = no limit)
+    #[arg(long, value_name = "CHARS", default_value_t = 40)]
+    max_cell_width: usize,
+
+    /// Terminal width to fit tables to (default: detected)
+    #[arg(long, value_name = "COLUMNS")]
+    width: Option<usize>,
+
+    /// Don't page long terminal output
+    #[arg(long)]
+    no_pager: bool,
+
+    /// Show at most this many changed rows (the summary still counts all)
+    #[arg(long, value_name = "N")]
+    max_rows: Option<usize>,
+
+    /// Show at most this many added, removed and modified rows each
+    #[arg(long, value_name = "N")]
+    max_changes_per_section: Option<usize>,
+
+    /// Show a random sample of this many changed rows
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+
+    /// Seed for --sample; the same seed picks the same rows
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    seed: u64,
+
+    /// Show only the N modified rows with the largest numeric changes
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
     /// Ignore case when comparing string values
     #[arg(long)]
     ignore_case: bool,
@@ -64,22 +199,109 @@ struct Cli {
     #[arg(long)]
     ignore_whitespace: bool,
 
+    /// Compare nested list values as sets, ignoring element order
+    #[arg(long)]
+    unordered_lists: bool,
+
+    /// Compare values as their column's shared type, so "42" equals 42,
+    /// "2024-01-01" equals a date and true equals 1
+    #[arg(long)]
+    coerce_types: bool,
+
+    /// Match key values numerically ("007", 7 and 7.0 are the same key)
+    #[arg(long)]
+    numeric_keys: bool,
+
     /// Column(s) to ignore in comparison (comma-separated)
     #[arg(long, value_delimiter = ',')]
     ignore_column: Vec<String>,
 
+    /// Only compare these columns, plus key columns (comma-separated names,
+    /// globs like price_* or regexes like /^q[1-4]$/)
+    #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+    include_column: Vec<String>,
+
+    /// Compare a column of the old file with a differently named column of
+    /// the new file (comma-separated OLD=NEW pairs)
+    #[arg(long, value_delimiter = ',', value_name = "OLD=NEW")]
+    map_column: Vec<String>,
+
+    /// Match column names ignoring case, spaces, underscores and hyphens
+    /// ("Customer ID" = customer_id)
+    #[arg(long)]
+    loose_column_names: bool,
+
+    /// Normalise a column's values before comparing them, e.g. phone=strip:-
+    /// or price=round:2 (repeatable; steps: lower, round:N, unicode:nfc,
+    /// strip:CHARS, replace:/REGEX/REPL/, map:FILE|a=b,..., cast:TYPE)
+    #[arg(long, value_name = "COLUMN=STEP")]
+    normalize: Vec<String>,
+
     /// Column to sort by before diffing (normalizes order)
     #[arg(long)]
     sort_by: Option<String>,
 
+    /// Only compare rows matching a filter, e.g. "region in ('EU', 'UK') and
+    /// status != 'archived'" (also: or, not, is [not] null, ~ regex)
+    #[arg(long)]
+    filter: Option<String>,
+
     /// For Excel files: which sheet to compare
     #[arg(long)]
     sheet: Option<String>,
 
+    /// For Excel files: compare every sheet, reporting added, removed and
+    /// renamed sheets
+    #[arg(long, conflicts_with = "sheet")]
+    all_sheets: bool,
+
+    /// Key columns for one sheet with --all-sheets, or one table of two SQLite
+    /// databases, e.g. "Orders=order_id,line" (repeatable; others use --key)
+    #[arg(long, value_name = "SHEET=COLUMNS")]
+    sheet_key: Vec<String>,
+
+    /// For Excel files: sheet row with the column names, or "auto" to skip
+    /// title rows (default: first row of the data)
+    #[arg(long, value_name = "ROW|auto")]
+    header_row: Option<HeaderRow>,
+
+    /// For Excel files: number of header rows, joined into one name per column
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    header_rows: u32,
+
+    /// For Excel files: read only a cell range ("B4:H200", "Sheet1!B4:H200"),
+    /// an Excel Table or a defined name
+    #[arg(long, value_name = "RANGE", conflicts_with = "all_sheets")]
+    range: Option<String>,
+
+    /// For Excel files: compare formula text as well as cached values
+    #[arg(long)]
+    formulas: bool,
+
+    /// For xlsx files: also report changes to number formats, fonts, fills,
+    /// comments, data validation and hidden rows and columns, cell by cell
+    #[arg(long)]
+    formatting: bool,
+
+    /// Read both files as fixed-width, with columns from a layout file: one
+    /// line per column giving name, start, width, type and implied decimals
+    #[arg(long, value_name = "LAYOUT_FILE")]
+    fixed_width: Option<PathBuf>,
+
+    /// For XML, YAML and JSON files: where the records are, as `/feed/item`,
+    /// `//item` (XML, at any depth) or `.items[]`. By default the children of
+    /// the XML root element, or the items of the top-level list
+    #[arg(long, value_name = "PATH")]
+    record_path: Option<String>,
+
     /// Only show statistics, not detailed changes
     #[arg(long)]
     stats_only: bool,
 
+    /// Number of threads for parsing and comparison (default: one per CPU)
+    #[arg(long)]
+    threads: Option<usize>,
+
     /// Run as git diff driver (internal use)
     #[arg(long, hide = true)]
     git_driver: bool,
@@ -105,8 +327,51 @@ fn main() -> ExitCode {
     }
 }
 
+/// Index of the first positional argument, skipping options and their values
+fn first_positional(args: &[std::ffi::OsString]) -> Option<usize> {
+    let command = Cli::command();
+    let takes_value = |matches: &dyn Fn(&clap::Arg) -> bool| {
+        command
+            .get_arguments()
+            .any(|arg| matches(arg) && arg.get_action().takes_values())
+    };
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if arg == "--" {
+            return (i + 1 < args.len()).then_some(i + 1);
+        } else if let Some(long) = arg.strip_prefix("--") {
+            // `--key id`, but not `--key=id`
+            if !long.contains('=') && takes_value(&|a| a.get_long() == Some(long)) {
+                i += 1;
+            }
+        } else if let Some(short) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // `-k id`, but not `-kid`
+            let mut chars = short.chars();
+            let c = chars.next();
+            if chars.as_str().is_empty() && takes_value(&|a| a.get_short() == c) {
+                i += 1;
+            }
+        } else {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
 fn run() -> Result<bool> {
-    let cli = Cli::parse();
+    // `datadiff dir OLD_DIR NEW_DIR` compares directories; so do two
+    // directory arguments. A file or directory named `dir` is still an input.
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let dir_command = match first_positional(&args) {
+        Some(i) if args[i] == "dir" && !Path::new("dir").exists() => {
+            args.remove(i);
+            true
+        }
+        _ => false,
+    };
+    let cli = Cli::parse_from(args);
 
     // Handle git driver mode
     if cli.git_driver {
@@ -125,63 +390,330 @@ fn run() -> Result<bool> {
     let old_file = cli.old_file.context("old_file is required")?;
     let new_file = cli.new_file.context("new_file is required")?;
 
+    let filter = cli.filter.as_deref().map(parse_filter).transpose()?;
+    let include_columns = parse_column_selectors(&cli.include_column)?;
+    let normalize = parse_normalizations(&cli.normalize)?;
+    let sheet_keys = cli
+        .sheet_key
+        .iter()
+        .map(|spec| {
+            let (sheet, keys) = spec
+                .split_once('=')
+                .with_context(|| format!("Expected SHEET=COLUMNS, got: {}", spec))?;
+            Ok((sheet.to_string(), keys.split(',').map(|k| k.trim().to_string()).collect()))
+        })
+        .collect::<Result<_>>()?;
+    let column_mapping = ColumnMapping {
+        pairs: cli
+            .map_column
+            .iter()
+            .map(|pair| ColumnMapping::parse_pair(pair))
+            .collect::<Result<_>>()?,
+        loose: cli.loose_column_names,
+    };
+
     let config = Config {
         old_file: old_file.clone(),
         new_file: new_file.clone(),
         key_columns: cli.key,
-        output_format: cli.format.into(),
+        output_format: if cli.template.is_some() {
+            OutputFormat::Template
+        } else {
+            cli.format.into()
+        },
+        template: cli.template,
+        inline_diff: cli.inline_diff.into(),
+        context_lines: cli.context,
+        color: cli.color.into(),
+        layout: cli.layout.into(),
+        changed_columns_only: cli.changed_columns,
+        max_cell_width: cli.max_cell_width,
+        terminal_width: cli.width,
+        pager: !cli.no_pager,
+        max_rows: cli.max_rows,
+        max_changes_per_section: cli.max_changes_per_section,
+        sample: cli.sample,
+        sample_seed: cli.seed,
+        top_changes: cli.top,
         ignore_case: cli.ignore_case,
         numeric_tolerance: cli.numeric_tolerance,
         ignore_whitespace: cli.ignore_whitespace,
+        unordered_lists: cli.unordered_lists,
+        coerce_types: cli.coerce_types,
+        numeric_keys: cli.numeric_keys,
         ignore_columns: cli.ignore_column,
+        include_columns,
+        column_mapping,
+        normalize,
         sort_by: cli.sort_by,
+        filter,
         sheet_name: cli.sheet,
+        all_sheets: cli.all_sheets,
+        sheet_keys,
+        header_row: cli.header_row.unwrap_or_default(),
+        header_rows: cli.header_rows as usize,
+        data_range: cli.range,
+        excel_formulas: cli.formulas,
+        excel_formatting: cli.formatting,
+        fixed_width_layout: cli
+            .fixed_width
+            .as_deref()
+            .map(FixedWidthLayout::read)
+            .transpose()?,
+        record_path: cli.record_path.as_deref().map(RecordPath::parse).transpose()?,
         stats_only: cli.stats_only,
+        threads: cli.threads,
         git_driver_mode: false,
     };
 
+    if let Some(threads) = config.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to configure thread pool")?;
+    }
+
+    // Stdin, pipes and compressed files are read into temporary files
+    if old_file == Path::new("-") && new_file == Path::new("-") {
+        anyhow::bail!("Only one of the files can be read from stdin");
+    }
+    let old_input = Input::open(&old_file)?;
+    let new_input = Input::open(&new_file)?;
+    let (old_path, new_path) = (old_input.path(), new_input.path());
+
     // Parse files
-    let factory = ParserFactory::new();
-    
-    let mut old_table = factory
-        .parse(&old_file, &config)
-        .with_context(|| format!("Failed to parse old file: {}", old_file.display()))?;
-    
-    let mut new_table = factory
-        .parse(&new_file, &config)
-        .with_context(|| format!("Failed to parse new file: {}", new_file.display()))?;
+    let mut factory = ParserFactory::new();
+    // A layout makes both files fixed-width unless a format is given
+    let default_format = config.fixed_width_layout.as_ref().map(|_| "fwf");
+    if let Some(format) = cli.old_format.as_deref().or(default_format) {
+        factory.set_format(old_path, format)?;
+    }
+    if let Some(format) = cli.new_format.as_deref().or(default_format) {
+        factory.set_format(new_path, format)?;
+    }
+
+    // Directories of partitions are datasets, not sets of files to pair
+    let partitioned = Dataset::is_partitioned(&old_file) || Dataset::is_partitioned(&new_file);
+    if dir_command || (old_file.is_dir() && new_file.is_dir() && !partitioned) {
+        return run_directory(&factory, &old_file, &new_file, &config);
+    }
 
-    // Set key columns if specified
-    if !config.key_columns.is_empty() {
-        old_table.set_key_columns(&config.key_columns);
-        new_table.set_key_columns(&config.key_columns);
+    // Two SQLite databases are compared like workbooks, table by table
+    if config.all_sheets
+        || (SqliteParser::is_database(old_path) && SqliteParser::is_database(new_path))
+    {
+        return run_workbook(&factory, (&old_file, old_path), (&new_file, new_path), &config);
     }
 
+    // Two datasets are compared partition by partition, skipping partitions
+    // whose files are identical
+    let mut partitions = None;
+    let datasets = (
+        Dataset::open(old_path, &factory)?,
+        Dataset::open(new_path, &factory)?,
+    );
+    // With mapped columns, keys, filter and include list wait for the renames
+    let parse_config = ParserFactory::parse_config(&config);
+    let (old_table, new_table) = if let (Some(mut old), Some(mut new)) = datasets {
+        if let Some(ref filter) = config.filter {
+            old.prune(filter);
+            new.prune(filter);
+        }
+        let unchanged = old.skip_unchanged(&mut new)?;
+        let unchanged_rows = unchanged
+            .row_count(&factory, &config)
+            .with_context(|| format!("Failed to count rows of {}", old_file.display()))?;
+        let mut columns = old.partition_columns();
+        for column in new.partition_columns() {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        let old_table = factory
+            .parse_dataset(&old, &parse_config)
+            .with_context(|| format!("Failed to parse old dataset: {}", old_file.display()))?;
+        let new_table = factory
+            .parse_dataset(&new, &parse_config)
+            .with_context(|| format!("Failed to parse new dataset: {}", new_file.display()))?;
+        let skipped = (unchanged.partitions().len(), unchanged_rows);
+        partitions = Some((columns, old.partitions(), new.partitions(), skipped));
+        (old_table, new_table)
+    } else {
+        let old_table = factory
+            .parse(old_path, &parse_config)
+            .with_context(|| format!("Failed to parse old file: {}", old_file.display()))?;
+        let new_table = factory
+            .parse(new_path, &parse_config)
+            .with_context(|| format!("Failed to parse new file: {}", new_file.display()))?;
+        (old_table, new_table)
+    };
+
+    // Show mapped columns under their old names; keys may use either name
+    let (old_table, new_table, column_mapping) =
+        ParserFactory::prepare_pair(old_table, new_table, &config.key_columns, &config);
+    let config = config.with_column_mapping(column_mapping);
+
     // Compute diff
-    let diff = compute_diff(&old_table, &new_table, &config);
+    let mut diff = compute_diff(&old_table, &new_table, &config);
+    if let Some((columns, old_partitions, new_partitions, (unchanged, rows))) = partitions {
+        // Rows of skipped partitions are unchanged, though not compared
+        diff.stats.add_unchanged(rows);
+        diff.partitions = Some(PartitionDiff {
+            changes: diff_partitions(
+                &diff,
+                &old_table,
+                &new_table,
+                &columns,
+                &old_partitions,
+                &new_partitions,
+            ),
+            unchanged,
+        });
+    }
+    if config.excel_formatting {
+        diff.format_changes = format_changes(
+            old_path,
+            &ExcelParser.sheet_name(old_path, &config)?,
+            new_path,
+            &ExcelParser.sheet_name(new_path, &config)?,
+        )?;
+    }
 
     // Handle stats-only mode
     if config.stats_only {
-        println!("Old file: {} ({} rows)", old_file.display(), diff.stats.old_row_count);
-        println!("New file: {} ({} rows)", new_file.display(), diff.stats.new_row_count);
-        println!();
-        println!("Added:     {}", diff.stats.rows_added);
-        println!("Removed:   {}", diff.stats.rows_removed);
-        println!("Modified:  {}", diff.stats.rows_modified);
-        println!("Unchanged: {}", diff.stats.rows_unchanged);
-        println!("Cells changed: {}", diff.stats.cells_changed);
+        print_stats(&old_file.display(), &new_file.display(), &diff.stats);
+        if config.excel_formatting {
+            println!("Formatting changes: {}", diff.format_changes.len());
+        }
+        if let Some(ref partitions) = diff.partitions {
+            println!(
+                "Changed partitions: {} ({} unchanged skipped)",
+                partitions.changes.len(),
+                partitions.unchanged
+            );
+        }
         return Ok(diff.has_changes());
     }
 
-    // Render output
+    // Render output; limits drop listed changes but keep the stats
+    apply_limits(&mut diff, &config.output_limits());
     render_to_stdout(
         &diff,
         &old_table,
         &new_table,
         &old_file,
         &new_file,
-        config.output_format,
+        &config,
     )?;
 
     Ok(diff.has_changes())
 }
+
+/// Compare every sheet of two workbooks, or every table of two databases,
+/// given as (name to show, path to read)
+fn run_workbook(
+    factory: &ParserFactory,
+    (old_file, old_path): (&Path, &Path),
+    (new_file, new_path): (&Path, &Path),
+    config: &Config,
+) -> Result<bool> {
+    let parse_config = ParserFactory::parse_config(config);
+    let old_sheets = factory
+        .parse_workbook(old_path, &parse_config)
+        .with_context(|| format!("Failed to parse old file: {}", old_file.display()))?;
+    let new_sheets = factory
+        .parse_workbook(new_path, &parse_config)
+        .with_context(|| format!("Failed to parse new file: {}", new_file.display()))?;
+
+    let mut workbook = diff_workbooks(old_sheets, new_sheets, config);
+    if config.excel_formatting {
+        for sheet in &mut workbook.sheets {
+            sheet.diff.format_changes =
+                format_changes(old_path, &sheet.old_name, new_path, &sheet.new_name)?;
+        }
+    }
+
+    if config.stats_only {
+        for change in &workbook.sheet_changes {
+            println!("{}", change);
+        }
+        for sheet in &workbook.sheets {
+            println!();
+            print_stats(
+                &format_args!("{}[{}]", old_file.display(), sheet.old_name),
+                &format_args!("{}[{}]", new_file.display(), sheet.new_name),
+                &sheet.diff.stats,
+            );
+            if config.excel_formatting {
+                println!("Formatting changes: {}", sheet.diff.format_changes.len());
+            }
+        }
+        return Ok(workbook.has_changes());
+    }
+
+    let limits = config.output_limits();
+    for sheet in &mut workbook.sheets {
+        apply_limits(&mut sheet.diff, &limits);
+    }
+    render_workbook_to_stdout(&workbook, old_file, new_file, config)?;
+
+    Ok(workbook.has_changes())
+}
+
+/// Formatting changes between a sheet of each xlsx file
+fn format_changes(
+    old_file: &Path,
+    old_sheet: &str,
+    new_file: &Path,
+    new_sheet: &str,
+) -> Result<Vec<FormatChange>> {
+    let read = |path: &Path, sheet: &str| -> Result<SheetFormat> {
+        SheetFormat::read(path, sheet)?
+            .with_context(|| format!("--formatting needs xlsx files: {}", path.display()))
+    };
+    Ok(diff_formats(&read(old_file, old_sheet)?, &read(new_file, new_sheet)?))
+}
+
+/// Compare every data file of two directories
+fn run_directory(
+    factory: &ParserFactory,
+    old_dir: &Path,
+    new_dir: &Path,
+    config: &Config,
+) -> Result<bool> {
+    let mut directory = diff_directories(old_dir, new_dir, factory, config)?;
+
+    if config.stats_only {
+        for change in &directory.file_changes {
+            println!("{}", change);
+        }
+        for file in &directory.files {
+            println!();
+            print_stats(
+                &old_dir.join(&file.old_path).display(),
+                &new_dir.join(&file.new_path).display(),
+                &file.diff.stats,
+            );
+        }
+        return Ok(directory.has_changes());
+    }
+
+    let limits = config.output_limits();
+    for file in &mut directory.files {
+        apply_limits(&mut file.diff, &limits);
+    }
+    render_directory_to_stdout(&directory, old_dir, new_dir, config)?;
+
+    Ok(directory.has_changes())
+}
+
+fn print_stats(old: &dyn std::fmt::Display, new: &dyn std::fmt::Display, stats: &DiffStats) {
+    println!("Old file: {} ({} rows)", old, stats.old_row_count);
+    println!("New file: {} ({} rows)", new, stats.new_row_count);
+    println!();
+    println!("Added:     {}", stats.rows_added);
+    println!("Removed:   {}", stats.rows_removed);
+    println!("Modified:  {}", stats.rows_modified);
+    println!("Unchanged: {}", stats.rows_unchanged);
+    println!("Cells changed: {}", stats.cells_changed);
+}
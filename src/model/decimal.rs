@@ -0,0 +1,178 @@
+//! Exact decimal numbers
+
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A fixed-point decimal stored as an unscaled integer and a scale,
+/// i.e. `value * 10^-scale`
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    /// Unscaled value
+    pub value: i128,
+    /// Number of digits after the decimal point (may be negative)
+    pub scale: i8,
+}
+
+impl Decimal {
+    /// Create a decimal from an unscaled value and scale
+    pub fn new(value: i128, scale: i8) -> Self {
+        Self { value, scale }
+    }
+
+    /// Remove trailing zeros so equal numbers share one representation
+    pub fn normalize(self) -> Self {
+        let mut d = self;
+        while d.value != 0 && d.value % 10 == 0 && d.scale > i8::MIN {
+            d.value /= 10;
+            d.scale -= 1;
+        }
+        if d.value == 0 {
+            d.scale = 0;
+        }
+        d
+    }
+
+    /// Approximate value as a float
+    pub fn to_f64(self) -> f64 {
+        self.value as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// Exact integer value, if the decimal has no fractional part
+    pub fn to_i64(self) -> Option<i64> {
+        let d = self.normalize();
+        if d.scale > 0 {
+            return None;
+        }
+        let factor = 10i128.checked_pow((-(d.scale as i32)) as u32)?;
+        d.value.checked_mul(factor)?.try_into().ok()
+    }
+
+    /// Round to `places` digits after the decimal point, halves away from zero
+    pub fn round(self, places: i8) -> Self {
+        if self.scale <= places {
+            return self;
+        }
+        let Some(factor) = 10i128.checked_pow((self.scale as i32 - places as i32) as u32) else {
+            return Self::new(0, places);
+        };
+        let (quotient, remainder) = (self.value / factor, self.value % factor);
+        let carry = if remainder.abs() * 2 >= factor {
+            self.value.signum()
+        } else {
+            0
+        };
+        Self::new(quotient + carry, places)
+    }
+
+    /// Parse a plain decimal literal such as `-12.340`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+        let digits = format!("{}{}", int_part, frac_part);
+        if frac_part.len() > i8::MAX as usize || !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let value = digits.parse::<i128>().ok()?;
+        Some(Self::new(value, frac_part.len() as i8))
+    }
+
+    /// Bring both decimals to a common scale, if that fits in an i128
+    fn aligned(self, other: Self) -> Option<(i128, i128)> {
+        let (a, b) = (self.normalize(), other.normalize());
+        let scale = a.scale.max(b.scale) as i32;
+        let rescale = |d: Decimal| {
+            10i128
+                .checked_pow((scale - d.scale as i32) as u32)
+                .and_then(|f| d.value.checked_mul(f))
+        };
+        Some((rescale(a)?, rescale(b)?))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.normalize(), other.normalize());
+        a.value == b.value && a.scale == b.scale
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let d = self.normalize();
+        d.value.hash(state);
+        d.scale.hash(state);
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.aligned(*other) {
+            Some((a, b)) => a.cmp(&b),
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.scale <= 0 {
+            let zeros = "0".repeat((-(self.scale as i32)) as usize);
+            return if self.value == 0 {
+                write!(f, "0")
+            } else {
+                write!(f, "{}{}", self.value, zeros)
+            };
+        }
+
+        let scale = self.scale as usize;
+        let digits = self.value.unsigned_abs().to_string();
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+        let sign = if self.value < 0 { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Decimal::parse(&s).ok_or_else(|| serde::de::Error::custom("invalid decimal"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Decimal::new(12345, 2).to_string(), "123.45");
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(7, -2).to_string(), "700");
+        assert_eq!(Decimal::new(u64::MAX as i128, 0).to_string(), "18446744073709551615");
+    }
+
+    #[test]
+    fn test_equality_across_scales() {
+        assert_eq!(Decimal::new(1500, 3), Decimal::new(15, 1));
+        assert!(Decimal::new(151, 2) > Decimal::new(15, 1));
+        assert_eq!(Decimal::parse("1.50"), Some(Decimal::new(15, 1)));
+        assert_eq!(Decimal::new(4200, 2).to_i64(), Some(42));
+    }
+}
@@ -1,26 +1,149 @@
 //! Primary key handling utilities
 
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::FxHasher;
+
+use super::decimal::Decimal;
 use super::table::{CellValue, Table};
 
-/// Builder for computing composite keys
-pub struct KeyBuilder {
-    column_indices: Vec<usize>,
-    separator: String,
+/// How key values are normalised before rows are matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyNormalization {
+    /// Lowercase string key values
+    pub fold_case: bool,
+    /// Trim leading/trailing whitespace from string key values
+    pub trim: bool,
+    /// Treat numeric strings and all numeric types alike (`"007"`, `7`, `7.0`)
+    pub canonical_numbers: bool,
+}
+
+/// A composite row key: the normalised values of the key columns.
+///
+/// Values are canonicalised so that `Eq` and `Hash` agree: integral floats
+/// become ints, decimals are normalised and all NaNs are the same value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RowKey(Vec<CellValue>);
+
+impl RowKey {
+    /// Build a key from the given columns of a row (all columns if none given)
+    pub fn new(
+        cells: &[CellValue],
+        key_columns: &[usize],
+        normalization: KeyNormalization,
+    ) -> Self {
+        let values = if key_columns.is_empty() {
+            cells.iter().map(|c| normalize(c, normalization)).collect()
+        } else {
+            key_columns
+                .iter()
+                .map(|&i| normalize(cells.get(i).unwrap_or(&CellValue::Null), normalization))
+                .collect()
+        };
+        RowKey(values)
+    }
+
+    /// The normalised key values
+    pub fn values(&self) -> &[CellValue] {
+        &self.0
+    }
+
+    /// Hash the key using FxHasher for performance
+    pub fn fx_hash(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl std::fmt::Display for RowKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, value) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", value.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Bring a key value into its canonical form
+fn normalize(value: &CellValue, n: KeyNormalization) -> CellValue {
+    match value {
+        CellValue::Float(f) => canonical_float(*f, n.canonical_numbers),
+        CellValue::Decimal(d) => canonical_decimal(*d),
+        CellValue::String(s) => {
+            let s = if n.trim { s.trim() } else { s.as_ref() };
+            if n.canonical_numbers {
+                if let Some(number) = parse_number(s) {
+                    return number;
+                }
+            }
+            if n.fold_case {
+                CellValue::String(Cow::Owned(s.to_lowercase()))
+            } else {
+                CellValue::String(Cow::Owned(s.to_string()))
+            }
+        }
+        CellValue::List(items) => CellValue::List(items.iter().map(|v| normalize(v, n)).collect()),
+        CellValue::Struct(fields) => CellValue::Struct(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), normalize(v, n)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
 }
 
-impl Default for KeyBuilder {
-    fn default() -> Self {
-        Self::new()
+fn canonical_float(f: f64, canonical_numbers: bool) -> CellValue {
+    if f.is_nan() {
+        CellValue::Float(f64::NAN)
+    } else if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+        CellValue::Int(f as i64)
+    } else if canonical_numbers {
+        // Shortest round-trip representation, so 0.1 and "0.1" agree
+        Decimal::parse(&f.to_string())
+            .map(canonical_decimal)
+            .unwrap_or(CellValue::Float(f))
+    } else {
+        CellValue::Float(f)
     }
 }
 
+fn canonical_decimal(d: Decimal) -> CellValue {
+    match d.to_i64() {
+        Some(i) => CellValue::Int(i),
+        None => CellValue::Decimal(d.normalize()),
+    }
+}
+
+fn parse_number(s: &str) -> Option<CellValue> {
+    if let Ok(i) = s.parse::<i64>() {
+        return Some(CellValue::Int(i));
+    }
+    if let Some(d) = Decimal::parse(s) {
+        return Some(canonical_decimal(d));
+    }
+    s.parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(|f| canonical_float(f, true))
+}
+
+/// Builder for computing composite keys
+#[derive(Debug, Clone, Default)]
+pub struct KeyBuilder {
+    column_indices: Vec<usize>,
+    normalization: KeyNormalization,
+}
+
 impl KeyBuilder {
     /// Create a new key builder
     pub fn new() -> Self {
-        Self {
-            column_indices: Vec::new(),
-            separator: "|".to_string(),
-        }
+        Self::default()
     }
 
     /// Set the key columns by index
@@ -38,29 +161,15 @@ impl KeyBuilder {
         self
     }
 
-    /// Set the separator between key components
-    pub fn with_separator(mut self, sep: impl Into<String>) -> Self {
-        self.separator = sep.into();
+    /// Set how key values are normalised
+    pub fn with_normalization(mut self, normalization: KeyNormalization) -> Self {
+        self.normalization = normalization;
         self
     }
 
-    /// Build a key string from cell values
-    pub fn build_key(&self, cells: &[CellValue]) -> String {
-        if self.column_indices.is_empty() {
-            // Use all columns if no key columns specified
-            cells
-                .iter()
-                .map(|c| c.display().into_owned())
-                .collect::<Vec<_>>()
-                .join(&self.separator)
-        } else {
-            self.column_indices
-                .iter()
-                .filter_map(|&i| cells.get(i))
-                .map(|c| c.display().into_owned())
-                .collect::<Vec<_>>()
-                .join(&self.separator)
-        }
+    /// Build a key from cell values
+    pub fn build_key(&self, cells: &[CellValue]) -> RowKey {
+        RowKey::new(cells, &self.column_indices, self.normalization)
     }
 
     /// Get the column indices
@@ -85,7 +194,6 @@ pub fn detect_key_columns(table: &Table) -> Vec<usize> {
 
         for row in &table.rows {
             if let Some(cell) = row.cells.get(col_idx) {
-                use std::hash::{Hash, Hasher};
                 let mut hasher = rustc_hash::FxHasher::default();
                 cell.hash(&mut hasher);
                 let hash = hasher.finish();
@@ -105,3 +213,63 @@ pub fn detect_key_columns(table: &Table) -> Vec<usize> {
     // If no single unique column found, return empty (will use all columns)
     Vec::new()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(cells: &[CellValue], n: KeyNormalization) -> RowKey {
+        RowKey::new(cells, &[], n)
+    }
+
+    #[test]
+    fn test_no_separator_collisions() {
+        let n = KeyNormalization::default();
+        let a = key(&[CellValue::from("a|b"), CellValue::from("c")], n);
+        let b = key(&[CellValue::from("a"), CellValue::from("b|c")], n);
+        assert_ne!(a, b);
+        assert_ne!(
+            key(&[CellValue::Int(1)], n),
+            key(&[CellValue::from("1")], n)
+        );
+    }
+
+    #[test]
+    fn test_numeric_keys_agree_with_eq_and_hash() {
+        let n = KeyNormalization::default();
+        let int = key(&[CellValue::Int(1)], n);
+        let float = key(&[CellValue::Float(1.0)], n);
+        assert_eq!(int, float);
+        assert_eq!(int.fx_hash(), float.fx_hash());
+
+        let canonical = KeyNormalization {
+            canonical_numbers: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            key(&[CellValue::from("007")], canonical),
+            key(&[CellValue::Float(7.0)], canonical)
+        );
+        assert_eq!(
+            key(&[CellValue::from("0.10")], canonical),
+            key(&[CellValue::Float(0.1)], canonical)
+        );
+    }
+
+    #[test]
+    fn test_case_and_whitespace_folding() {
+        let n = KeyNormalization {
+            fold_case: true,
+            trim: true,
+            canonical_numbers: false,
+        };
+        assert_eq!(
+            key(&[CellValue::from(" ABC ")], n),
+            key(&[CellValue::from("abc")], n)
+        );
+        assert_eq!(
+            key(&[CellValue::from("A"), CellValue::Int(2)], n).to_string(),
+            "a|2"
+        );
+    }
+}
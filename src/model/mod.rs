@@ -1,9 +1,11 @@
 //! Data model for tabular data representation
 
+mod decimal;
 mod key;
 mod schema;
 mod table;
 
-pub use key::KeyBuilder;
+pub use decimal::Decimal;
+pub use key::{KeyBuilder, KeyNormalization, RowKey};
 pub use schema::{CellType, Column};
 pub use table::{CellValue, Row, Table};
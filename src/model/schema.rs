@@ -3,24 +3,24 @@
 use serde::{Deserialize, Serialize};
 
 /// Inferred cell type for a column
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CellType {
+    #[default]
     Null,
     Bool,
     Int,
     Float,
+    Decimal,
     String,
     Date,
     DateTime,
+    Time,
+    Binary,
+    List,
+    Struct,
     Mixed,
 }
 
-impl Default for CellType {
-    fn default() -> Self {
-        CellType::Null
-    }
-}
-
 impl CellType {
     /// Widen the type to accommodate another type
     pub fn widen(self, other: CellType) -> CellType {
@@ -31,12 +31,50 @@ impl CellType {
         match (self, other) {
             (CellType::Null, t) | (t, CellType::Null) => t,
             (CellType::Int, CellType::Float) | (CellType::Float, CellType::Int) => CellType::Float,
+            (CellType::Int, CellType::Decimal) | (CellType::Decimal, CellType::Int) => {
+                CellType::Decimal
+            }
+            (CellType::Float, CellType::Decimal) | (CellType::Decimal, CellType::Float) => {
+                CellType::Float
+            }
             (CellType::Date, CellType::DateTime) | (CellType::DateTime, CellType::Date) => {
                 CellType::DateTime
             }
             _ => CellType::Mixed,
         }
     }
+
+    /// Type values of both types can be cast to for comparison: the widened
+    /// type, the typed side when the other is a string, or int for bools
+    /// against ints. Mixed when there is none.
+    pub fn common(self, other: CellType) -> CellType {
+        match self.widen(other) {
+            CellType::Mixed => match (self, other) {
+                (CellType::String, t) | (t, CellType::String) if t.is_scalar() => t,
+                (CellType::Bool, t) | (t, CellType::Bool)
+                    if matches!(t, CellType::Int | CellType::Float | CellType::Decimal) =>
+                {
+                    t
+                }
+                _ => CellType::Mixed,
+            },
+            t => t,
+        }
+    }
+
+    /// Whether values of this type can be parsed from text
+    fn is_scalar(self) -> bool {
+        matches!(
+            self,
+            CellType::Bool
+                | CellType::Int
+                | CellType::Float
+                | CellType::Decimal
+                | CellType::Date
+                | CellType::DateTime
+                | CellType::Time
+        )
+    }
 }
 
 impl std::fmt::Display for CellType {
@@ -46,9 +84,14 @@ impl std::fmt::Display for CellType {
             CellType::Bool => write!(f, "bool"),
             CellType::Int => write!(f, "int"),
             CellType::Float => write!(f, "float"),
+            CellType::Decimal => write!(f, "decimal"),
             CellType::String => write!(f, "string"),
             CellType::Date => write!(f, "date"),
             CellType::DateTime => write!(f, "datetime"),
+            CellType::Time => write!(f, "time"),
+            CellType::Binary => write!(f, "binary"),
+            CellType::List => write!(f, "list"),
+            CellType::Struct => write!(f, "struct"),
             CellType::Mixed => write!(f, "mixed"),
         }
     }
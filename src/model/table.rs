@@ -1,14 +1,17 @@
 //! Table, Row, and Cell data structures
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use indexmap::IndexMap;
-use rustc_hash::FxHasher;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use super::schema::Column;
+use super::decimal::Decimal;
+use super::key::{KeyNormalization, RowKey};
+use super::schema::{CellType, Column};
 
 /// A cell value with type information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,9 +21,18 @@ pub enum CellValue {
     Bool(bool),
     Int(i64),
     Float(f64),
+    /// Exact decimal (also used for integers beyond the i64 range)
+    Decimal(Decimal),
     String(Cow<'static, str>),
     Date(NaiveDate),
     DateTime(NaiveDateTime),
+    Time(NaiveTime),
+    /// Raw binary data
+    Bytes(#[serde(with = "hex_bytes")] Vec<u8>),
+    /// Ordered list of values (JSON arrays, Arrow lists)
+    List(Vec<CellValue>),
+    /// Named fields (JSON objects, Arrow structs and maps)
+    Struct(IndexMap<String, CellValue>),
 }
 
 impl PartialEq for CellValue {
@@ -37,12 +49,17 @@ impl PartialEq for CellValue {
                     a == b
                 }
             }
+            (CellValue::Decimal(a), CellValue::Decimal(b)) => a == b,
             (CellValue::String(a), CellValue::String(b)) => a == b,
             (CellValue::Date(a), CellValue::Date(b)) => a == b,
             (CellValue::DateTime(a), CellValue::DateTime(b)) => a == b,
-            // Cross-type numeric comparison
-            (CellValue::Int(a), CellValue::Float(b)) => (*a as f64) == *b,
-            (CellValue::Float(a), CellValue::Int(b)) => *a == (*b as f64),
+            (CellValue::Time(a), CellValue::Time(b)) => a == b,
+            (CellValue::Bytes(a), CellValue::Bytes(b)) => a == b,
+            (CellValue::List(a), CellValue::List(b)) => a == b,
+            (CellValue::Struct(a), CellValue::Struct(b)) => a == b,
+            // Cross-type numeric comparison; exact, so that it agrees with Hash
+            (CellValue::Int(a), CellValue::Float(b)) => float_as_int(*b) == Some(*a),
+            (CellValue::Float(a), CellValue::Int(b)) => float_as_int(*a) == Some(*b),
             _ => false,
         }
     }
@@ -52,25 +69,136 @@ impl Eq for CellValue {}
 
 impl Hash for CellValue {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        // Values that compare equal must hash alike: a float equal to an
+        // integer hashes as that integer, and all NaNs hash the same
+        if let CellValue::Float(f) = self {
+            if let Some(i) = float_as_int(*f) {
+                return CellValue::Int(i).hash(state);
+            }
+        }
         std::mem::discriminant(self).hash(state);
         match self {
             CellValue::Null => {}
             CellValue::Bool(b) => b.hash(state),
             CellValue::Int(i) => i.hash(state),
+            CellValue::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
             CellValue::Float(f) => f.to_bits().hash(state),
+            CellValue::Decimal(d) => d.hash(state),
             CellValue::String(s) => s.hash(state),
             CellValue::Date(d) => d.hash(state),
             CellValue::DateTime(dt) => dt.hash(state),
+            CellValue::Time(t) => t.hash(state),
+            CellValue::Bytes(b) => b.hash(state),
+            CellValue::List(items) => items.hash(state),
+            CellValue::Struct(fields) => {
+                // Equality ignores field order, so hash fields sorted by name
+                let mut sorted: Vec<_> = fields.iter().collect();
+                sorted.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                sorted.len().hash(state);
+                for (name, value) in sorted {
+                    name.hash(state);
+                    value.hash(state);
+                }
+            }
         }
     }
 }
 
+/// The integer a float is exactly equal to, if any
+fn float_as_int(f: f64) -> Option<i64> {
+    // 2^63 is the first float beyond the i64 range
+    if f.fract() == 0.0 && f >= i64::MIN as f64 && f < -(i64::MIN as f64) {
+        Some(f as i64)
+    } else {
+        None
+    }
+}
+
 impl CellValue {
     /// Check if the value is null
     pub fn is_null(&self) -> bool {
         matches!(self, CellValue::Null)
     }
 
+    /// Check if the value is a list or struct
+    pub fn is_nested(&self) -> bool {
+        matches!(self, CellValue::List(_) | CellValue::Struct(_))
+    }
+
+    /// Get the type of this value
+    pub fn cell_type(&self) -> CellType {
+        match self {
+            CellValue::Null => CellType::Null,
+            CellValue::Bool(_) => CellType::Bool,
+            CellValue::Int(_) => CellType::Int,
+            CellValue::Float(_) => CellType::Float,
+            CellValue::Decimal(_) => CellType::Decimal,
+            CellValue::String(_) => CellType::String,
+            CellValue::Date(_) => CellType::Date,
+            CellValue::DateTime(_) => CellType::DateTime,
+            CellValue::Time(_) => CellType::Time,
+            CellValue::Bytes(_) => CellType::Binary,
+            CellValue::List(_) => CellType::List,
+            CellValue::Struct(_) => CellType::Struct,
+        }
+    }
+
+    /// Convert to another scalar type: numbers between each other, text by
+    /// parsing it, bools to and from 0/1, dates to and from datetimes.
+    /// Returns None when the value doesn't convert.
+    pub fn cast(&self, target: CellType) -> Option<CellValue> {
+        let text = self.display();
+        let text = text.trim();
+        Some(match target {
+            CellType::String => CellValue::String(Cow::Owned(text.to_string())),
+            CellType::Int => CellValue::Int(match self {
+                CellValue::Int(i) => *i,
+                CellValue::Bool(b) => *b as i64,
+                CellValue::Float(f) => float_as_int(*f)?,
+                CellValue::Decimal(d) => d.to_i64()?,
+                _ => match text.parse::<i64>() {
+                    Ok(i) => i,
+                    Err(_) => Decimal::parse(text)?.to_i64()?,
+                },
+            }),
+            CellType::Float => CellValue::Float(match self {
+                CellValue::Int(i) => *i as f64,
+                CellValue::Float(f) => *f,
+                CellValue::Decimal(d) => d.to_f64(),
+                CellValue::Bool(b) => *b as i64 as f64,
+                _ => text.parse().ok()?,
+            }),
+            CellType::Decimal => CellValue::Decimal(match self {
+                CellValue::Int(i) => Decimal::new(*i as i128, 0),
+                CellValue::Decimal(d) => *d,
+                CellValue::Bool(b) => Decimal::new(*b as i128, 0),
+                _ => Decimal::parse(text)?,
+            }),
+            CellType::Bool => CellValue::Bool(match text.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => true,
+                "false" | "no" | "n" | "0" => false,
+                _ => return None,
+            }),
+            CellType::Date => CellValue::Date(match self {
+                CellValue::Date(d) => *d,
+                CellValue::DateTime(dt) => dt.date(),
+                _ => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?,
+            }),
+            CellType::DateTime => CellValue::DateTime(match self {
+                CellValue::DateTime(dt) => *dt,
+                CellValue::Date(d) => d.and_time(NaiveTime::MIN),
+                _ => NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S")
+                    .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S"))
+                    .ok()?,
+            }),
+            CellType::Time => CellValue::Time(match self {
+                CellValue::Time(t) => *t,
+                _ => NaiveTime::parse_from_str(text, "%H:%M:%S").ok()?,
+            }),
+            _ => return None,
+        })
+    }
+
     /// Convert to a display string
     pub fn display(&self) -> Cow<'_, str> {
         match self {
@@ -78,9 +206,56 @@ impl CellValue {
             CellValue::Bool(b) => Cow::Owned(b.to_string()),
             CellValue::Int(i) => Cow::Owned(i.to_string()),
             CellValue::Float(f) => Cow::Owned(f.to_string()),
+            CellValue::Decimal(d) => Cow::Owned(d.to_string()),
             CellValue::String(s) => Cow::Borrowed(s.as_ref()),
             CellValue::Date(d) => Cow::Owned(d.to_string()),
             CellValue::DateTime(dt) => Cow::Owned(dt.to_string()),
+            CellValue::Time(t) => Cow::Owned(t.to_string()),
+            CellValue::Bytes(b) => Cow::Owned(hex_bytes::encode(b)),
+            // Nested values are shown as compact JSON
+            CellValue::List(_) | CellValue::Struct(_) => {
+                Cow::Owned(serde_json::to_string(self).unwrap_or_default())
+            }
+        }
+    }
+
+    /// Order two values of compatible types (numbers with numbers, strings
+    /// with strings, ...). Returns None for values that cannot be ordered.
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (CellValue::Null, CellValue::Null) => Some(Ordering::Equal),
+            (CellValue::Bool(a), CellValue::Bool(b)) => Some(a.cmp(b)),
+            (CellValue::Int(a), CellValue::Int(b)) => Some(a.cmp(b)),
+            (CellValue::Float(a), CellValue::Float(b)) => a.partial_cmp(b),
+            (CellValue::Int(a), CellValue::Float(b)) => (*a as f64).partial_cmp(b),
+            (CellValue::Float(a), CellValue::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (CellValue::Decimal(a), CellValue::Decimal(b)) => Some(a.cmp(b)),
+            (CellValue::Decimal(a), CellValue::Int(b)) => {
+                Some(a.cmp(&Decimal::new(*b as i128, 0)))
+            }
+            (CellValue::Int(a), CellValue::Decimal(b)) => {
+                Some(Decimal::new(*a as i128, 0).cmp(b))
+            }
+            (CellValue::Decimal(a), CellValue::Float(b)) => a.to_f64().partial_cmp(b),
+            (CellValue::Float(a), CellValue::Decimal(b)) => a.partial_cmp(&b.to_f64()),
+            (CellValue::String(a), CellValue::String(b)) => Some(a.cmp(b)),
+            (CellValue::Date(a), CellValue::Date(b)) => Some(a.cmp(b)),
+            (CellValue::DateTime(a), CellValue::DateTime(b)) => Some(a.cmp(b)),
+            (CellValue::Date(a), CellValue::DateTime(b)) => Some(a.and_time(NaiveTime::MIN).cmp(b)),
+            (CellValue::DateTime(a), CellValue::Date(b)) => Some(a.cmp(&b.and_time(NaiveTime::MIN))),
+            (CellValue::Time(a), CellValue::Time(b)) => Some(a.cmp(b)),
+            (CellValue::Bytes(a), CellValue::Bytes(b)) => Some(a.cmp(b)),
+            // Allow comparing dates against string literals like '2024-01-01'
+            (CellValue::Date(a), CellValue::String(b)) => {
+                NaiveDate::parse_from_str(b, "%Y-%m-%d").ok().map(|b| a.cmp(&b))
+            }
+            (CellValue::DateTime(a), CellValue::String(b)) => {
+                NaiveDateTime::parse_from_str(b, "%Y-%m-%d %H:%M:%S")
+                    .or_else(|_| NaiveDateTime::parse_from_str(b, "%Y-%m-%dT%H:%M:%S"))
+                    .ok()
+                    .map(|b| a.cmp(&b))
+            }
+            _ => None,
         }
     }
 
@@ -111,6 +286,39 @@ impl CellValue {
     }
 }
 
+/// Binary values are written as `0x`-prefixed hex strings
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut s = String::with_capacity(2 + bytes.len() * 2);
+        s.push_str("0x");
+        for b in bytes {
+            s.push_str(&format!("{:02x}", b));
+        }
+        s
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let hex = s
+            .strip_prefix("0x")
+            .ok_or_else(|| serde::de::Error::custom("expected 0x-prefixed hex"))?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| serde::de::Error::custom("invalid hex"))
+            })
+            .collect()
+    }
+}
+
 impl std::fmt::Display for CellValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.display())
@@ -164,8 +372,8 @@ where
 pub struct Row {
     /// Cell values in column order
     pub cells: Vec<CellValue>,
-    /// Composite key string for this row
-    pub key: String,
+    /// Composite key for this row
+    pub key: RowKey,
     /// Pre-computed hash of the key for O(1) lookup
     pub key_hash: u64,
     /// Original line/row number in source file (1-indexed)
@@ -174,9 +382,14 @@ pub struct Row {
 
 impl Row {
     /// Create a new row with computed key
-    pub fn new(cells: Vec<CellValue>, key_column_indices: &[usize], source_line: usize) -> Self {
-        let key = Self::compute_key(&cells, key_column_indices);
-        let key_hash = Self::hash_key(&key);
+    pub fn new(
+        cells: Vec<CellValue>,
+        key_column_indices: &[usize],
+        normalization: KeyNormalization,
+        source_line: usize,
+    ) -> Self {
+        let key = RowKey::new(&cells, key_column_indices, normalization);
+        let key_hash = key.fx_hash();
         Self {
             cells,
             key,
@@ -185,41 +398,15 @@ impl Row {
         }
     }
 
-    /// Compute composite key from specified columns
-    fn compute_key(cells: &[CellValue], key_column_indices: &[usize]) -> String {
-        if key_column_indices.is_empty() {
-            // If no key columns specified, use all columns
-            cells
-                .iter()
-                .map(|c| c.display().into_owned())
-                .collect::<Vec<_>>()
-                .join("|")
-        } else {
-            key_column_indices
-                .iter()
-                .filter_map(|&i| cells.get(i))
-                .map(|c| c.display().into_owned())
-                .collect::<Vec<_>>()
-                .join("|")
-        }
-    }
-
-    /// Hash the key using FxHasher for performance
-    fn hash_key(key: &str) -> u64 {
-        let mut hasher = FxHasher::default();
-        key.hash(&mut hasher);
-        hasher.finish()
-    }
-
     /// Get a cell value by column index
     pub fn get(&self, index: usize) -> Option<&CellValue> {
         self.cells.get(index)
     }
 
     /// Recompute the key with new key column indices
-    pub fn recompute_key(&mut self, key_column_indices: &[usize]) {
-        self.key = Self::compute_key(&self.cells, key_column_indices);
-        self.key_hash = Self::hash_key(&self.key);
+    pub fn recompute_key(&mut self, key_column_indices: &[usize], normalization: KeyNormalization) {
+        self.key = RowKey::new(&self.cells, key_column_indices, normalization);
+        self.key_hash = self.key.fx_hash();
     }
 }
 
@@ -232,6 +419,8 @@ pub struct Table {
     pub rows: Vec<Row>,
     /// Indices of columns used as primary key
     pub key_columns: Vec<usize>,
+    /// How key values are normalised
+    pub key_normalization: KeyNormalization,
     /// Index from key hash to row index for O(1) lookup
     pub row_index: IndexMap<u64, usize>,
 }
@@ -243,19 +432,36 @@ impl Table {
             columns,
             rows: Vec::new(),
             key_columns: Vec::new(),
+            key_normalization: KeyNormalization::default(),
             row_index: IndexMap::new(),
         }
     }
 
     /// Add a row to the table
     pub fn add_row(&mut self, cells: Vec<CellValue>, source_line: usize) {
-        let row = Row::new(cells, &self.key_columns, source_line);
+        let row = Row::new(cells, &self.key_columns, self.key_normalization, source_line);
         let hash = row.key_hash;
         let idx = self.rows.len();
         self.rows.push(row);
         self.row_index.insert(hash, idx);
     }
 
+    /// Add many rows at once, computing their keys in parallel
+    pub fn extend_rows(&mut self, rows: Vec<(Vec<CellValue>, usize)>) {
+        let key_columns = &self.key_columns;
+        let normalization = self.key_normalization;
+        let rows: Vec<Row> = rows
+            .into_par_iter()
+            .map(|(cells, source_line)| Row::new(cells, key_columns, normalization, source_line))
+            .collect();
+
+        self.rows.reserve(rows.len());
+        for row in rows {
+            self.row_index.insert(row.key_hash, self.rows.len());
+            self.rows.push(row);
+        }
+    }
+
     /// Set key columns by name
     pub fn set_key_columns(&mut self, key_names: &[String]) {
         self.key_columns = key_names
@@ -263,28 +469,74 @@ impl Table {
             .filter_map(|name| self.columns.iter().position(|c| &c.name == name))
             .collect();
 
-        // Recompute keys for all rows
-        for row in &mut self.rows {
-            row.recompute_key(&self.key_columns);
-        }
-
-        // Rebuild index
-        self.rebuild_row_index();
+        self.recompute_keys();
     }
 
     /// Set key columns by index
     pub fn set_key_column_indices(&mut self, indices: Vec<usize>) {
         self.key_columns = indices;
 
-        // Recompute keys for all rows
-        for row in &mut self.rows {
-            row.recompute_key(&self.key_columns);
+        self.recompute_keys();
+    }
+
+    /// Set how key values are normalised
+    pub fn set_key_normalization(&mut self, normalization: KeyNormalization) {
+        if self.key_normalization != normalization {
+            self.key_normalization = normalization;
+            self.recompute_keys();
         }
+    }
+
+    /// Recompute keys for all rows and rebuild the index
+    fn recompute_keys(&mut self) {
+        let key_columns = &self.key_columns;
+        let normalization = self.key_normalization;
+        self.rows
+            .par_iter_mut()
+            .for_each(|row| row.recompute_key(key_columns, normalization));
+        self.rebuild_row_index();
+    }
 
-        // Rebuild index
+    /// Keep only the rows for which the predicate returns true
+    pub fn retain_rows(&mut self, mut keep: impl FnMut(&Row) -> bool) {
+        self.rows.retain(|row| keep(row));
         self.rebuild_row_index();
     }
 
+    /// Keep only the columns for which the predicate returns true
+    pub fn retain_columns(&mut self, mut keep: impl FnMut(&Column) -> bool) {
+        let mask: Vec<bool> = self.columns.iter().map(&mut keep).collect();
+        if mask.iter().all(|&k| k) {
+            return;
+        }
+
+        let mut new_index = vec![None; mask.len()];
+        let mut columns = Vec::new();
+        for (i, (column, &k)) in self.columns.drain(..).zip(&mask).enumerate() {
+            if k {
+                new_index[i] = Some(columns.len());
+                columns.push(Column {
+                    index: columns.len(),
+                    ..column
+                });
+            }
+        }
+        self.columns = columns;
+        self.rows.par_iter_mut().for_each(|row| {
+            row.cells = std::mem::take(&mut row.cells)
+                .into_iter()
+                .zip(&mask)
+                .filter_map(|(cell, &k)| k.then_some(cell))
+                .collect();
+        });
+        self.key_columns = self
+            .key_columns
+            .iter()
+            .filter_map(|&i| new_index.get(i).copied().flatten())
+            .collect();
+        self.recompute_keys();
+    }
+
     /// Rebuild the row index
     fn rebuild_row_index(&mut self) {
         self.row_index.clear();
@@ -321,17 +573,9 @@ impl Table {
     /// Sort rows by a column
     pub fn sort_by_column(&mut self, column_name: &str) {
         if let Some(col_idx) = self.column_index(column_name) {
-            self.rows.sort_by(|a, b| {
-                let va = a.get(col_idx);
-                let vb = b.get(col_idx);
-                match (va, vb) {
-                    (Some(CellValue::Int(a)), Some(CellValue::Int(b))) => a.cmp(b),
-                    (Some(CellValue::Float(a)), Some(CellValue::Float(b))) => {
-                        a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
-                    }
-                    (Some(CellValue::String(a)), Some(CellValue::String(b))) => a.cmp(b),
-                    _ => std::cmp::Ordering::Equal,
-                }
+            self.rows.sort_by(|a, b| match (a.get(col_idx), b.get(col_idx)) {
+                (Some(va), Some(vb)) => va.compare(vb).unwrap_or(Ordering::Equal),
+                _ => Ordering::Equal,
             });
             self.rebuild_row_index();
         }
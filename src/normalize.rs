@@ -0,0 +1,339 @@
+//! Per-column value normalisation applied before cells are compared
+//!
+//! A pipeline is a list of steps such as `phone=strip:-` or
+//! `price=round:2`. Normalised values are only used for comparison; the
+//! original values are kept in the diff.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use rustc_hash::FxHashMap;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::filter::{ColumnSelector, Pattern};
+use crate::model::{CellType, CellValue};
+
+/// Unicode normalisation form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+/// A single normalisation step
+#[derive(Debug, Clone)]
+pub enum Transform {
+    /// Replace regex matches in the value's text
+    Replace {
+        pattern: Pattern,
+        replacement: String,
+    },
+    /// Round numbers to this many decimal places
+    Round(i8),
+    /// Normalise strings to a Unicode form
+    Unicode(UnicodeForm),
+    /// Lowercase strings
+    Lowercase,
+    /// Remove these characters from the value's text
+    Strip(String),
+    /// Replace whole values found in a lookup table
+    Map(FxHashMap<String, String>),
+    /// Convert to another type; values that don't convert are kept
+    Cast(CellType),
+}
+
+impl Transform {
+    /// Parse a step: `lower`, `round:N`, `unicode:nfc`, `strip:CHARS`,
+    /// `replace:/REGEX/REPLACEMENT/`, `map:FILE` or `map:a=b,c=d`, `cast:TYPE`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, arg) = match spec.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (spec, None),
+        };
+        let need_arg =
+            || arg.with_context(|| format!("'{}' needs an argument, e.g. {}:...", name, name));
+
+        Ok(match name.to_lowercase().as_str() {
+            "lower" | "lowercase" => Transform::Lowercase,
+            "round" => {
+                let places = need_arg()?;
+                Transform::Round(
+                    places
+                        .parse()
+                        .with_context(|| format!("Invalid number of places: {}", places))?,
+                )
+            }
+            "unicode" => Transform::Unicode(match need_arg()?.to_lowercase().as_str() {
+                "nfc" => UnicodeForm::Nfc,
+                "nfd" => UnicodeForm::Nfd,
+                "nfkc" => UnicodeForm::Nfkc,
+                "nfkd" => UnicodeForm::Nfkd,
+                other => bail!(
+                    "Unknown Unicode form: {} (expected nfc, nfd, nfkc or nfkd)",
+                    other
+                ),
+            }),
+            "strip" => Transform::Strip(need_arg()?.to_string()),
+            "replace" => {
+                let (pattern, replacement) = split_replace(need_arg()?)?;
+                Transform::Replace {
+                    pattern: Pattern::new(&pattern)?,
+                    replacement,
+                }
+            }
+            "map" => Transform::Map(parse_map(need_arg()?)?),
+            "cast" => Transform::Cast(match need_arg()?.to_lowercase().as_str() {
+                "int" | "integer" => CellType::Int,
+                "float" => CellType::Float,
+                "decimal" => CellType::Decimal,
+                "string" | "text" => CellType::String,
+                "bool" | "boolean" => CellType::Bool,
+                "date" => CellType::Date,
+                "datetime" => CellType::DateTime,
+                "time" => CellType::Time,
+                other => bail!("Cannot cast to {}", other),
+            }),
+            _ => bail!("Unknown normalisation step: {}", spec),
+        })
+    }
+
+    /// Apply the step to a value; nulls and nested values pass through
+    pub fn apply(&self, value: CellValue) -> CellValue {
+        if value.is_null() || value.is_nested() {
+            return value;
+        }
+        match self {
+            Transform::Replace {
+                pattern,
+                replacement,
+            } => map_text(value, |s| pattern.replace_all(s, replacement)),
+            Transform::Round(places) => round(value, *places),
+            Transform::Unicode(form) => match value {
+                CellValue::String(s) => CellValue::String(Cow::Owned(match form {
+                    UnicodeForm::Nfc => s.nfc().collect(),
+                    UnicodeForm::Nfd => s.nfd().collect(),
+                    UnicodeForm::Nfkc => s.nfkc().collect(),
+                    UnicodeForm::Nfkd => s.nfkd().collect(),
+                })),
+                other => other,
+            },
+            Transform::Lowercase => match value {
+                CellValue::String(s) => CellValue::String(Cow::Owned(s.to_lowercase())),
+                other => other,
+            },
+            Transform::Strip(chars) => map_text(value, |s| {
+                if s.contains(|c| chars.contains(c)) {
+                    Cow::Owned(s.chars().filter(|&c| !chars.contains(c)).collect())
+                } else {
+                    Cow::Borrowed(s)
+                }
+            }),
+            Transform::Map(table) => {
+                let mapped = table.get(value.display().as_ref()).cloned();
+                mapped.map_or(value, |s| CellValue::String(Cow::Owned(s)))
+            }
+            Transform::Cast(target) => value.cast(*target).unwrap_or(value),
+        }
+    }
+}
+
+/// Normalisation steps for the columns a selector matches
+#[derive(Debug, Clone)]
+pub struct ColumnTransform {
+    pub selector: ColumnSelector,
+    pub transform: Transform,
+}
+
+impl ColumnTransform {
+    /// Parse `COLUMN=STEP`; the column may be a name, glob or `/regex/`
+    pub fn parse(input: &str) -> Result<Self> {
+        let (column, step) = input
+            .split_once('=')
+            .with_context(|| format!("Expected COLUMN=STEP, got: {}", input))?;
+        Ok(Self {
+            selector: ColumnSelector::parse(column.trim())?,
+            transform: Transform::parse(step.trim())
+                .with_context(|| format!("Invalid normalisation: {}", input))?,
+        })
+    }
+}
+
+/// Parse normalisation steps from command-line strings
+pub fn parse_normalizations(inputs: &[String]) -> Result<Vec<ColumnTransform>> {
+    inputs.iter().map(|s| ColumnTransform::parse(s)).collect()
+}
+
+/// Run a value through the steps in order
+pub fn normalize(value: &CellValue, steps: &[&Transform]) -> CellValue {
+    steps
+        .iter()
+        .fold(value.clone(), |value, step| step.apply(value))
+}
+
+/// Apply a text transform to a scalar. Values it leaves unchanged keep their
+/// type; numeric results become numbers, so `555-1234` stripped of `-`
+/// matches `5551234` read as an integer.
+fn map_text(value: CellValue, f: impl FnOnce(&str) -> Cow<'_, str>) -> CellValue {
+    let changed = {
+        let text = value.display();
+        match f(&text) {
+            Cow::Owned(s) if s != text => Some(s),
+            _ => None,
+        }
+    };
+    match changed {
+        Some(s) => {
+            let trimmed = s.trim();
+            if let Ok(i) = trimmed.parse::<i64>() {
+                CellValue::Int(i)
+            } else if let Ok(f) = trimmed.parse::<f64>() {
+                CellValue::Float(f)
+            } else {
+                CellValue::String(Cow::Owned(s))
+            }
+        }
+        None => value,
+    }
+}
+
+fn round(value: CellValue, places: i8) -> CellValue {
+    match value {
+        CellValue::Float(f) => {
+            let factor = 10f64.powi(places as i32);
+            CellValue::Float((f * factor).round() / factor)
+        }
+        CellValue::Decimal(d) => CellValue::Decimal(d.round(places)),
+        other => other,
+    }
+}
+
+/// Split `/REGEX/REPLACEMENT/` on its delimiter (the first character);
+/// `\` escapes the delimiter
+fn split_replace(arg: &str) -> Result<(String, String)> {
+    let mut chars = arg.chars();
+    let delimiter = chars.next().context("Expected /REGEX/REPLACEMENT/")?;
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.clone().next() == Some(delimiter) => {
+                // Keep the escape in the regex, where the delimiter may be special
+                let in_regex = parts.len() == 1;
+                let part = parts.last_mut().unwrap();
+                if in_regex {
+                    part.push('\\');
+                }
+                part.push(delimiter);
+                chars.next();
+            }
+            c if c == delimiter => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    // The closing delimiter is optional
+    if parts.len() == 3 && parts[2].is_empty() {
+        parts.pop();
+    }
+    match <[String; 2]>::try_from(parts) {
+        Ok([pattern, replacement]) => Ok((pattern, replacement)),
+        Err(_) => bail!(
+            "Expected {d}REGEX{d}REPLACEMENT{d}, got: {}",
+            arg,
+            d = delimiter
+        ),
+    }
+}
+
+/// Lookup table from inline `a=b,c=d` pairs or a two-column CSV file
+fn parse_map(arg: &str) -> Result<FxHashMap<String, String>> {
+    if arg.contains('=') {
+        return arg
+            .split(',')
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(from, to)| (from.to_string(), to.to_string()))
+                    .with_context(|| format!("Expected FROM=TO, got: {}", pair))
+            })
+            .collect();
+    }
+    let path = Path::new(arg);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Failed to open lookup table: {}", path.display()))?;
+    let mut table = FxHashMap::default();
+    for record in reader.records() {
+        let record =
+            record.with_context(|| format!("Failed to read lookup table: {}", path.display()))?;
+        match (record.get(0), record.get(1)) {
+            (Some(from), Some(to)) => table.insert(from.to_string(), to.to_string()),
+            _ => bail!("Lookup table rows need two columns: {}", path.display()),
+        };
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Decimal;
+
+    fn run(steps: &[&str], value: CellValue) -> CellValue {
+        let steps: Vec<Transform> = steps.iter().map(|s| Transform::parse(s).unwrap()).collect();
+        normalize(&value, &steps.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_text_steps() {
+        assert_eq!(
+            run(&["strip:-() "], "(555) 123-4567".into()),
+            CellValue::Int(5_551_234_567)
+        );
+        assert_eq!(
+            run(&["replace:/^USD\\s*//", "cast:decimal"], "USD 10.50".into()),
+            run(&["cast:decimal"], CellValue::Float(10.5))
+        );
+        assert_eq!(run(&["replace:|a\\|b|x|"], "a|b|c".into()), "x|c".into());
+        assert_eq!(run(&["unicode:nfc"], "e\u{301}".into()), "\u{e9}".into());
+        assert_eq!(run(&["lower", "map:y=yes,n=no"], "Y".into()), "yes".into());
+        // Unchanged values keep their type
+        assert_eq!(run(&["strip:-"], CellValue::Int(42)), CellValue::Int(42));
+    }
+
+    #[test]
+    fn test_numeric_steps() {
+        assert_eq!(
+            run(&["round:2"], CellValue::Float(1.005_1)),
+            CellValue::Float(1.01)
+        );
+        assert_eq!(
+            run(
+                &["round:1"],
+                CellValue::Decimal(Decimal::parse("2.25").unwrap())
+            ),
+            CellValue::Decimal(Decimal::parse("2.3").unwrap())
+        );
+        assert_eq!(
+            run(&["cast:int"], CellValue::Float(10.0)),
+            CellValue::Int(10)
+        );
+        assert_eq!(run(&["cast:int"], "10.0".into()), CellValue::Int(10));
+        assert_eq!(run(&["cast:int"], "ten".into()), "ten".into());
+        assert_eq!(
+            run(&["cast:bool"], CellValue::Int(1)),
+            CellValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ColumnTransform::parse("phone").is_err());
+        assert!(ColumnTransform::parse("phone=shout").is_err());
+        assert!(ColumnTransform::parse("phone=round:x").is_err());
+        assert!(ColumnTransform::parse("phone=replace:/a/b/c/").is_err());
+        assert!(ColumnTransform::parse("price_*=round:2").is_ok());
+    }
+}
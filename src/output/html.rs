@@ -1,21 +1,42 @@
-//! HTML report output
+//! Interactive HTML report output
+//!
+//! The report is a single self-contained file: styles, script and the diff
+//! data are embedded, and rows are rendered page by page in the browser.
 
 use std::io::Write;
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use tera::{Context, Tera};
 
-use crate::diff::DiffResult;
-use crate::model::Table;
+use crate::config::InlineDiff;
+use crate::diff::{DiffResult, DiffStats, DirectoryDiff, RowChange, SpanKind, WorkbookDiff};
+use crate::model::{CellValue, Row, Table};
 
-use super::OutputFormatter;
+use super::{directory_parts, workbook_parts, OutputFormatter, ReportPart};
+
+const REPORT_TEMPLATE: &str = include_str!("templates/report.html");
+const REPORT_CSS: &str = include_str!("templates/report.css");
+const REPORT_JS: &str = include_str!("templates/report.js");
+const MULTI_TEMPLATE: &str = include_str!("templates/multi.html");
 
 /// HTML report output
-pub struct HtmlOutput;
+pub struct HtmlOutput {
+    inline_diff: InlineDiff,
+}
 
 impl HtmlOutput {
     pub fn new() -> Self {
-        Self
+        Self {
+            inline_diff: InlineDiff::default(),
+        }
+    }
+
+    /// Set inline diff granularity for changed string cells
+    pub fn with_inline_diff(mut self, inline_diff: InlineDiff) -> Self {
+        self.inline_diff = inline_diff;
+        self
     }
 }
 
@@ -25,6 +46,58 @@ impl Default for HtmlOutput {
     }
 }
 
+/// Data embedded in the report for the row grid
+#[derive(Serialize)]
+struct ReportData<'a> {
+    columns: Vec<&'a str>,
+    rows: Vec<ReportRow>,
+}
+
+/// One changed row; field names are short to keep large reports small
+#[derive(Serialize)]
+struct ReportRow {
+    /// Change type: `a`dded, `r`emoved or `m`odified
+    t: &'static str,
+    /// Row key
+    k: String,
+    /// Source line
+    l: usize,
+    /// Old cells in report column order
+    o: Option<Vec<Option<String>>>,
+    /// New cells in report column order
+    n: Option<Vec<Option<String>>>,
+    /// Indices of changed columns
+    c: Vec<usize>,
+    /// Nested changes as `[path, old, new]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    d: Option<Vec<[String; 3]>>,
+    /// Inline diffs as `[column, [[kind, text], ...]]`, kind being `=`, `-` or `+`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<Vec<(usize, Vec<InlineSpan>)>>,
+}
+
+/// `(kind, text)` of an inline diff span
+type InlineSpan = (&'static str, String);
+
+/// A sheet or file of a workbook or directory report; changed ones embed
+/// their own report
+#[derive(Serialize)]
+struct EmbeddedReport<'a> {
+    name: &'a str,
+    stats: &'a DiffStats,
+    report: Option<String>,
+}
+
+/// Change count for one column of the heatmap
+#[derive(Serialize)]
+struct HeatmapColumn<'a> {
+    index: usize,
+    name: &'a str,
+    changes: usize,
+    /// Changes relative to the most-changed column, 0.0 to 1.0
+    intensity: String,
+}
+
 impl OutputFormatter for HtmlOutput {
     fn render(
         &self,
@@ -35,287 +108,320 @@ impl OutputFormatter for HtmlOutput {
         new_path: &Path,
         writer: &mut dyn Write,
     ) -> Result<()> {
-        // HTML header
-        writeln!(writer, "<!DOCTYPE html>")?;
-        writeln!(writer, "<html lang=\"en\">")?;
-        writeln!(writer, "<head>")?;
-        writeln!(writer, "  <meta charset=\"UTF-8\">")?;
-        writeln!(writer, "  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">")?;
-        writeln!(writer, "  <title>datadiff: {} → {}</title>", 
-            html_escape(old_path.display().to_string()),
-            html_escape(new_path.display().to_string())
-        )?;
-        writeln!(writer, "  <style>")?;
-        writeln!(writer, "{}", CSS_STYLES)?;
-        writeln!(writer, "  </style>")?;
-        writeln!(writer, "</head>")?;
-        writeln!(writer, "<body>")?;
-
-        // Header
-        writeln!(writer, "  <div class=\"header\">")?;
-        writeln!(writer, "    <h1>datadiff</h1>")?;
-        writeln!(writer, "    <p class=\"files\">{} → {}</p>",
-            html_escape(old_path.display().to_string()),
-            html_escape(new_path.display().to_string())
-        )?;
-        writeln!(writer, "  </div>")?;
-
-        // Summary
-        writeln!(writer, "  <div class=\"summary\">")?;
-        writeln!(writer, "    <div class=\"stat added\"><span class=\"num\">+{}</span><span class=\"label\">added</span></div>",
-            diff.stats.rows_added)?;
-        writeln!(writer, "    <div class=\"stat removed\"><span class=\"num\">-{}</span><span class=\"label\">removed</span></div>",
-            diff.stats.rows_removed)?;
-        writeln!(writer, "    <div class=\"stat modified\"><span class=\"num\">~{}</span><span class=\"label\">modified</span></div>",
-            diff.stats.rows_modified)?;
-        writeln!(writer, "    <div class=\"stat total\"><span class=\"num\">{} → {}</span><span class=\"label\">rows</span></div>",
-            diff.stats.old_row_count, diff.stats.new_row_count)?;
-        writeln!(writer, "  </div>")?;
-
-        // Schema changes
-        if !diff.schema_changes.is_empty() {
-            writeln!(writer, "  <div class=\"section\">")?;
-            writeln!(writer, "    <h2>Schema Changes</h2>")?;
-            writeln!(writer, "    <ul>")?;
-            for change in &diff.schema_changes {
-                writeln!(writer, "      <li>{}</li>", html_escape(change.to_string()))?;
+        let data = report_data(diff, old_table, new_table, self.inline_diff);
+        let heatmap = heatmap(&data);
+
+        let mut context = Context::new();
+        context.insert("old_file", &old_path.display().to_string());
+        context.insert("new_file", &new_path.display().to_string());
+        context.insert("stats", &diff.stats);
+        context.insert("omitted", &diff.omitted());
+        let schema_changes: Vec<String> =
+            diff.schema_changes.iter().map(|c| c.to_string()).collect();
+        context.insert("schema_changes", &schema_changes);
+        let format_changes: Vec<String> =
+            diff.format_changes.iter().map(|c| c.to_string()).collect();
+        context.insert("format_changes", &format_changes);
+        let partitions: Option<Vec<String>> = diff.partitions.as_ref().map(|p| {
+            let mut lines: Vec<String> = p.changes.iter().map(|c| c.to_string()).collect();
+            if p.unchanged > 0 {
+                lines.push(format!("({} unchanged partitions skipped)", p.unchanged));
             }
-            writeln!(writer, "    </ul>")?;
-            writeln!(writer, "  </div>")?;
-        }
+            lines
+        });
+        context.insert("partitions", &partitions);
+        context.insert("heatmap", &heatmap);
+        context.insert("data", &embed_json(&data)?);
+        context.insert("css", REPORT_CSS);
+        context.insert("js", REPORT_JS);
 
-        // Added rows
-        let added: Vec<_> = diff.added_rows().collect();
-        if !added.is_empty() {
-            writeln!(writer, "  <div class=\"section\">")?;
-            writeln!(writer, "    <h2>Added Rows</h2>")?;
-            write_rows_table(writer, &added, new_table, "added")?;
-            writeln!(writer, "  </div>")?;
-        }
+        let mut tera = Tera::default();
+        tera.add_raw_template("report.html", REPORT_TEMPLATE)
+            .context("Invalid HTML report template")?;
+        let html = tera
+            .render("report.html", &context)
+            .context("Failed to render HTML report")?;
+        writer.write_all(html.as_bytes())?;
 
-        // Removed rows
-        let removed: Vec<_> = diff.removed_rows().collect();
-        if !removed.is_empty() {
-            writeln!(writer, "  <div class=\"section\">")?;
-            writeln!(writer, "    <h2>Removed Rows</h2>")?;
-            write_rows_table(writer, &removed, old_table, "removed")?;
-            writeln!(writer, "  </div>")?;
-        }
+        Ok(())
+    }
 
-        // Modified rows
-        let modified: Vec<_> = diff.modified_rows().collect();
-        if !modified.is_empty() {
-            writeln!(writer, "  <div class=\"section\">")?;
-            writeln!(writer, "    <h2>Modified Rows</h2>")?;
-            for (old_row, _new_row, changes) in modified {
-                writeln!(writer, "    <div class=\"modified-row\">")?;
-                writeln!(writer, "      <h3>{}</h3>", html_escape(&old_row.key))?;
-                writeln!(writer, "      <table class=\"changes\">")?;
-                writeln!(writer, "        <tr><th>Column</th><th>Old Value</th><th>New Value</th></tr>")?;
-                for change in changes {
-                    writeln!(writer, "        <tr>")?;
-                    writeln!(writer, "          <td>{}</td>", html_escape(&change.column))?;
-                    writeln!(writer, "          <td class=\"old\">{}</td>", html_escape(change.old_value.display()))?;
-                    writeln!(writer, "          <td class=\"new\">{}</td>", html_escape(change.new_value.display()))?;
-                    writeln!(writer, "        </tr>")?;
-                }
-                writeln!(writer, "      </table>")?;
-                writeln!(writer, "    </div>")?;
-            }
-            writeln!(writer, "  </div>")?;
+    fn render_workbook(
+        &self,
+        workbook: &WorkbookDiff,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let changes: Vec<String> = workbook.sheet_changes.iter().map(|c| c.to_string()).collect();
+        let parts = workbook_parts(workbook, old_path, new_path);
+        self.render_parts("Sheet", &changes, &parts, old_path, new_path, writer)
+    }
+
+    fn render_directory(
+        &self,
+        directory: &DirectoryDiff,
+        old_dir: &Path,
+        new_dir: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let changes: Vec<String> = directory.file_changes.iter().map(|c| c.to_string()).collect();
+        let parts = directory_parts(directory, old_dir, new_dir);
+        self.render_parts("File", &changes, &parts, old_dir, new_dir, writer)
+    }
+}
+
+impl HtmlOutput {
+    /// A page listing sheet or file changes and embedding the report of
+    /// every changed part
+    fn render_parts(
+        &self,
+        noun: &str,
+        changes: &[String],
+        parts: &[ReportPart],
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let mut reports = Vec::new();
+        for part in parts {
+            let report = if part.diff.has_changes() {
+                let mut report = Vec::new();
+                self.render(
+                    part.diff,
+                    part.old_table,
+                    part.new_table,
+                    &part.old_path,
+                    &part.new_path,
+                    &mut report,
+                )?;
+                Some(String::from_utf8(report)?)
+            } else {
+                None
+            };
+            reports.push(EmbeddedReport {
+                name: part.name,
+                stats: &part.diff.stats,
+                report,
+            });
         }
 
-        // Footer
-        writeln!(writer, "  <div class=\"footer\">")?;
-        writeln!(writer, "    <p>Generated by <a href=\"https://github.com/example/datadiff\">datadiff</a></p>")?;
-        writeln!(writer, "  </div>")?;
+        let mut context = Context::new();
+        context.insert("old_file", &old_path.display().to_string());
+        context.insert("new_file", &new_path.display().to_string());
+        context.insert("noun", noun);
+        context.insert("changes", changes);
+        // Each report is escaped into its iframe's srcdoc attribute
+        context.insert("parts", &reports);
+        context.insert("css", REPORT_CSS);
 
-        writeln!(writer, "</body>")?;
-        writeln!(writer, "</html>")?;
+        let mut tera = Tera::default();
+        tera.add_raw_template("multi.html", MULTI_TEMPLATE)
+            .context("Invalid HTML multi-part template")?;
+        let html = tera
+            .render("multi.html", &context)
+            .context("Failed to render HTML report")?;
+        writer.write_all(html.as_bytes())?;
 
         Ok(())
     }
 }
 
-fn write_rows_table(
-    writer: &mut dyn Write,
-    rows: &[&crate::model::Row],
-    table: &Table,
-    class: &str,
-) -> Result<()> {
-    writeln!(writer, "    <table class=\"{}\">", class)?;
-    
-    // Header
-    writeln!(writer, "      <tr>")?;
-    for col in &table.columns {
-        writeln!(writer, "        <th>{}</th>", html_escape(&col.name))?;
-    }
-    writeln!(writer, "      </tr>")?;
-    
-    // Rows
-    for row in rows {
-        writeln!(writer, "      <tr>")?;
-        for cell in &row.cells {
-            writeln!(writer, "        <td>{}</td>", html_escape(cell.display()))?;
+/// Columns of both tables: new columns first, then columns only in the old table
+fn report_columns<'a>(old_table: &'a Table, new_table: &'a Table) -> Vec<&'a str> {
+    let mut columns: Vec<&str> = new_table.columns.iter().map(|c| c.name.as_str()).collect();
+    for column in &old_table.columns {
+        if new_table.column_index(&column.name).is_none() {
+            columns.push(&column.name);
         }
-        writeln!(writer, "      </tr>")?;
     }
-    
-    writeln!(writer, "    </table>")?;
-    Ok(())
+    columns
 }
 
-fn html_escape(s: impl AsRef<str>) -> String {
-    s.as_ref()
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
+fn report_data<'a>(
+    diff: &DiffResult,
+    old_table: &'a Table,
+    new_table: &'a Table,
+    inline_diff: InlineDiff,
+) -> ReportData<'a> {
+    let columns = report_columns(old_table, new_table);
+    let position = |name: &str| columns.iter().position(|c| *c == name);
+    let old_positions: Vec<_> = old_table
+        .columns
+        .iter()
+        .map(|c| position(&c.name))
+        .collect();
+    let new_positions: Vec<_> = new_table
+        .columns
+        .iter()
+        .map(|c| position(&c.name))
+        .collect();
+    let cells = |row: &Row, positions: &[Option<usize>]| {
+        let mut cells = vec![None; columns.len()];
+        for (cell, pos) in row.cells.iter().zip(positions) {
+            if let (Some(pos), false) = (pos, cell.is_null()) {
+                cells[*pos] = Some(cell.display().into_owned());
+            }
+        }
+        cells
+    };
+
+    let rows = diff
+        .row_changes
+        .iter()
+        .map(|change| match change {
+            RowChange::Added { key, row } => ReportRow {
+                t: "a",
+                k: key.clone(),
+                l: row.source_line,
+                o: None,
+                n: Some(cells(row, &new_positions)),
+                c: Vec::new(),
+                d: None,
+                s: None,
+            },
+            RowChange::Removed { key, row } => ReportRow {
+                t: "r",
+                k: key.clone(),
+                l: row.source_line,
+                o: Some(cells(row, &old_positions)),
+                n: None,
+                c: Vec::new(),
+                d: None,
+                s: None,
+            },
+            RowChange::Modified {
+                key,
+                old_row,
+                new_row,
+                changes,
+            } => {
+                let mut changed: Vec<usize> =
+                    changes.iter().filter_map(|c| position(&c.column)).collect();
+                changed.dedup();
+                let nested: Vec<[String; 3]> = changes
+                    .iter()
+                    .filter(|c| c.path.is_some())
+                    .map(|c| {
+                        [
+                            c.label().to_string(),
+                            display(&c.old_value),
+                            display(&c.new_value),
+                        ]
+                    })
+                    .collect();
+                let spans: Vec<_> = changes
+                    .iter()
+                    .filter(|c| c.path.is_none())
+                    .filter_map(|c| Some((position(&c.column)?, c.inline_diff(inline_diff)?)))
+                    .map(|(index, spans)| {
+                        let spans = spans
+                            .into_iter()
+                            .map(|span| {
+                                let kind = match span.kind {
+                                    SpanKind::Equal => "=",
+                                    SpanKind::Delete => "-",
+                                    SpanKind::Insert => "+",
+                                };
+                                (kind, span.text)
+                            })
+                            .collect();
+                        (index, spans)
+                    })
+                    .collect();
+                ReportRow {
+                    t: "m",
+                    k: key.clone(),
+                    l: old_row.source_line,
+                    o: Some(cells(old_row, &old_positions)),
+                    n: Some(cells(new_row, &new_positions)),
+                    c: changed,
+                    d: (!nested.is_empty()).then_some(nested),
+                    s: (!spans.is_empty()).then_some(spans),
+                }
+            }
+        })
+        .collect();
+
+    ReportData { columns, rows }
 }
 
-const CSS_STYLES: &str = r#"
-    :root {
-      --bg: #1a1b26;
-      --fg: #a9b1d6;
-      --accent: #7aa2f7;
-      --green: #9ece6a;
-      --red: #f7768e;
-      --yellow: #e0af68;
-      --border: #414868;
-    }
-    
-    * { box-sizing: border-box; margin: 0; padding: 0; }
-    
-    body {
-      font-family: 'JetBrains Mono', 'Fira Code', monospace;
-      background: var(--bg);
-      color: var(--fg);
-      padding: 2rem;
-      line-height: 1.6;
-    }
-    
-    .header {
-      border-bottom: 2px solid var(--border);
-      padding-bottom: 1rem;
-      margin-bottom: 2rem;
-    }
-    
-    .header h1 {
-      color: var(--accent);
-      font-size: 2rem;
-      font-weight: 600;
-    }
-    
-    .header .files {
-      color: var(--fg);
-      opacity: 0.8;
-      margin-top: 0.5rem;
-    }
-    
-    .summary {
-      display: flex;
-      gap: 2rem;
-      margin-bottom: 2rem;
-    }
-    
-    .stat {
-      display: flex;
-      flex-direction: column;
-      padding: 1rem;
-      border-radius: 8px;
-      background: rgba(255,255,255,0.05);
-    }
-    
-    .stat .num {
-      font-size: 1.5rem;
-      font-weight: 600;
-    }
-    
-    .stat.added .num { color: var(--green); }
-    .stat.removed .num { color: var(--red); }
-    .stat.modified .num { color: var(--yellow); }
-    
-    .section {
-      margin-bottom: 2rem;
-    }
-    
-    .section h2 {
-      color: var(--accent);
-      font-size: 1.25rem;
-      margin-bottom: 1rem;
-      padding-bottom: 0.5rem;
-      border-bottom: 1px solid var(--border);
-    }
-    
-    table {
-      width: 100%;
-      border-collapse: collapse;
-      margin-bottom: 1rem;
-    }
-    
-    th, td {
-      text-align: left;
-      padding: 0.75rem;
-      border: 1px solid var(--border);
-    }
-    
-    th {
-      background: rgba(255,255,255,0.05);
-      font-weight: 600;
-    }
-    
-    table.added tr:not(:first-child) {
-      background: rgba(158, 206, 106, 0.1);
-    }
-    
-    table.removed tr:not(:first-child) {
-      background: rgba(247, 118, 142, 0.1);
-    }
-    
-    .modified-row {
-      margin-bottom: 1.5rem;
-      padding: 1rem;
-      background: rgba(255,255,255,0.02);
-      border-radius: 8px;
-    }
-    
-    .modified-row h3 {
-      color: var(--yellow);
-      margin-bottom: 0.5rem;
-    }
-    
-    .changes td.old {
-      background: rgba(247, 118, 142, 0.15);
-      color: var(--red);
-    }
-    
-    .changes td.new {
-      background: rgba(158, 206, 106, 0.15);
-      color: var(--green);
-    }
-    
-    .footer {
-      margin-top: 3rem;
-      padding-top: 1rem;
-      border-top: 1px solid var(--border);
-      opacity: 0.6;
-      font-size: 0.875rem;
+/// Count changed cells per column over all modified rows
+fn heatmap<'a>(data: &ReportData<'a>) -> Vec<HeatmapColumn<'a>> {
+    let mut counts = vec![0usize; data.columns.len()];
+    for row in &data.rows {
+        for &index in &row.c {
+            counts[index] += 1;
+        }
     }
-    
-    .footer a {
-      color: var(--accent);
-      text-decoration: none;
+    let max = counts.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return Vec::new();
     }
-    
-    ul {
-      list-style: none;
-      padding-left: 1rem;
+    data.columns
+        .iter()
+        .zip(counts)
+        .enumerate()
+        .map(|(index, (name, changes))| HeatmapColumn {
+            index,
+            name,
+            changes,
+            intensity: format!("{:.2}", changes as f64 / max as f64),
+        })
+        .collect()
+}
+
+fn display(value: &CellValue) -> String {
+    value.display().into_owned()
+}
+
+/// Serialize data for a `<script>` element; `<` is escaped so cell values
+/// cannot close the element
+fn embed_json<T: Serialize>(data: &T) -> Result<String> {
+    Ok(serde_json::to_string(data)?.replace('<', "\\u003c"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::Column;
+
+    fn table(rows: &[(i64, &str)]) -> Table {
+        let mut table = Table::new(vec![Column::new("id", 0), Column::new("name", 1)]);
+        table.set_key_columns(&["id".to_string()]);
+        for (i, (id, name)) in rows.iter().enumerate() {
+            table.add_row(vec![CellValue::Int(*id), CellValue::from(*name)], i + 2);
+        }
+        table
     }
-    
-    ul li::before {
-      content: "→";
-      margin-right: 0.5rem;
-      color: var(--accent);
+
+    #[test]
+    fn test_report_is_self_contained() {
+        let old = table(&[(1, "a"), (2, "b")]);
+        let new = table(&[(1, "</script><b>x</b>"), (3, "c")]);
+        let diff = compute_diff(&old, &new, &Config::default());
+
+        let mut out = Vec::new();
+        HtmlOutput::new()
+            .render(
+                &diff,
+                &old,
+                &new,
+                Path::new("old.csv"),
+                Path::new("new.csv"),
+                &mut out,
+            )
+            .unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.contains("id=\"diff-data\""));
+        assert!(!html.contains("<script src") && !html.contains("<link"));
+        // Cell values can't break out of the data element
+        assert_eq!(html.matches("</script>").count(), 2);
+        assert!(html.contains("\\u003c/script>\\u003cb>x\\u003c/b>"));
+        // The heatmap lists the changed column
+        assert!(html.contains("data-column=\"1\""));
     }
-"#;
+}
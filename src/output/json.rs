@@ -6,23 +6,40 @@ use std::path::Path;
 use anyhow::Result;
 use serde::Serialize;
 
-use crate::diff::{DiffResult, RowChange, SchemaChange};
+use crate::config::InlineDiff;
+use crate::diff::{
+    DiffResult, DirectoryDiff, FileChange, FormatChange, OmittedRows, PartitionDiff, RowChange,
+    SchemaChange, SheetChange, TextSpan, WorkbookDiff,
+};
 use crate::model::{CellValue, Table};
 
-use super::OutputFormatter;
+use super::{sheet_path, OutputFormatter};
 
 /// JSON output formatter
 pub struct JsonOutput {
     pretty: bool,
+    inline_diff: InlineDiff,
 }
 
 impl JsonOutput {
     pub fn new() -> Self {
-        Self { pretty: true }
+        Self {
+            pretty: true,
+            inline_diff: InlineDiff::default(),
+        }
     }
 
     pub fn compact() -> Self {
-        Self { pretty: false }
+        Self {
+            pretty: false,
+            ..Self::new()
+        }
+    }
+
+    /// Set inline diff granularity for changed string cells
+    pub fn with_inline_diff(mut self, inline_diff: InlineDiff) -> Self {
+        self.inline_diff = inline_diff;
+        self
     }
 }
 
@@ -54,8 +71,12 @@ struct JsonCell {
 #[derive(Serialize)]
 struct JsonCellChange {
     column: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
     old_value: serde_json::Value,
     new_value: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spans: Option<Vec<TextSpan>>,
 }
 
 #[derive(Serialize)]
@@ -64,7 +85,48 @@ struct JsonDiffOutput {
     new_file: String,
     schema_changes: Vec<SchemaChange>,
     row_changes: Vec<JsonRowChange>,
+    /// Row changes left out of `row_changes` by output limits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    omitted: Option<OmittedRows>,
     stats: JsonStats,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    format_changes: Vec<FormatChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    partitions: Option<PartitionDiff>,
+}
+
+#[derive(Serialize)]
+struct JsonWorkbookOutput {
+    old_file: String,
+    new_file: String,
+    sheet_changes: Vec<SheetChange>,
+    sheets: Vec<JsonSheet>,
+}
+
+/// Diff of a sheet present in both workbooks
+#[derive(Serialize)]
+struct JsonSheet {
+    old_sheet: String,
+    new_sheet: String,
+    #[serde(flatten)]
+    diff: JsonDiffOutput,
+}
+
+#[derive(Serialize)]
+struct JsonDirectoryOutput {
+    old_dir: String,
+    new_dir: String,
+    file_changes: Vec<FileChange>,
+    files: Vec<JsonFile>,
+}
+
+/// Diff of a file present in both directories
+#[derive(Serialize)]
+struct JsonFile {
+    old_path: String,
+    new_path: String,
+    #[serde(flatten)]
+    diff: JsonDiffOutput,
 }
 
 #[derive(Serialize)]
@@ -84,22 +146,34 @@ fn cell_value_to_json(value: &CellValue) -> serde_json::Value {
         CellValue::Bool(b) => serde_json::Value::Bool(*b),
         CellValue::Int(i) => serde_json::json!(*i),
         CellValue::Float(f) => serde_json::json!(*f),
+        // Decimals are written as strings so no precision is lost
+        CellValue::Decimal(d) => serde_json::Value::String(d.to_string()),
         CellValue::String(s) => serde_json::Value::String(s.to_string()),
         CellValue::Date(d) => serde_json::Value::String(d.to_string()),
         CellValue::DateTime(dt) => serde_json::Value::String(dt.to_string()),
+        CellValue::Time(t) => serde_json::Value::String(t.to_string()),
+        CellValue::Bytes(_) => serde_json::Value::String(value.display().into_owned()),
+        CellValue::List(items) => {
+            serde_json::Value::Array(items.iter().map(cell_value_to_json).collect())
+        }
+        CellValue::Struct(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), cell_value_to_json(v)))
+                .collect(),
+        ),
     }
 }
 
-impl OutputFormatter for JsonOutput {
-    fn render(
+impl JsonOutput {
+    fn document(
         &self,
         diff: &DiffResult,
         old_table: &Table,
         new_table: &Table,
         old_path: &Path,
         new_path: &Path,
-        writer: &mut dyn Write,
-    ) -> Result<()> {
+    ) -> JsonDiffOutput {
         let row_changes: Vec<JsonRowChange> = diff
             .row_changes
             .iter()
@@ -159,8 +233,10 @@ impl OutputFormatter for JsonOutput {
                             .iter()
                             .map(|c| JsonCellChange {
                                 column: c.column.clone(),
+                                path: c.path.clone(),
                                 old_value: cell_value_to_json(&c.old_value),
                                 new_value: cell_value_to_json(&c.new_value),
+                                spans: c.inline_diff(self.inline_diff),
                             })
                             .collect(),
                     ),
@@ -168,11 +244,12 @@ impl OutputFormatter for JsonOutput {
             })
             .collect();
 
-        let output = JsonDiffOutput {
+        JsonDiffOutput {
             old_file: old_path.display().to_string(),
             new_file: new_path.display().to_string(),
             schema_changes: diff.schema_changes.clone(),
             row_changes,
+            omitted: Some(diff.omitted()).filter(|o| o.total() > 0),
             stats: JsonStats {
                 rows_added: diff.stats.rows_added,
                 rows_removed: diff.stats.rows_removed,
@@ -182,15 +259,95 @@ impl OutputFormatter for JsonOutput {
                 old_row_count: diff.stats.old_row_count,
                 new_row_count: diff.stats.new_row_count,
             },
-        };
+            format_changes: diff.format_changes.clone(),
+            partitions: diff.partitions.clone(),
+        }
+    }
 
+    fn write(&self, output: &impl Serialize, writer: &mut dyn Write) -> Result<()> {
         if self.pretty {
-            serde_json::to_writer_pretty(&mut *writer, &output)?;
+            serde_json::to_writer_pretty(&mut *writer, output)?;
         } else {
-            serde_json::to_writer(&mut *writer, &output)?;
+            serde_json::to_writer(&mut *writer, output)?;
         }
         writeln!(writer)?;
-
         Ok(())
     }
 }
+
+impl OutputFormatter for JsonOutput {
+    fn render(
+        &self,
+        diff: &DiffResult,
+        old_table: &Table,
+        new_table: &Table,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let output = self.document(diff, old_table, new_table, old_path, new_path);
+        self.write(&output, writer)
+    }
+
+    fn render_workbook(
+        &self,
+        workbook: &WorkbookDiff,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let sheets = workbook
+            .sheets
+            .iter()
+            .map(|sheet| JsonSheet {
+                old_sheet: sheet.old_name.clone(),
+                new_sheet: sheet.new_name.clone(),
+                diff: self.document(
+                    &sheet.diff,
+                    &sheet.old_table,
+                    &sheet.new_table,
+                    &sheet_path(old_path, &sheet.old_name),
+                    &sheet_path(new_path, &sheet.new_name),
+                ),
+            })
+            .collect();
+        let output = JsonWorkbookOutput {
+            old_file: old_path.display().to_string(),
+            new_file: new_path.display().to_string(),
+            sheet_changes: workbook.sheet_changes.clone(),
+            sheets,
+        };
+        self.write(&output, writer)
+    }
+
+    fn render_directory(
+        &self,
+        directory: &DirectoryDiff,
+        old_dir: &Path,
+        new_dir: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let files = directory
+            .files
+            .iter()
+            .map(|file| JsonFile {
+                old_path: file.old_path.clone(),
+                new_path: file.new_path.clone(),
+                diff: self.document(
+                    &file.diff,
+                    &file.old_table,
+                    &file.new_table,
+                    &old_dir.join(&file.old_path),
+                    &new_dir.join(&file.new_path),
+                ),
+            })
+            .collect();
+        let output = JsonDirectoryOutput {
+            old_dir: old_dir.display().to_string(),
+            new_dir: new_dir.display().to_string(),
+            file_changes: directory.file_changes.clone(),
+            files,
+        };
+        self.write(&output, writer)
+    }
+}
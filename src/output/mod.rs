@@ -2,21 +2,25 @@
 
 mod html;
 mod json;
+mod pager;
+mod template;
 mod terminal;
 mod unified;
 
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use termcolor::ColorChoice;
 
-use crate::config::OutputFormat;
-use crate::diff::DiffResult;
+use crate::config::{ColorMode, Config, OutputFormat};
+use crate::diff::{DiffResult, DirectoryDiff, WorkbookDiff};
 use crate::model::Table;
 
 pub use html::HtmlOutput;
 pub use json::JsonOutput;
-pub use terminal::TerminalOutput;
+pub use template::{template_context, TemplateOutput};
+pub use terminal::{detect_width, TerminalOutput};
 pub use unified::UnifiedOutput;
 
 /// Trait for output formatters
@@ -31,6 +35,124 @@ pub trait OutputFormatter {
         new_path: &Path,
         writer: &mut dyn Write,
     ) -> Result<()>;
+
+    /// Render a workbook diff: a summary of the sheets, then each changed
+    /// sheet as its own diff with `file[sheet]` paths
+    fn render_workbook(
+        &self,
+        workbook: &WorkbookDiff,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        writeln!(writer, "Workbook: {} → {}", old_path.display(), new_path.display())?;
+        for change in &workbook.sheet_changes {
+            writeln!(writer, "  {}", change)?;
+        }
+        write_parts(self, &workbook_parts(workbook, old_path, new_path), writer)
+    }
+
+    /// Render a directory diff: a summary of the files, then each changed
+    /// file as its own diff
+    fn render_directory(
+        &self,
+        directory: &DirectoryDiff,
+        old_dir: &Path,
+        new_dir: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        writeln!(writer, "Directory: {} → {}", old_dir.display(), new_dir.display())?;
+        for change in &directory.file_changes {
+            writeln!(writer, "  {}", change)?;
+        }
+        write_parts(self, &directory_parts(directory, old_dir, new_dir), writer)
+    }
+}
+
+/// One compared pair of a workbook or directory report
+struct ReportPart<'a> {
+    /// Sheet name or relative file path
+    name: &'a str,
+    old_path: PathBuf,
+    new_path: PathBuf,
+    old_table: &'a Table,
+    new_table: &'a Table,
+    diff: &'a DiffResult,
+}
+
+fn workbook_parts<'a>(
+    workbook: &'a WorkbookDiff,
+    old_path: &Path,
+    new_path: &Path,
+) -> Vec<ReportPart<'a>> {
+    workbook
+        .sheets
+        .iter()
+        .map(|sheet| ReportPart {
+            name: &sheet.new_name,
+            old_path: sheet_path(old_path, &sheet.old_name),
+            new_path: sheet_path(new_path, &sheet.new_name),
+            old_table: &sheet.old_table,
+            new_table: &sheet.new_table,
+            diff: &sheet.diff,
+        })
+        .collect()
+}
+
+fn directory_parts<'a>(
+    directory: &'a DirectoryDiff,
+    old_dir: &Path,
+    new_dir: &Path,
+) -> Vec<ReportPart<'a>> {
+    directory
+        .files
+        .iter()
+        .map(|file| ReportPart {
+            name: &file.new_path,
+            old_path: old_dir.join(&file.old_path),
+            new_path: new_dir.join(&file.new_path),
+            old_table: &file.old_table,
+            new_table: &file.new_table,
+            diff: &file.diff,
+        })
+        .collect()
+}
+
+/// Per-part stats lines, then the diff of every changed part
+fn write_parts<F: OutputFormatter + ?Sized>(
+    formatter: &F,
+    parts: &[ReportPart],
+    writer: &mut dyn Write,
+) -> Result<()> {
+    for part in parts {
+        let stats = &part.diff.stats;
+        write!(
+            writer,
+            "  {}: +{} added, -{} removed, ~{} modified",
+            part.name, stats.rows_added, stats.rows_removed, stats.rows_modified
+        )?;
+        match part.diff.format_changes.len() {
+            0 => writeln!(writer)?,
+            n => writeln!(writer, ", {} formatting changes", n)?,
+        }
+    }
+    for part in parts.iter().filter(|p| p.diff.has_changes()) {
+        writeln!(writer)?;
+        formatter.render(
+            part.diff,
+            part.old_table,
+            part.new_table,
+            &part.old_path,
+            &part.new_path,
+            writer,
+        )?;
+    }
+    Ok(())
+}
+
+/// Path shown for a sheet of a workbook, e.g. `report.xlsx[Orders]`
+pub fn sheet_path(path: &Path, sheet: &str) -> PathBuf {
+    PathBuf::from(format!("{}[{}]", path.display(), sheet))
 }
 
 /// Factory for creating output formatters
@@ -38,26 +160,103 @@ pub struct OutputFactory;
 
 impl OutputFactory {
     /// Create an output formatter based on format type
+    ///
+    /// The template format uses the built-in text template; see
+    /// [`OutputFactory::from_config`] for user templates.
     pub fn create(format: OutputFormat) -> Box<dyn OutputFormatter> {
         match format {
             OutputFormat::Terminal => Box::new(TerminalOutput::new()),
             OutputFormat::Json => Box::new(JsonOutput::new()),
             OutputFormat::Html => Box::new(HtmlOutput::new()),
             OutputFormat::Unified => Box::new(UnifiedOutput::new()),
+            OutputFormat::Template => Box::new(
+                TemplateOutput::builtin("text").expect("built-in template is valid"),
+            ),
         }
     }
+
+    /// Create the output formatter selected by a configuration
+    pub fn from_config(config: &Config) -> Result<Box<dyn OutputFormatter>> {
+        let inline_diff = config.inline_diff;
+        Ok(match config.output_format {
+            OutputFormat::Terminal => {
+                let color = match config.color {
+                    ColorMode::Auto => ColorChoice::Auto,
+                    ColorMode::Always => ColorChoice::Always,
+                    ColorMode::Never => ColorChoice::Never,
+                };
+                Box::new(
+                    TerminalOutput::with_color_choice(color)
+                        .with_inline_diff(inline_diff)
+                        .with_layout(config.layout)
+                        .with_width(config.terminal_width.or_else(detect_width))
+                        .with_max_cell_width(config.max_cell_width)
+                        .with_changed_columns_only(config.changed_columns_only),
+                )
+            }
+            OutputFormat::Json => Box::new(JsonOutput::new().with_inline_diff(inline_diff)),
+            OutputFormat::Html => Box::new(HtmlOutput::new().with_inline_diff(inline_diff)),
+            OutputFormat::Unified => Box::new(UnifiedOutput::with_context(config.context_lines)),
+            OutputFormat::Template => {
+                let template = match &config.template {
+                    Some(template) => TemplateOutput::load(template)?,
+                    None => TemplateOutput::builtin("text")?,
+                };
+                Box::new(template.with_inline_diff(inline_diff))
+            }
+        })
+    }
 }
 
-/// Render diff result to stdout
+/// Render diff result to stdout; long terminal output goes through a pager
 pub fn render_to_stdout(
     diff: &DiffResult,
     old_table: &Table,
     new_table: &Table,
     old_path: &Path,
     new_path: &Path,
-    format: OutputFormat,
+    config: &Config,
 ) -> Result<()> {
-    let formatter = OutputFactory::create(format);
+    let formatter = OutputFactory::from_config(config)?;
+    if config.output_format == OutputFormat::Terminal && config.pager && pager::can_page() {
+        let mut buffer = Vec::new();
+        formatter.render(diff, old_table, new_table, old_path, new_path, &mut buffer)?;
+        return pager::page(&buffer);
+    }
     let mut stdout = std::io::stdout();
     formatter.render(diff, old_table, new_table, old_path, new_path, &mut stdout)
 }
+
+/// Render a workbook diff to stdout; long terminal output goes through a pager
+pub fn render_workbook_to_stdout(
+    workbook: &WorkbookDiff,
+    old_path: &Path,
+    new_path: &Path,
+    config: &Config,
+) -> Result<()> {
+    let formatter = OutputFactory::from_config(config)?;
+    if config.output_format == OutputFormat::Terminal && config.pager && pager::can_page() {
+        let mut buffer = Vec::new();
+        formatter.render_workbook(workbook, old_path, new_path, &mut buffer)?;
+        return pager::page(&buffer);
+    }
+    let mut stdout = std::io::stdout();
+    formatter.render_workbook(workbook, old_path, new_path, &mut stdout)
+}
+
+/// Render a directory diff to stdout; long terminal output goes through a pager
+pub fn render_directory_to_stdout(
+    directory: &DirectoryDiff,
+    old_dir: &Path,
+    new_dir: &Path,
+    config: &Config,
+) -> Result<()> {
+    let formatter = OutputFactory::from_config(config)?;
+    if config.output_format == OutputFormat::Terminal && config.pager && pager::can_page() {
+        let mut buffer = Vec::new();
+        formatter.render_directory(directory, old_dir, new_dir, &mut buffer)?;
+        return pager::page(&buffer);
+    }
+    let mut stdout = std::io::stdout();
+    formatter.render_directory(directory, old_dir, new_dir, &mut stdout)
+}
@@ -0,0 +1,64 @@
+//! Paging of long terminal output
+
+use std::io::{ErrorKind, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+/// Whether output can be paged: stdout must be a terminal
+pub fn can_page() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+/// Write output to stdout, through a pager when it is taller than the terminal
+///
+/// The pager is `$DATADIFF_PAGER`, `$PAGER` or `less`; `LESS` defaults to
+/// `FRX` so colours pass through and short output exits at once.
+pub fn page(output: &[u8]) -> Result<()> {
+    let height = terminal_size::terminal_size().map(|(_, height)| height.0 as usize);
+    let lines = output.iter().filter(|&&b| b == b'\n').count();
+    if height.is_none_or(|height| lines < height) {
+        return write_stdout(output);
+    }
+
+    let Some(mut command) = pager_command() else {
+        return write_stdout(output);
+    };
+    let Ok(mut child) = command.stdin(Stdio::piped()).spawn() else {
+        return write_stdout(output);
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closes its input when the user quits early
+        match stdin.write_all(output) {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+fn pager_command() -> Option<Command> {
+    let pager = std::env::var("DATADIFF_PAGER")
+        .or_else(|_| std::env::var("PAGER"))
+        .unwrap_or_else(|_| "less".to_string());
+    let mut words = pager.split_whitespace();
+    let program = words.next()?;
+    if program == "cat" {
+        return None;
+    }
+
+    let mut command = Command::new(program);
+    command.args(words);
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    Some(command)
+}
+
+fn write_stdout(output: &[u8]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(output)?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,589 @@
+//! Template output: renders the diff through Tera templates
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Context as _, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::config::InlineDiff;
+use crate::diff::{
+    DiffResult, DiffStats, DirectoryDiff, RowChange, SchemaChange, TextSpan, WorkbookDiff,
+};
+use crate::model::{Row, Table};
+
+use super::{directory_parts, workbook_parts, OutputFormatter, ReportPart};
+
+/// Built-in templates by name; the name's extension decides autoescaping
+const BUILTIN_TEMPLATES: &[(&str, &str, &str)] = &[
+    (
+        "html",
+        "builtin.html",
+        include_str!("templates/builtin.html"),
+    ),
+    (
+        "markdown",
+        "builtin.md",
+        include_str!("templates/builtin.md"),
+    ),
+    ("text", "builtin.txt", include_str!("templates/builtin.txt")),
+];
+
+/// Built-in templates for workbook and directory reports, which embed each
+/// changed part as rendered by the part template; the part template's
+/// extension picks one
+const MULTI_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "builtin-multi.html",
+        include_str!("templates/builtin-multi.html"),
+    ),
+    (
+        "builtin-multi.md",
+        include_str!("templates/builtin-multi.md"),
+    ),
+    (
+        "builtin-multi.txt",
+        include_str!("templates/builtin-multi.txt"),
+    ),
+];
+
+/// Output formatter backed by a Tera template
+pub struct TemplateOutput {
+    name: String,
+    /// Template for workbook and directory reports
+    multi_name: &'static str,
+    tera: Tera,
+    inline_diff: InlineDiff,
+}
+
+impl TemplateOutput {
+    /// Use one of the built-in templates (`html`, `markdown` or `text`)
+    pub fn builtin(name: &str) -> Result<Self> {
+        let (_, file, source) = BUILTIN_TEMPLATES
+            .iter()
+            .find(|(builtin, _, _)| *builtin == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = BUILTIN_TEMPLATES.iter().map(|(n, _, _)| *n).collect();
+                anyhow!(
+                    "Unknown built-in template '{}' (expected one of: {})",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+        Self::from_source(file, source)
+    }
+
+    /// Load a template from a file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template: {}", path.display()))?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "template".to_string());
+        Self::from_source(&name, &source)
+    }
+
+    /// Load a built-in template by name, or else a template file
+    pub fn load(spec: &str) -> Result<Self> {
+        if BUILTIN_TEMPLATES.iter().any(|(name, _, _)| *name == spec) {
+            Self::builtin(spec)
+        } else {
+            Self::from_file(Path::new(spec))
+        }
+    }
+
+    /// Compile a template from source; syntax errors report line and column
+    pub fn from_source(name: &str, source: &str) -> Result<Self> {
+        let mut tera = Tera::default();
+        tera.add_raw_template(name, source)
+            .map_err(|e| anyhow!("Invalid template '{}': {}", name, error_chain(&e)))?;
+        let extension = Path::new(name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase());
+        let multi_name = match extension.as_deref() {
+            Some("html" | "htm") => "builtin-multi.html",
+            Some("md" | "markdown") => "builtin-multi.md",
+            _ => "builtin-multi.txt",
+        };
+        let (_, multi_source) = MULTI_TEMPLATES
+            .iter()
+            .find(|(multi, _)| *multi == multi_name)
+            .expect("built-in multi-part template exists");
+        tera.add_raw_template(multi_name, multi_source)
+            .map_err(|e| anyhow!("Invalid template '{}': {}", multi_name, error_chain(&e)))?;
+        Ok(Self {
+            name: name.to_string(),
+            multi_name,
+            tera,
+            inline_diff: InlineDiff::default(),
+        })
+    }
+
+    /// Set inline diff granularity for changed string cells
+    pub fn with_inline_diff(mut self, inline_diff: InlineDiff) -> Self {
+        self.inline_diff = inline_diff;
+        self
+    }
+
+    /// Render the template with an explicit context
+    fn render_context(&self, context: &Context) -> Result<String> {
+        self.tera.render(&self.name, context).map_err(|e| {
+            let message = error_chain(&e);
+            match error_line(&message) {
+                Some(line) => anyhow!(
+                    "Error rendering template '{}' at line {}: {}",
+                    self.name,
+                    line,
+                    message
+                ),
+                None => anyhow!("Error rendering template '{}': {}", self.name, message),
+            }
+        })
+    }
+}
+
+impl TemplateOutput {
+    /// A report listing sheet or file changes and the stats of every part,
+    /// then each changed part rendered by the template
+    fn render_parts(
+        &self,
+        noun: &str,
+        changes: &[String],
+        parts: &[ReportPart],
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let mut infos = Vec::new();
+        for part in parts {
+            let report = if part.diff.has_changes() {
+                let mut report = Vec::new();
+                self.render(
+                    part.diff,
+                    part.old_table,
+                    part.new_table,
+                    &part.old_path,
+                    &part.new_path,
+                    &mut report,
+                )?;
+                Some(String::from_utf8(report)?)
+            } else {
+                None
+            };
+            infos.push(PartInfo {
+                name: part.name,
+                stats: &part.diff.stats,
+                format_changes: part.diff.format_changes.len(),
+                report,
+            });
+        }
+
+        let mut context = Context::new();
+        context.insert("old_file", &old_path.display().to_string());
+        context.insert("new_file", &new_path.display().to_string());
+        context.insert("noun", noun);
+        context.insert("changes", changes);
+        context.insert("parts", &infos);
+        let output = self.tera.render(self.multi_name, &context).map_err(|e| {
+            anyhow!(
+                "Error rendering template '{}': {}",
+                self.multi_name,
+                error_chain(&e)
+            )
+        })?;
+        writer.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Join a Tera error with its sources; parse errors carry the position there
+fn error_chain(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+/// Line of the error, from the `--> line:column` position Tera gives for
+/// syntax errors; render errors carry no position
+fn error_line(message: &str) -> Option<usize> {
+    let (_, position) = message.split_once("--> ")?;
+    let digits: String = position.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// One sheet or file of a workbook or directory report
+#[derive(Serialize)]
+struct PartInfo<'a> {
+    name: &'a str,
+    stats: &'a DiffStats,
+    format_changes: usize,
+    /// The part rendered by the template, if it changed
+    report: Option<String>,
+}
+
+/// Metadata about one of the compared tables
+#[derive(Serialize)]
+struct TableInfo<'a> {
+    path: String,
+    row_count: usize,
+    column_count: usize,
+    key_columns: Vec<&'a str>,
+    columns: Vec<ColumnInfo<'a>>,
+}
+
+#[derive(Serialize)]
+struct ColumnInfo<'a> {
+    name: &'a str,
+    index: usize,
+    #[serde(rename = "type")]
+    cell_type: String,
+}
+
+#[derive(Serialize)]
+struct SchemaChangeInfo<'a> {
+    kind: &'static str,
+    description: String,
+    change: &'a SchemaChange,
+}
+
+#[derive(Serialize)]
+struct RowChangeInfo<'a> {
+    #[serde(rename = "type")]
+    change_type: &'static str,
+    key: &'a str,
+    line: usize,
+    /// Cells of added/removed rows by column name, as display strings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cells: Option<IndexMap<String, String>>,
+    /// Cell changes of modified rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<Vec<CellChangeInfo<'a>>>,
+}
+
+#[derive(Serialize)]
+struct CellChangeInfo<'a> {
+    column: &'a str,
+    path: Option<&'a str>,
+    label: &'a str,
+    old_value: String,
+    new_value: String,
+    /// Inline diff of related string values (`kind`, `text`)
+    spans: Option<Vec<TextSpan>>,
+}
+
+fn table_info<'a>(table: &'a Table, path: &Path) -> TableInfo<'a> {
+    TableInfo {
+        path: path.display().to_string(),
+        row_count: table.row_count(),
+        column_count: table.column_count(),
+        key_columns: table
+            .key_columns
+            .iter()
+            .filter_map(|&i| table.columns.get(i))
+            .map(|c| c.name.as_str())
+            .collect(),
+        columns: table
+            .columns
+            .iter()
+            .map(|c| ColumnInfo {
+                name: &c.name,
+                index: c.index,
+                cell_type: c.inferred_type.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn row_cells(row: &Row, table: &Table) -> IndexMap<String, String> {
+    row.cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let name = table
+                .columns
+                .get(i)
+                .map(|c| c.name.clone())
+                .unwrap_or_else(|| format!("column_{}", i));
+            (name, cell.display().into_owned())
+        })
+        .collect()
+}
+
+fn schema_change_kind(change: &SchemaChange) -> &'static str {
+    match change {
+        SchemaChange::ColumnAdded { .. } => "added",
+        SchemaChange::ColumnRemoved { .. } => "removed",
+        SchemaChange::ColumnRenamed { .. } => "renamed",
+        SchemaChange::ColumnMoved { .. } => "moved",
+        SchemaChange::ColumnTypeChanged { .. } => "type_changed",
+    }
+}
+
+/// Build the template context.
+///
+/// Variables: `old_file`, `new_file`, `old_table`, `new_table`, `stats`,
+/// `omitted`, `has_changes`, `schema_changes`, `row_changes`, `added`,
+/// `removed`, `modified`, `format_changes` and `partitions` (null unless
+/// partitioned datasets were compared).
+pub fn template_context(
+    diff: &DiffResult,
+    old_table: &Table,
+    new_table: &Table,
+    old_path: &Path,
+    new_path: &Path,
+    inline_diff: InlineDiff,
+) -> Context {
+    let row_changes: Vec<RowChangeInfo> = diff
+        .row_changes
+        .iter()
+        .map(|change| match change {
+            RowChange::Added { key, row } => RowChangeInfo {
+                change_type: "added",
+                key,
+                line: row.source_line,
+                cells: Some(row_cells(row, new_table)),
+                changes: None,
+            },
+            RowChange::Removed { key, row } => RowChangeInfo {
+                change_type: "removed",
+                key,
+                line: row.source_line,
+                cells: Some(row_cells(row, old_table)),
+                changes: None,
+            },
+            RowChange::Modified {
+                key,
+                old_row,
+                changes,
+                ..
+            } => RowChangeInfo {
+                change_type: "modified",
+                key,
+                line: old_row.source_line,
+                cells: None,
+                changes: Some(
+                    changes
+                        .iter()
+                        .map(|c| CellChangeInfo {
+                            column: &c.column,
+                            path: c.path.as_deref(),
+                            label: c.label(),
+                            old_value: c.old_value.display().into_owned(),
+                            new_value: c.new_value.display().into_owned(),
+                            spans: c.inline_diff(inline_diff),
+                        })
+                        .collect(),
+                ),
+            },
+        })
+        .collect();
+    let of_type = |t: &str| -> Vec<&RowChangeInfo> {
+        row_changes.iter().filter(|c| c.change_type == t).collect()
+    };
+
+    let schema_changes: Vec<SchemaChangeInfo> = diff
+        .schema_changes
+        .iter()
+        .map(|change| SchemaChangeInfo {
+            kind: schema_change_kind(change),
+            description: change.to_string(),
+            change,
+        })
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("old_file", &old_path.display().to_string());
+    context.insert("new_file", &new_path.display().to_string());
+    context.insert("old_table", &table_info(old_table, old_path));
+    context.insert("new_table", &table_info(new_table, new_path));
+    context.insert::<DiffStats, _>("stats", &diff.stats);
+    context.insert("omitted", &diff.omitted());
+    context.insert("has_changes", &diff.has_changes());
+    context.insert("schema_changes", &schema_changes);
+    context.insert("added", &of_type("added"));
+    context.insert("removed", &of_type("removed"));
+    context.insert("modified", &of_type("modified"));
+    context.insert("row_changes", &row_changes);
+    context.insert("format_changes", &diff.format_changes);
+    context.insert("partitions", &diff.partitions);
+    context
+}
+
+impl OutputFormatter for TemplateOutput {
+    fn render(
+        &self,
+        diff: &DiffResult,
+        old_table: &Table,
+        new_table: &Table,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let context = template_context(
+            diff,
+            old_table,
+            new_table,
+            old_path,
+            new_path,
+            self.inline_diff,
+        );
+        let output = self.render_context(&context)?;
+        writer.write_all(output.as_bytes())?;
+        Ok(())
+    }
+
+    fn render_workbook(
+        &self,
+        workbook: &WorkbookDiff,
+        old_path: &Path,
+        new_path: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let changes: Vec<String> = workbook
+            .sheet_changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let parts = workbook_parts(workbook, old_path, new_path);
+        self.render_parts("Sheet", &changes, &parts, old_path, new_path, writer)
+    }
+
+    fn render_directory(
+        &self,
+        directory: &DirectoryDiff,
+        old_dir: &Path,
+        new_dir: &Path,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let changes: Vec<String> = directory
+            .file_changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let parts = directory_parts(directory, old_dir, new_dir);
+        self.render_parts("File", &changes, &parts, old_dir, new_dir, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::{CellValue, Column};
+
+    fn tables() -> (Table, Table) {
+        let mut old = Table::new(vec![Column::new("id", 0), Column::new("name", 1)]);
+        old.set_key_columns(&["id".to_string()]);
+        old.add_row(vec![CellValue::Int(1), CellValue::from("a")], 2);
+        let mut new = Table::new(vec![Column::new("id", 0), Column::new("name", 1)]);
+        new.set_key_columns(&["id".to_string()]);
+        new.add_row(vec![CellValue::Int(1), CellValue::from("b")], 2);
+        new.add_row(vec![CellValue::Int(2), CellValue::from("c")], 3);
+        (old, new)
+    }
+
+    fn render(template: &TemplateOutput) -> Result<String> {
+        let (old, new) = tables();
+        let diff = compute_diff(&old, &new, &Config::default());
+
+        let mut out = Vec::new();
+        template.render(
+            &diff,
+            &old,
+            &new,
+            Path::new("old.csv"),
+            Path::new("new.csv"),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_builtin_templates_render() {
+        for (name, _, _) in BUILTIN_TEMPLATES {
+            let output = render(&TemplateOutput::builtin(name).unwrap()).unwrap();
+            assert!(output.contains("old.csv"), "{}", name);
+            assert!(output.contains("name"), "{}", name);
+        }
+        let markdown = render(&TemplateOutput::builtin("markdown").unwrap()).unwrap();
+        assert!(markdown.contains("| 1 | name | a | b |"));
+    }
+
+    #[test]
+    fn test_workbook_report() {
+        use crate::diff::SheetDiff;
+
+        let sheet = |name: &str| {
+            let (old, new) = tables();
+            SheetDiff {
+                old_name: name.to_string(),
+                new_name: name.to_string(),
+                diff: compute_diff(&old, &new, &Config::default()),
+                old_table: old,
+                new_table: new,
+            }
+        };
+        let workbook = WorkbookDiff {
+            sheet_changes: Vec::new(),
+            sheets: vec![sheet("Orders"), sheet("Customers")],
+        };
+        let render = |template: &TemplateOutput| {
+            let mut out = Vec::new();
+            template
+                .render_workbook(
+                    &workbook,
+                    Path::new("old.xlsx"),
+                    Path::new("new.xlsx"),
+                    &mut out,
+                )
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        // One document, with each sheet's report embedded in it
+        let html = render(&TemplateOutput::builtin("html").unwrap());
+        assert!(html.starts_with("<!DOCTYPE html>"), "{}", html);
+        assert_eq!(html.matches("<html").count(), 1);
+        assert_eq!(html.matches("<iframe").count(), 2);
+        assert!(html.contains("old.xlsx[Customers]"));
+
+        let markdown = render(&TemplateOutput::builtin("markdown").unwrap());
+        assert!(markdown.starts_with("# datadiff: old.xlsx → new.xlsx"));
+        assert!(markdown.contains("| Orders | 1 | 0 | 1 | 0 |"));
+        assert!(markdown.contains("# datadiff: old.xlsx[Orders] → new.xlsx[Orders]"));
+    }
+
+    #[test]
+    fn test_custom_template_context() {
+        let source = "{{ new_table.key_columns | join(sep=\",\") }} {{ stats.rows_added }}\n\
+                      {% for row in modified %}{{ row.key }}:{{ row.changes[0].new_value }}{% endfor %}";
+        let output = render(&TemplateOutput::from_source("custom.txt", source).unwrap()).unwrap();
+        assert_eq!(output, "id 1\n1:b");
+    }
+
+    #[test]
+    fn test_errors_point_at_line() {
+        let err = TemplateOutput::from_source("bad.txt", "ok\n{% if %}\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("2:"), "{}", err);
+
+        assert_eq!(error_line(&format!("{:#}", err)), Some(2));
+
+        // Render errors name the variable but have no position to report
+        let template =
+            TemplateOutput::from_source("bad.txt", "ok\n\n{{ missing.value }}\n").unwrap();
+        let err = render(&template).unwrap_err().to_string();
+        assert!(err.contains("missing.value"), "{}", err);
+        assert!(!err.contains("line"), "{}", err);
+    }
+}
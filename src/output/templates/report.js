@@ -0,0 +1,188 @@
+(function () {
+  "use strict";
+
+  // Row records: t = type (a/r/m), k = key, l = source line, o/n = old/new
+  // cells in column order, c = indices of changed columns, d = nested changes,
+  // s = inline diffs of changed strings as [column, [[kind, text], ...]]
+  var data = JSON.parse(document.getElementById("diff-data").textContent);
+  var TYPES = { a: "added", r: "removed", m: "modified" };
+  var MARKS = { a: "+", r: "-", m: "~" };
+
+  var state = {
+    types: { a: true, r: true, m: true },
+    column: "",
+    search: "",
+    sort: "file",
+    changedOnly: false,
+    page: 0,
+    pageSize: 100
+  };
+
+  var $ = function (id) { return document.getElementById(id); };
+  var grid = $("grid");
+
+  function escape(value) {
+    return String(value)
+      .replace(/&/g, "&amp;")
+      .replace(/</g, "&lt;")
+      .replace(/>/g, "&gt;")
+      .replace(/"/g, "&quot;");
+  }
+
+  function filteredRows() {
+    var search = state.search.toLowerCase();
+    var column = state.column === "" ? -1 : Number(state.column);
+    var rows = data.rows.filter(function (row) {
+      if (!state.types[row.t]) return false;
+      if (column >= 0 && row.c.indexOf(column) < 0) return false;
+      return !search || row.k.toLowerCase().indexOf(search) >= 0;
+    });
+    if (state.sort === "key") {
+      rows = rows.slice().sort(function (x, y) {
+        return x.k.localeCompare(y.k, undefined, { numeric: true });
+      });
+    }
+    return rows;
+  }
+
+  function visibleColumns(rows) {
+    var all = data.columns.map(function (_, i) { return i; });
+    if (!state.changedOnly) return all;
+    var changed = {};
+    rows.forEach(function (row) {
+      if (row.t === "m") row.c.forEach(function (i) { changed[i] = true; });
+    });
+    return all.filter(function (i) { return changed[i]; });
+  }
+
+  // Render an inline diff: the old side shows deletions, the new side insertions
+  function spans(row, index, side) {
+    if (!row.s) return null;
+    var hidden = side === "old" ? "+" : "-";
+    var tag = side === "old" ? "del" : "ins";
+    for (var i = 0; i < row.s.length; i++) {
+      if (row.s[i][0] !== index) continue;
+      return row.s[i][1].filter(function (span) { return span[0] !== hidden; })
+        .map(function (span) {
+          return span[0] === "=" ? escape(span[1]) : "<" + tag + ">" + escape(span[1]) + "</" + tag + ">";
+        }).join("");
+    }
+    return null;
+  }
+
+  function cell(cells, index, side, row) {
+    if (!cells) return "<td class=\"" + side + "\"></td>";
+    var value = cells[index];
+    var inline = spans(row, index, side);
+    var classes = [side];
+    if (row.t === "m") classes.push(row.c.indexOf(index) >= 0 ? "changed" : "unchanged");
+    if (value === null) classes.push("null");
+    var title = "";
+    if (row.d) {
+      var name = data.columns[index];
+      var details = row.d.filter(function (d) {
+        return d[0] === name || d[0].indexOf(name + ".") === 0 || d[0].indexOf(name + "[") === 0;
+      });
+      if (details.length) {
+        title = " title=\"" + escape(details.map(function (d) {
+          return d[0] + ": " + d[1] + " → " + d[2];
+        }).join("\n")) + "\"";
+      }
+    }
+    return "<td class=\"" + classes.join(" ") + "\"" + title + ">" +
+      (inline !== null ? inline : value === null ? "null" : escape(value)) + "</td>";
+  }
+
+  function render() {
+    var rows = filteredRows();
+    var columns = visibleColumns(rows);
+    var pages = Math.max(1, Math.ceil(rows.length / state.pageSize));
+    state.page = Math.min(state.page, pages - 1);
+    var start = state.page * state.pageSize;
+    var page = rows.slice(start, start + state.pageSize);
+
+    var html = ["<thead><tr><th rowspan=\"2\"></th><th rowspan=\"2\">key</th>",
+      "<th class=\"side\" colspan=\"" + columns.length + "\">old</th><th class=\"sep\" rowspan=\"2\"></th>",
+      "<th class=\"side\" colspan=\"" + columns.length + "\">new</th></tr><tr>"];
+    var header = columns.map(function (i) { return "<th>" + escape(data.columns[i]) + "</th>"; }).join("");
+    html.push(header, header, "</tr></thead><tbody>");
+
+    if (page.length === 0) {
+      html.push("<tr><td class=\"empty\" colspan=\"" + (columns.length * 2 + 3) + "\">No matching rows</td></tr>");
+    }
+    page.forEach(function (row) {
+      html.push("<tr class=\"" + TYPES[row.t] + "\"><td class=\"type\">" + MARKS[row.t] + "</td>");
+      html.push("<td title=\"line " + row.l + "\">" + escape(row.k) + "</td>");
+      columns.forEach(function (i) { html.push(cell(row.o, i, "old", row)); });
+      html.push("<td class=\"sep\"></td>");
+      columns.forEach(function (i) { html.push(cell(row.n, i, "new", row)); });
+      html.push("</tr>");
+    });
+    html.push("</tbody>");
+    grid.innerHTML = html.join("");
+
+    $("page-info").textContent = rows.length === 0 ? "0 rows" :
+      (start + 1) + "–" + (start + page.length) + " of " + rows.length + " rows";
+    $("prev-page").disabled = state.page === 0;
+    $("next-page").disabled = state.page >= pages - 1;
+  }
+
+  function update(change) {
+    change();
+    state.page = 0;
+    render();
+  }
+
+  var columnFilter = $("column-filter");
+  data.columns.forEach(function (name, i) {
+    var option = document.createElement("option");
+    option.value = String(i);
+    option.textContent = name;
+    columnFilter.appendChild(option);
+  });
+
+  function selectColumn(value) {
+    state.column = value;
+    columnFilter.value = value;
+    Array.prototype.forEach.call(document.querySelectorAll(".heat"), function (el) {
+      el.classList.toggle("active", el.getAttribute("data-column") === value);
+    });
+  }
+
+  ["a", "r", "m"].forEach(function (type) {
+    $("show-" + TYPES[type]).addEventListener("change", function (e) {
+      update(function () { state.types[type] = e.target.checked; });
+    });
+  });
+  columnFilter.addEventListener("change", function (e) {
+    update(function () { selectColumn(e.target.value); });
+  });
+  Array.prototype.forEach.call(document.querySelectorAll(".heat"), function (el) {
+    el.addEventListener("click", function () {
+      var value = el.getAttribute("data-column");
+      update(function () { selectColumn(state.column === value ? "" : value); });
+    });
+  });
+  $("key-search").addEventListener("input", function (e) {
+    update(function () { state.search = e.target.value; });
+  });
+  $("sort-order").addEventListener("change", function (e) {
+    update(function () { state.sort = e.target.value; });
+  });
+  $("changed-columns").addEventListener("change", function (e) {
+    update(function () { state.changedOnly = e.target.checked; });
+  });
+  $("page-size").addEventListener("change", function (e) {
+    update(function () { state.pageSize = Number(e.target.value); });
+  });
+  $("prev-page").addEventListener("click", function () {
+    state.page -= 1;
+    render();
+  });
+  $("next-page").addEventListener("click", function () {
+    state.page += 1;
+    render();
+  });
+
+  render();
+})();
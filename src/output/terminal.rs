@@ -1,31 +1,100 @@
 //! Colored terminal output
 
+use std::io::IsTerminal;
 use std::io::Write;
 use std::path::Path;
 
 use anyhow::Result;
-use termcolor::ColorChoice;
+use rustc_hash::FxHashSet;
+use termcolor::{Ansi, Color, ColorChoice, ColorSpec, WriteColor};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::diff::{cell_diff::percentage_change, CellChange, DiffResult, SchemaChange};
-use crate::model::{Row, Table};
+use crate::config::{InlineDiff, RowLayout};
+use crate::diff::{
+    cell_diff::percentage_change, CellChange, DiffResult, FormatChange, PartitionDiff,
+    SchemaChange, SpanKind, TextSpan,
+};
+use crate::model::{CellValue, Row, Table};
 
 use super::OutputFormatter;
 
+/// Narrowest a column is shrunk to before the auto layout switches to records
+const MIN_AUTO_COLUMN_WIDTH: usize = 8;
+/// Narrowest a column is shrunk to in table layout
+const MIN_COLUMN_WIDTH: usize = 3;
+/// Widest column label in record layout
+const MAX_LABEL_WIDTH: usize = 30;
+
 /// Terminal output with colors
 pub struct TerminalOutput {
-    #[allow(dead_code)]
-    color_choice: ColorChoice,
+    color: bool,
+    inline_diff: InlineDiff,
+    layout: RowLayout,
+    width: Option<usize>,
+    max_cell_width: usize,
+    changed_columns_only: bool,
 }
 
 impl TerminalOutput {
     pub fn new() -> Self {
         Self {
-            color_choice: ColorChoice::Auto,
+            color: color_enabled(ColorChoice::Auto),
+            inline_diff: InlineDiff::default(),
+            layout: RowLayout::default(),
+            width: None,
+            max_cell_width: 40,
+            changed_columns_only: false,
         }
     }
 
     pub fn with_color_choice(color_choice: ColorChoice) -> Self {
-        Self { color_choice }
+        Self {
+            color: color_enabled(color_choice),
+            ..Self::new()
+        }
+    }
+
+    /// Set inline diff granularity for changed string cells
+    pub fn with_inline_diff(mut self, inline_diff: InlineDiff) -> Self {
+        self.inline_diff = inline_diff;
+        self
+    }
+
+    /// Set the row layout
+    pub fn with_layout(mut self, layout: RowLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Fit tables to a terminal this many columns wide
+    pub fn with_width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Truncate table cells to this many characters (0 = no limit)
+    pub fn with_max_cell_width(mut self, width: usize) -> Self {
+        self.max_cell_width = width;
+        self
+    }
+
+    /// Show only key columns and columns with changes
+    pub fn with_changed_columns_only(mut self, changed_only: bool) -> Self {
+        self.changed_columns_only = changed_only;
+        self
+    }
+
+    /// Wrap text in ANSI colour codes when colour is on
+    fn paint(&self, text: &str, spec: &ColorSpec) -> String {
+        if !self.color || text.is_empty() {
+            return text.to_string();
+        }
+        let mut out = Ansi::new(Vec::new());
+        // Writes to a Vec cannot fail
+        let _ = out.set_color(spec);
+        let _ = out.write_all(text.as_bytes());
+        let _ = out.reset();
+        String::from_utf8_lossy(&out.into_inner()).into_owned()
     }
 
     fn write_header(&self, writer: &mut dyn Write, old_path: &Path, new_path: &Path) -> Result<()> {
@@ -46,7 +115,7 @@ impl TerminalOutput {
             return Ok(());
         }
 
-        writeln!(writer, "Schema Changes:")?;
+        writeln!(writer, "{}", self.paint("Schema Changes:", &bold()))?;
         for change in changes {
             writeln!(writer, "  {}", change)?;
         }
@@ -54,83 +123,245 @@ impl TerminalOutput {
         Ok(())
     }
 
+    fn write_format_changes(&self, changes: &[FormatChange], writer: &mut dyn Write) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(writer, "{}", self.paint("Formatting Changes:", &bold()))?;
+        for change in changes {
+            writeln!(writer, "  {}", change)?;
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    fn write_partitions(&self, partitions: Option<&PartitionDiff>, writer: &mut dyn Write) -> Result<()> {
+        let Some(partitions) = partitions else {
+            return Ok(());
+        };
+
+        writeln!(writer, "{}", self.paint("Changed Partitions:", &bold()))?;
+        for change in &partitions.changes {
+            writeln!(writer, "  {}", change)?;
+        }
+        if partitions.unchanged > 0 {
+            writeln!(writer, "  ({} unchanged partitions skipped)", partitions.unchanged)?;
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+
     fn write_summary(&self, diff: &DiffResult, writer: &mut dyn Write) -> Result<()> {
         writeln!(
             writer,
-            "Summary: +{} added, -{} removed, ~{} modified (out of {} → {} rows)",
-            diff.stats.rows_added,
-            diff.stats.rows_removed,
-            diff.stats.rows_modified,
+            "Summary: {}, {}, {} (out of {} → {} rows)",
+            self.paint(&format!("+{} added", diff.stats.rows_added), &fg(Color::Green)),
+            self.paint(&format!("-{} removed", diff.stats.rows_removed), &fg(Color::Red)),
+            self.paint(&format!("~{} modified", diff.stats.rows_modified), &fg(Color::Yellow)),
             diff.stats.old_row_count,
             diff.stats.new_row_count
         )?;
+        let omitted = diff.omitted().total();
+        if omitted > 0 {
+            let listed = diff.row_changes.len();
+            writeln!(writer, "Showing {} of {} changed rows", listed, listed + omitted)?;
+        }
         writeln!(writer)?;
         Ok(())
     }
 
-    fn write_added_rows(&self, diff: &DiffResult, table: &Table, writer: &mut dyn Write) -> Result<()> {
+    fn write_added_rows(
+        &self,
+        diff: &DiffResult,
+        table: &Table,
+        focus: &FxHashSet<&str>,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
         let added: Vec<_> = diff.added_rows().collect();
-        if added.is_empty() {
+        let omitted = diff.omitted().added;
+        self.write_rows_section("Added Rows:", "+", Color::Green, &added, omitted, table, focus, writer)
+    }
+
+    fn write_removed_rows(
+        &self,
+        diff: &DiffResult,
+        table: &Table,
+        focus: &FxHashSet<&str>,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let removed: Vec<_> = diff.removed_rows().collect();
+        let omitted = diff.omitted().removed;
+        self.write_rows_section("Removed Rows:", "-", Color::Red, &removed, omitted, table, focus, writer)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_rows_section(
+        &self,
+        title: &str,
+        marker: &str,
+        color: Color,
+        rows: &[&Row],
+        omitted: usize,
+        table: &Table,
+        focus: &FxHashSet<&str>,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if rows.is_empty() && omitted == 0 {
             return Ok(());
         }
 
-        writeln!(writer, "Added Rows:")?;
-        self.write_rows_table(&added, table, writer)?;
+        writeln!(writer, "{}", self.paint(title, &bold()))?;
+        if rows.is_empty() {
+            self.write_omitted(title, omitted, writer)?;
+            writeln!(writer)?;
+            return Ok(());
+        }
+        let columns = self.visible_columns(table, focus);
+        let headers: Vec<String> = columns
+            .iter()
+            .map(|&i| escape_control(&table.columns[i].name))
+            .collect();
+        let data: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| columns.iter().map(|&i| cell_text(row.get(i))).collect())
+            .collect();
+
+        match self.table_widths(&headers, &data) {
+            Some(widths) => {
+                let spec = fg(color);
+                let display = build_table(&headers, &data, &widths, |text| self.paint(text, &spec));
+                write!(writer, "{}", display)?;
+            }
+            None => self.write_records(marker, color, rows, &headers, &data, writer)?,
+        }
+        self.write_omitted(title, omitted, writer)?;
         writeln!(writer)?;
         Ok(())
     }
 
-    fn write_removed_rows(&self, diff: &DiffResult, table: &Table, writer: &mut dyn Write) -> Result<()> {
-        let removed: Vec<_> = diff.removed_rows().collect();
-        if removed.is_empty() {
-            return Ok(());
+    /// Trailer for rows left out by output limits, e.g. "… and 3 more added rows"
+    fn write_omitted(&self, title: &str, omitted: usize, writer: &mut dyn Write) -> Result<()> {
+        if omitted > 0 {
+            let noun = title.trim_end_matches(':').to_lowercase();
+            let line = format!("  … and {} more {}", omitted, noun);
+            writeln!(writer, "{}", self.paint(&line, &dimmed()))?;
         }
-
-        writeln!(writer, "Removed Rows:")?;
-        self.write_rows_table(&removed, table, writer)?;
-        writeln!(writer)?;
         Ok(())
     }
 
-    fn write_rows_table(&self, rows: &[&Row], table: &Table, writer: &mut dyn Write) -> Result<()> {
-        if rows.is_empty() {
-            return Ok(());
+    /// Indices of the columns to show: all, or key columns plus `focus`
+    fn visible_columns(&self, table: &Table, focus: &FxHashSet<&str>) -> Vec<usize> {
+        (0..table.columns.len())
+            .filter(|&i| {
+                !self.changed_columns_only
+                    || table.key_columns.contains(&i)
+                    || focus.contains(table.columns[i].name.as_str())
+            })
+            .collect()
+    }
+
+    /// Column widths for a table, or None when rows should be shown as records
+    fn table_widths(&self, headers: &[String], data: &[Vec<String>]) -> Option<Vec<usize>> {
+        if self.layout == RowLayout::Vertical || headers.is_empty() {
+            return None;
         }
 
-        // Build table data
-        let headers: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
-        
-        let mut table_data: Vec<Vec<String>> = Vec::new();
-        table_data.push(headers);
-        
-        for row in rows {
-            let row_data: Vec<String> = row
-                .cells
-                .iter()
-                .map(|c| format!("{}", c.display()))
-                .collect();
-            table_data.push(row_data);
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.width()).collect();
+        for row in data {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        if self.max_cell_width > 0 {
+            for width in &mut widths {
+                *width = (*width).min(self.max_cell_width);
+            }
         }
 
-        // Use tabled for formatting
-        let display = build_table(&table_data);
-        writeln!(writer, "{}", display)?;
+        let Some(terminal_width) = self.width else {
+            return Some(widths);
+        };
+        // Each column takes its width plus " │" and a space; one more for the left border
+        let budget = terminal_width.saturating_sub(3 * widths.len() + 1);
+        match self.layout {
+            RowLayout::Auto => shrink_widths(&mut widths, budget, MIN_AUTO_COLUMN_WIDTH).then_some(widths),
+            _ => {
+                // Too wide even at minimum width: let the lines overflow
+                shrink_widths(&mut widths, budget, MIN_COLUMN_WIDTH);
+                Some(widths)
+            }
+        }
+    }
+
+    /// Write rows as records: a marked key line, then one line per column
+    fn write_records(
+        &self,
+        marker: &str,
+        color: Color,
+        rows: &[&Row],
+        headers: &[String],
+        data: &[Vec<String>],
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let label_width = headers
+            .iter()
+            .map(|h| h.width())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_LABEL_WIDTH);
+        // Values use the rest of the line after "    label │ "
+        let value_width = self
+            .width
+            .map_or(0, |w| w.saturating_sub(label_width + 7).max(MIN_COLUMN_WIDTH));
+        let mut key_spec = fg(color);
+        key_spec.set_bold(true);
+
+        for (row, cells) in rows.iter().zip(data) {
+            writeln!(writer, "  {}", self.paint(&format!("{} {}", marker, row.key), &key_spec))?;
+            for (header, cell) in headers.iter().zip(cells) {
+                writeln!(
+                    writer,
+                    "    {} │ {}",
+                    pad(&truncate(header, label_width), label_width),
+                    truncate(cell, value_width)
+                )?;
+            }
+        }
         Ok(())
     }
 
-    fn write_modified_rows(&self, diff: &DiffResult, writer: &mut dyn Write) -> Result<()> {
+    fn write_modified_rows(&self, diff: &DiffResult, table: &Table, writer: &mut dyn Write) -> Result<()> {
         let modified: Vec<_> = diff.modified_rows().collect();
-        if modified.is_empty() {
+        let omitted = diff.omitted().modified;
+        if modified.is_empty() && omitted == 0 {
             return Ok(());
         }
 
-        writeln!(writer, "Modified Rows:")?;
-        for (old_row, _new_row, changes) in modified {
-            writeln!(writer, "  {}:", old_row.key)?;
-            for change in changes {
-                self.write_cell_change(change, writer)?;
+        // Records show unchanged columns too, unless only changes were asked for
+        let full_records = self.layout == RowLayout::Vertical && !self.changed_columns_only;
+        writeln!(writer, "{}", self.paint("Modified Rows:", &bold()))?;
+        for (_old_row, new_row, changes) in modified {
+            writeln!(writer, "  {}:", self.paint(&new_row.key.to_string(), &fg(Color::Yellow)))?;
+            if !full_records {
+                for change in changes {
+                    self.write_cell_change(change, writer)?;
+                }
+                continue;
+            }
+            for (index, column) in table.columns.iter().enumerate() {
+                let mut column_changes = changes.iter().filter(|c| c.column == column.name).peekable();
+                if column_changes.peek().is_none() {
+                    let value = truncate(&cell_text(new_row.get(index)), self.max_cell_width);
+                    let line = format!("    {}: {}", escape_control(&column.name), value);
+                    writeln!(writer, "{}", self.paint(&line, &dimmed()))?;
+                }
+                for change in column_changes {
+                    self.write_cell_change(change, writer)?;
+                }
             }
         }
+        self.write_omitted("Modified Rows:", omitted, writer)?;
         writeln!(writer)?;
         Ok(())
     }
@@ -141,16 +372,42 @@ impl TerminalOutput {
             .map(|p| format!(" ({:+.1}%)", p))
             .unwrap_or_default();
 
-        writeln!(
-            writer,
-            "    {}: {} → {}{}",
-            change.column,
-            change.old_value.display(),
-            change.new_value.display(),
-            pct_str
-        )?;
+        let value = match change.inline_diff(self.inline_diff) {
+            Some(spans) => self.format_spans(&spans),
+            None => format!(
+                "{} → {}",
+                self.paint(&change.old_value.display(), &fg(Color::Red)),
+                self.paint(&change.new_value.display(), &fg(Color::Green))
+            ),
+        };
+        writeln!(writer, "    {}: {}{}", change.label(), value, pct_str)?;
         Ok(())
     }
+
+    /// Render an inline diff: coloured, or with `[-deleted-]{+inserted+}`
+    /// markers when colour is off
+    fn format_spans(&self, spans: &[TextSpan]) -> String {
+        spans
+            .iter()
+            .map(|span| match (span.kind, self.color) {
+                (SpanKind::Equal, _) => span.text.clone(),
+                (SpanKind::Delete, false) => format!("[-{}-]", span.text),
+                (SpanKind::Insert, false) => format!("{{+{}+}}", span.text),
+                (SpanKind::Delete, true) => self.paint(
+                    &span.text,
+                    ColorSpec::new()
+                        .set_fg(Some(Color::Red))
+                        .set_strikethrough(true),
+                ),
+                (SpanKind::Insert, true) => self.paint(
+                    &span.text,
+                    ColorSpec::new()
+                        .set_fg(Some(Color::Green))
+                        .set_underline(true),
+                ),
+            })
+            .collect()
+    }
 }
 
 impl Default for TerminalOutput {
@@ -176,85 +433,246 @@ impl OutputFormatter for TerminalOutput {
             return Ok(());
         }
 
+        let focus = changed_columns(diff);
         self.write_schema_changes(&diff.schema_changes, writer)?;
         self.write_summary(diff, writer)?;
-        self.write_added_rows(diff, new_table, writer)?;
-        self.write_removed_rows(diff, old_table, writer)?;
-        self.write_modified_rows(diff, writer)?;
+        self.write_partitions(diff.partitions.as_ref(), writer)?;
+        self.write_added_rows(diff, new_table, &focus, writer)?;
+        self.write_removed_rows(diff, old_table, &focus, writer)?;
+        self.write_modified_rows(diff, new_table, writer)?;
+        self.write_format_changes(&diff.format_changes, writer)?;
 
         Ok(())
     }
 }
 
-/// Build a formatted table from data
-fn build_table(data: &[Vec<String>]) -> String {
-    if data.is_empty() || data[0].is_empty() {
-        return String::new();
+/// Width of the terminal on stdout, if stdout is a terminal
+pub fn detect_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
     }
+    terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
+}
 
-    let col_count = data[0].len();
-    
-    // Build column-aligned output manually
-    let mut col_widths: Vec<usize> = vec![0; col_count];
-    for row in data {
-        for (i, cell) in row.iter().enumerate() {
-            if i < col_widths.len() {
-                col_widths[i] = col_widths[i].max(cell.len());
-            }
+/// Whether to colour output. `Auto` colours only a terminal, and honours
+/// `NO_COLOR` (<https://no-color.org>)
+fn color_enabled(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always | ColorChoice::AlwaysAnsi => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal()
+                && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
         }
     }
+}
 
-    let mut output = String::new();
-    
-    // Top border
-    output.push('┌');
-    for (i, width) in col_widths.iter().enumerate() {
-        output.push_str(&"─".repeat(*width + 2));
-        if i < col_widths.len() - 1 {
-            output.push('┬');
+/// Columns with modified cells or schema changes
+fn changed_columns(diff: &DiffResult) -> FxHashSet<&str> {
+    let mut columns: FxHashSet<&str> = diff
+        .modified_rows()
+        .flat_map(|(_, _, changes)| changes.iter().map(|c| c.column.as_str()))
+        .collect();
+    for change in &diff.schema_changes {
+        match change {
+            SchemaChange::ColumnAdded { name, .. }
+            | SchemaChange::ColumnRemoved { name, .. }
+            | SchemaChange::ColumnTypeChanged { name, .. } => {
+                columns.insert(name);
+            }
+            SchemaChange::ColumnRenamed {
+                old_name, new_name, ..
+            } => {
+                columns.insert(old_name);
+                columns.insert(new_name);
+            }
+            SchemaChange::ColumnMoved { .. } => {}
         }
     }
-    output.push_str("┐\n");
+    columns
+}
+
+fn bold() -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    spec.set_bold(true);
+    spec
+}
+
+fn dimmed() -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    spec.set_dimmed(true);
+    spec
+}
+
+fn fg(color: Color) -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    spec.set_fg(Some(color));
+    spec
+}
 
-    // Header row
-    if let Some(header) = data.first() {
-        output.push('│');
-        for (i, cell) in header.iter().enumerate() {
-            let width = col_widths.get(i).copied().unwrap_or(0);
-            output.push_str(&format!(" {:width$} │", cell, width = width));
+/// A cell as one line of text
+fn cell_text(value: Option<&CellValue>) -> String {
+    value.map(|v| escape_control(&v.display())).unwrap_or_default()
+}
+
+/// Escape line breaks and tabs so a value stays on one line
+fn escape_control(text: &str) -> String {
+    if !text.contains(['\n', '\r', '\t']) {
+        return text.to_string();
+    }
+    text.replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+/// Cut text to a display width, marking the cut with `…` (0 = no limit)
+fn truncate(text: &str, width: usize) -> String {
+    if width == 0 || text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width - 1 {
+            break;
         }
-        output.push('\n');
+        out.push(c);
+        used += w;
     }
+    out.push('…');
+    out
+}
 
-    // Header separator
-    output.push('├');
-    for (i, width) in col_widths.iter().enumerate() {
-        output.push_str(&"─".repeat(*width + 2));
-        if i < col_widths.len() - 1 {
-            output.push('┼');
+/// Pad text with spaces to a display width
+fn pad(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// Narrow the widest columns until the total fits the budget; false if that
+/// would take a column below `min`
+fn shrink_widths(widths: &mut [usize], budget: usize, min: usize) -> bool {
+    let mut total: usize = widths.iter().sum();
+    while total > budget {
+        let Some(widest) = widths.iter_mut().max() else {
+            return true;
+        };
+        if *widest <= min {
+            return false;
         }
+        *widest -= 1;
+        total -= 1;
     }
-    output.push_str("┤\n");
+    true
+}
 
-    // Data rows
-    for row in data.iter().skip(1) {
-        output.push('│');
-        for (i, cell) in row.iter().enumerate() {
-            let width = col_widths.get(i).copied().unwrap_or(0);
-            output.push_str(&format!(" {:width$} │", cell, width = width));
+/// Build a bordered table; `paint` styles the cells of data rows
+fn build_table(
+    headers: &[String],
+    data: &[Vec<String>],
+    widths: &[usize],
+    paint: impl Fn(&str) -> String,
+) -> String {
+    let border = |left: char, mid: char, right: char| {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}\n", left, segments.join(&mid.to_string()), right)
+    };
+    let line = |cells: &[String], styled: bool| {
+        let mut output = String::from("│");
+        for (cell, &width) in cells.iter().zip(widths) {
+            let text = pad(&truncate(cell, width), width);
+            let text = if styled { paint(&text) } else { text };
+            output.push_str(&format!(" {} │", text));
         }
         output.push('\n');
+        output
+    };
+
+    let mut output = border('┌', '┬', '┐');
+    output.push_str(&line(headers, false));
+    output.push_str(&border('├', '┼', '┤'));
+    for row in data {
+        output.push_str(&line(row, true));
+    }
+    output.push_str(&border('└', '┴', '┘'));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::Column;
+
+    fn table(rows: &[(i64, &str, &str)]) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("id", 0),
+            Column::new("name", 1),
+            Column::new("notes", 2),
+        ]);
+        table.set_key_columns(&["id".to_string()]);
+        for (i, (id, name, notes)) in rows.iter().enumerate() {
+            table.add_row(
+                vec![CellValue::Int(*id), CellValue::from(*name), CellValue::from(*notes)],
+                i + 2,
+            );
+        }
+        table
     }
 
-    // Bottom border
-    output.push('└');
-    for (i, width) in col_widths.iter().enumerate() {
-        output.push_str(&"─".repeat(*width + 2));
-        if i < col_widths.len() - 1 {
-            output.push('┴');
+    fn render(output: TerminalOutput, old: &Table, new: &Table) -> String {
+        let diff = compute_diff(old, new, &Config::default());
+        let mut out = Vec::new();
+        output
+            .render(&diff, old, new, Path::new("a.csv"), Path::new("b.csv"), &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_table_fits_terminal_width() {
+        let old = table(&[(1, "a", "x")]);
+        let long = "a long note that will not fit\nacross two lines";
+        let new = table(&[(1, "a", "x"), (2, "Bob", long)]);
+
+        let out = render(
+            TerminalOutput::with_color_choice(ColorChoice::Never).with_width(Some(30)),
+            &old,
+            &new,
+        );
+        let table_lines: Vec<&str> = out.lines().filter(|l| l.starts_with('│')).collect();
+        assert_eq!(table_lines.len(), 2);
+        for line in &table_lines {
+            assert_eq!(line.width(), 30, "{line}");
         }
+        assert!(table_lines[1].contains("│ a long note t… │"));
+        assert!(!out.contains('\u{1b}'));
     }
-    output.push_str("┘\n");
 
-    output
+    #[test]
+    fn test_records_for_wide_rows_and_changed_columns() {
+        let old = table(&[(1, "a", "x"), (2, "b", "y")]);
+        let new = table(&[(1, "a", "a long note"), (3, "c", "z")]);
+
+        // Three columns can't fit in 20 characters, so rows become records
+        let out = render(
+            TerminalOutput::with_color_choice(ColorChoice::Never).with_width(Some(20)),
+            &old,
+            &new,
+        );
+        assert!(out.contains("  + 3\n    id    │ 3\n    name  │ c\n    notes │ z\n"));
+
+        // Only the key and the changed column
+        let out = render(
+            TerminalOutput::with_color_choice(ColorChoice::Never)
+                .with_layout(RowLayout::Vertical)
+                .with_changed_columns_only(true),
+            &old,
+            &new,
+        );
+        assert!(out.contains("  - 2\n    id    │ 2\n    notes │ y\n"));
+        assert!(out.contains("  1:\n    notes: x → a long note\n"));
+    }
 }
@@ -1,18 +1,22 @@
 //! Git-style unified diff output
+//!
+//! The diff is computed against each table rendered as RFC 4180 CSV: line 1
+//! is the header and rows follow in table order, so the output can be applied
+//! with `patch` to such a rendering.
 
 use std::io::Write;
 use std::path::Path;
 
 use anyhow::Result;
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::diff::{DiffResult, RowChange};
-use crate::model::Table;
+use crate::diff::{CellChange, DiffResult, RowChange};
+use crate::model::{CellValue, Table};
 
 use super::OutputFormatter;
 
 /// Unified diff output (Git-style)
 pub struct UnifiedOutput {
-    #[allow(dead_code)]
     context_lines: usize,
 }
 
@@ -32,6 +36,196 @@ impl Default for UnifiedOutput {
     }
 }
 
+/// One step of the edit script. Indices are records: 0 is the header, `k + 1` is row `k`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal(usize, usize),
+    Change(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+impl Op {
+    fn is_change(self) -> bool {
+        !matches!(self, Op::Equal(..))
+    }
+}
+
+/// A table viewed as CSV records
+struct Records<'a> {
+    table: &'a Table,
+    /// First line of each record (1-based)
+    starts: Vec<usize>,
+}
+
+impl<'a> Records<'a> {
+    fn new(table: &'a Table) -> Self {
+        let header_lines = 1 + table
+            .columns
+            .iter()
+            .map(|c| c.name.matches('\n').count())
+            .sum::<usize>();
+        let mut starts = Vec::with_capacity(table.rows.len() + 2);
+        starts.push(1);
+        let mut next = 1 + header_lines;
+        for row in &table.rows {
+            starts.push(next);
+            next += 1 + row
+                .cells
+                .iter()
+                .map(|c| match c {
+                    CellValue::String(s) => s.matches('\n').count(),
+                    _ => 0,
+                })
+                .sum::<usize>();
+        }
+        starts.push(next);
+        Self { table, starts }
+    }
+
+    fn line_count(&self, record: usize) -> usize {
+        self.starts[record + 1] - self.starts[record]
+    }
+
+    /// The record's CSV text, one entry per physical line
+    fn lines(&self, record: usize) -> Result<Vec<String>> {
+        let fields: Vec<String> = if record == 0 {
+            self.table.columns.iter().map(|c| c.name.clone()).collect()
+        } else {
+            self.table.rows[record - 1]
+                .cells
+                .iter()
+                .map(|c| match c {
+                    CellValue::Null => String::new(),
+                    other => other.display().into_owned(),
+                })
+                .collect()
+        };
+        Ok(csv_record(&fields)?.split('\n').map(str::to_string).collect())
+    }
+}
+
+/// Write fields as one RFC 4180 record, quoting only where needed
+fn csv_record(fields: &[String]) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    writer.write_record(fields)?;
+    let mut bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    bytes.pop();
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Partner row in the new table for each old row, matched by key
+fn match_partners(old_table: &Table, new_table: &Table) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let mut old_partner = vec![None; old_table.rows.len()];
+    let mut new_partner = vec![None; new_table.rows.len()];
+    for (i, old_row) in old_table.rows.iter().enumerate() {
+        if let Some(&j) = new_table.row_index.get(&old_row.key_hash) {
+            if new_partner[j].is_none() && new_table.rows[j].key == old_row.key {
+                old_partner[i] = Some(j);
+                new_partner[j] = Some(i);
+            }
+        }
+    }
+    (old_partner, new_partner)
+}
+
+/// Whether two matched rows differ in any shared column
+fn cells_differ(old_table: &Table, new_table: &Table, old: usize, new: usize) -> bool {
+    let (old_row, new_row) = (&old_table.rows[old], &new_table.rows[new]);
+    old_table.columns.iter().enumerate().any(|(i, column)| {
+        new_table
+            .column_index(&column.name)
+            .is_some_and(|j| old_row.cells.get(i) != new_row.cells.get(j))
+    })
+}
+
+/// Build the edit script from old to new, walking both tables in order.
+///
+/// Matched rows that moved are shown as a deletion and an insertion; the
+/// side whose partner is nearer is kept in place. When output limits left
+/// modified rows out of the diff, rows are also compared cell by cell so
+/// unlisted changes are never shown as context.
+fn edit_script(
+    old_table: &Table,
+    new_table: &Table,
+    modified: &FxHashMap<u64, &[CellChange]>,
+    limited: bool,
+) -> Vec<Op> {
+    let (old_partner, new_partner) = match_partners(old_table, new_table);
+    let header_equal = old_table
+        .columns
+        .iter()
+        .map(|c| &c.name)
+        .eq(new_table.columns.iter().map(|c| &c.name));
+
+    let mut ops = vec![if header_equal {
+        Op::Equal(0, 0)
+    } else {
+        Op::Change(0, 0)
+    }];
+    let mut moved_old = FxHashSet::default();
+    let mut moved_new = FxHashSet::default();
+    let (mut i, mut j) = (0, 0);
+    let (old_len, new_len) = (old_table.rows.len(), new_table.rows.len());
+
+    while i < old_len || j < new_len {
+        let old_unmatched = i < old_len
+            && (j >= new_len || old_partner[i].is_none() || moved_old.contains(&i));
+        let new_unmatched = j < new_len
+            && (i >= old_len || new_partner[j].is_none() || moved_new.contains(&j));
+        if old_unmatched {
+            ops.push(Op::Delete(i + 1));
+            i += 1;
+        } else if new_unmatched {
+            ops.push(Op::Insert(j + 1));
+            j += 1;
+        } else {
+            let p = old_partner[i].unwrap_or(j);
+            let q = new_partner[j].unwrap_or(i);
+            if p == j {
+                let changed = modified.contains_key(&old_table.rows[i].key_hash)
+                    || (limited && cells_differ(old_table, new_table, i, j));
+                ops.push(if changed {
+                    Op::Change(i + 1, j + 1)
+                } else {
+                    Op::Equal(i + 1, j + 1)
+                });
+                i += 1;
+                j += 1;
+            } else if p.saturating_sub(j) <= q.saturating_sub(i) {
+                ops.push(Op::Delete(i + 1));
+                moved_new.insert(p);
+                i += 1;
+            } else {
+                ops.push(Op::Insert(j + 1));
+                moved_old.insert(q);
+                j += 1;
+            }
+        }
+    }
+    ops
+}
+
+/// Group changes into hunks of op ranges, merging hunks whose context
+/// overlaps. Hunks are centred on the ops `shown` accepts.
+fn hunk_ranges(ops: &[Op], context: usize, shown: impl Fn(Op) -> bool) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (idx, &op) in ops.iter().enumerate() {
+        if !op.is_change() || !shown(op) {
+            continue;
+        }
+        let start = idx.saturating_sub(context);
+        let end = (idx + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
 impl OutputFormatter for UnifiedOutput {
     fn render(
         &self,
@@ -50,53 +244,190 @@ impl OutputFormatter for UnifiedOutput {
             return Ok(());
         }
 
-        // Output header row if different
-        let old_headers: Vec<_> = old_table.columns.iter().map(|c| c.name.as_str()).collect();
-        let new_headers: Vec<_> = new_table.columns.iter().map(|c| c.name.as_str()).collect();
-
-        if old_headers != new_headers {
-            writeln!(writer, "@@ -1 +1 @@ header")?;
-            writeln!(writer, "-{}", old_headers.join(","))?;
-            writeln!(writer, "+{}", new_headers.join(","))?;
-        }
-
-        // Output changes
+        let modified: FxHashMap<u64, &[CellChange]> = diff
+            .row_changes
+            .iter()
+            .filter_map(|change| match change {
+                RowChange::Modified {
+                    old_row, changes, ..
+                } => Some((old_row.key_hash, changes.as_slice())),
+                _ => None,
+            })
+            .collect();
+        // With output limits, hunks are only built around listed changes
+        let omitted = diff.omitted().total();
+        let mut listed_old = FxHashSet::default();
+        let mut listed_new = FxHashSet::default();
         for change in &diff.row_changes {
             match change {
                 RowChange::Added { row, .. } => {
-                    let cells: Vec<_> = row.cells.iter().map(|c| c.display().to_string()).collect();
-                    writeln!(writer, "@@ +{} @@", row.source_line)?;
-                    writeln!(writer, "+{}", cells.join(","))?;
+                    listed_new.insert(row.key_hash);
                 }
                 RowChange::Removed { row, .. } => {
-                    let cells: Vec<_> = row.cells.iter().map(|c| c.display().to_string()).collect();
-                    writeln!(writer, "@@ -{} @@", row.source_line)?;
-                    writeln!(writer, "-{}", cells.join(","))?;
+                    listed_old.insert(row.key_hash);
                 }
                 RowChange::Modified {
                     old_row, new_row, ..
                 } => {
-                    let old_cells: Vec<_> = old_row
-                        .cells
-                        .iter()
-                        .map(|c| c.display().to_string())
-                        .collect();
-                    let new_cells: Vec<_> = new_row
-                        .cells
-                        .iter()
-                        .map(|c| c.display().to_string())
-                        .collect();
-                    writeln!(
-                        writer,
-                        "@@ -{},{} +{},{} @@",
-                        old_row.source_line, 1, new_row.source_line, 1
-                    )?;
-                    writeln!(writer, "-{}", old_cells.join(","))?;
-                    writeln!(writer, "+{}", new_cells.join(","))?;
+                    listed_old.insert(old_row.key_hash);
+                    listed_new.insert(new_row.key_hash);
+                }
+            }
+        }
+        let shown = |op: Op| {
+            omitted == 0
+                || match op {
+                    Op::Change(0, _) => true,
+                    Op::Change(o, _) | Op::Delete(o) => {
+                        listed_old.contains(&old_table.rows[o - 1].key_hash)
+                    }
+                    Op::Insert(n) => listed_new.contains(&new_table.rows[n - 1].key_hash),
+                    Op::Equal(..) => false,
+                }
+        };
+        let ops = edit_script(old_table, new_table, &modified, omitted > 0);
+        let old_records = Records::new(old_table);
+        let new_records = Records::new(new_table);
+
+        // Line positions before each op
+        let mut positions = Vec::with_capacity(ops.len() + 1);
+        let (mut old_line, mut new_line) = (1, 1);
+        for op in &ops {
+            positions.push((old_line, new_line));
+            match *op {
+                Op::Equal(o, n) | Op::Change(o, n) => {
+                    old_line += old_records.line_count(o);
+                    new_line += new_records.line_count(n);
+                }
+                Op::Delete(o) => old_line += old_records.line_count(o),
+                Op::Insert(n) => new_line += new_records.line_count(n),
+            }
+        }
+        positions.push((old_line, new_line));
+
+        for (start, end) in hunk_ranges(&ops, self.context_lines, shown) {
+            let (old_start, new_start) = positions[start];
+            let old_count = positions[end].0 - old_start;
+            let new_count = positions[end].1 - new_start;
+
+            // Changed columns in this hunk, in the section heading slot
+            let mut columns: Vec<&str> = Vec::new();
+            for op in &ops[start..end] {
+                match *op {
+                    Op::Change(0, _) => columns.push("header"),
+                    Op::Change(o, _) => {
+                        let key_hash = old_table.rows[o - 1].key_hash;
+                        for change in modified.get(&key_hash).copied().unwrap_or(&[]) {
+                            if !columns.contains(&change.column.as_str()) {
+                                columns.push(&change.column);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let range = |start: usize, count: usize| match count {
+                0 => format!("{},0", start - 1),
+                1 => start.to_string(),
+                _ => format!("{},{}", start, count),
+            };
+            write!(
+                writer,
+                "@@ -{} +{} @@",
+                range(old_start, old_count),
+                range(new_start, new_count)
+            )?;
+            if columns.is_empty() {
+                writeln!(writer)?;
+            } else {
+                writeln!(writer, " changed: {}", columns.join(", "))?;
+            }
+
+            for op in &ops[start..end] {
+                match *op {
+                    Op::Equal(o, _) => write_lines(writer, ' ', &old_records.lines(o)?)?,
+                    Op::Change(o, n) => {
+                        write_lines(writer, '-', &old_records.lines(o)?)?;
+                        write_lines(writer, '+', &new_records.lines(n)?)?;
+                    }
+                    Op::Delete(o) => write_lines(writer, '-', &old_records.lines(o)?)?,
+                    Op::Insert(n) => write_lines(writer, '+', &new_records.lines(n)?)?,
                 }
             }
         }
 
+        if omitted > 0 {
+            writeln!(writer, "... {} more changed rows not shown", omitted)?;
+        }
+
         Ok(())
     }
 }
+
+fn write_lines(writer: &mut dyn Write, prefix: char, lines: &[String]) -> Result<()> {
+    for line in lines {
+        writeln!(writer, "{}{}", prefix, line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diff::compute_diff;
+    use crate::model::Column;
+
+    fn table(rows: &[(i64, &str)]) -> Table {
+        let mut table = Table::new(vec![Column::new("id", 0), Column::new("name", 1)]);
+        table.set_key_columns(&["id".to_string()]);
+        for (i, (id, name)) in rows.iter().enumerate() {
+            table.add_row(vec![CellValue::Int(*id), CellValue::from(*name)], i + 2);
+        }
+        table
+    }
+
+    fn render(old: &Table, new: &Table, context: usize) -> String {
+        let diff = compute_diff(old, new, &Config::default());
+        let mut out = Vec::new();
+        UnifiedOutput::with_context(context)
+            .render(&diff, old, new, Path::new("a.csv"), Path::new("b.csv"), &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_quoting_and_context() {
+        let rows: Vec<(i64, &str)> = (1..=8).map(|i| (i, "x")).collect();
+        let old = table(&rows);
+        let mut changed = rows.clone();
+        changed[3].1 = "a, \"quoted\" value";
+        let new = table(&changed);
+
+        let out = render(&old, &new, 1);
+        assert_eq!(
+            out,
+            "--- a.csv\n+++ b.csv\n@@ -4,3 +4,3 @@ changed: name\n 3,x\n-4,x\n+4,\"a, \"\"quoted\"\" value\"\n 5,x\n"
+        );
+    }
+
+    #[test]
+    fn test_hunks_merge_when_context_overlaps() {
+        let rows: Vec<(i64, &str)> = (1..=10).map(|i| (i, "x")).collect();
+        let old = table(&rows);
+        let mut new_rows = rows.clone();
+        new_rows[1].1 = "y";
+        new_rows[4].1 = "y";
+        new_rows.remove(8);
+        let new = table(&new_rows);
+
+        let out = render(&old, &new, 1);
+        let hunks: Vec<_> = out.lines().filter(|l| l.starts_with("@@")).collect();
+        assert_eq!(
+            hunks,
+            vec!["@@ -2,6 +2,6 @@ changed: name", "@@ -9,3 +9,2 @@"]
+        );
+        assert!(out.contains("-9,x\n"));
+    }
+}
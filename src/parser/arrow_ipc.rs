@@ -0,0 +1,179 @@
+//! Arrow IPC (Feather v2) file and stream parser
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::model::{Column, Table};
+
+use super::parquet::{arrow_type_to_cell_type, batch_rows, projected_columns};
+use super::Parser;
+
+/// Magic bytes at the start of an Arrow IPC file (Feather v2)
+pub const ARROW_MAGIC: &[u8] = b"ARROW1";
+
+/// Magic bytes at the start of a Feather v1 file
+pub const FEATHER_V1_MAGIC: &[u8] = b"FEA1";
+
+/// Parser for Arrow IPC files and streams
+pub struct ArrowIpcParser;
+
+impl Parser for ArrowIpcParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open Arrow file: {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0u8; 6];
+        let read = reader
+            .read(&mut magic)
+            .with_context(|| format!("Failed to read Arrow file: {}", path.display()))?;
+        reader.rewind()?;
+
+        // Files start with the magic bytes; streams with their first message
+        let (schema, batches) = if magic[..read].starts_with(ARROW_MAGIC) {
+            let reader = FileReader::try_new(reader, None).context("Invalid Arrow IPC file")?;
+            (reader.schema(), read_batches(reader)?)
+        } else if magic[..read].starts_with(FEATHER_V1_MAGIC) {
+            bail!("Feather v1 files are not supported; write them as Feather v2 (Arrow IPC)");
+        } else {
+            let reader = StreamReader::try_new(reader, None).context("Invalid Arrow IPC stream")?;
+            (reader.schema(), read_batches(reader)?)
+        };
+
+        // Only convert the columns the diff will look at; ignored columns are
+        // kept, with null values
+        let projection = projected_columns(&schema, config);
+        let columns: Vec<Column> = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                Column::with_type(
+                    field.name().clone(),
+                    i,
+                    arrow_type_to_cell_type(field.data_type()),
+                )
+            })
+            .collect();
+
+        let mut table = Table::new(columns);
+
+        // Set key columns if specified
+        if !config.key_columns.is_empty() {
+            table.set_key_columns(&config.key_columns);
+        }
+
+        // Convert batches in parallel; collecting keeps them in file order
+        let mut first_rows = Vec::with_capacity(batches.len());
+        let mut next = 0;
+        for batch in &batches {
+            first_rows.push(next);
+            next += batch.num_rows();
+        }
+        let converted: Vec<_> = batches
+            .par_iter()
+            .zip(first_rows)
+            .map(|(batch, first_row)| {
+                let batch = batch
+                    .project(&projection)
+                    .context("Failed to project Arrow batch")?;
+                batch_rows(&batch, &projection, schema.fields().len(), first_row)
+            })
+            .collect::<Result<_>>()?;
+        for rows in converted {
+            table.extend_rows(rows);
+        }
+
+        // Sort if requested
+        if let Some(ref sort_col) = config.sort_by {
+            table.sort_by_column(sort_col);
+        }
+
+        Ok(table)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(
+            ext.to_lowercase().as_str(),
+            "arrow" | "arrows" | "feather" | "ipc"
+        )
+    }
+}
+
+fn read_batches<R>(reader: R) -> Result<Vec<RecordBatch>>
+where
+    R: Iterator<Item = std::result::Result<RecordBatch, arrow::error::ArrowError>>,
+{
+    reader
+        .map(|batch| batch.context("Failed to read Arrow batch"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+
+    use super::*;
+    use crate::model::{CellType, CellValue};
+
+    #[test]
+    fn test_file_and_stream() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("data.feather");
+        let mut writer = FileWriter::try_new(File::create(&file_path).unwrap(), &schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let stream_path = dir.path().join("data.arrows");
+        let mut writer =
+            StreamWriter::try_new(File::create(&stream_path).unwrap(), &schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        let config = Config::default().with_key_columns(vec!["id".to_string()]);
+        let table = ArrowIpcParser.parse(&file_path, &config).unwrap();
+        assert_eq!(table.columns[0].inferred_type, CellType::Int);
+        assert_eq!(table.columns[1].inferred_type, CellType::String);
+        assert_eq!(table.row_count(), 4);
+        assert_eq!(
+            table.rows[3].cells,
+            vec![CellValue::Int(2), CellValue::Null]
+        );
+        assert_eq!(table.rows[3].source_line, 5);
+
+        let table = ArrowIpcParser.parse(&stream_path, &config).unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.key_columns, vec![0]);
+
+        // Columns outside the include list are not converted
+        let config = Config {
+            include_columns: crate::filter::parse_column_selectors(&["id".to_string()]).unwrap(),
+            ..config
+        };
+        let table = ArrowIpcParser.parse(&stream_path, &config).unwrap();
+        assert_eq!(table.rows[0].cells, vec![CellValue::Int(1), CellValue::Null]);
+    }
+}
@@ -0,0 +1,727 @@
+//! Avro object container file parser
+//!
+//! A container file is a header (magic bytes, metadata holding the writer's
+//! JSON schema and codec, and a sync marker) followed by blocks of records,
+//! each ending with the sync marker. Blocks are decoded in parallel.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use arrow::datatypes::i256;
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use indexmap::IndexMap;
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::model::{CellType, CellValue, Column, Table};
+
+use super::parquet::decimal256_to_decimal;
+use super::Parser;
+
+/// Magic bytes at the start of an Avro object container file
+pub const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+/// Parser for Avro object container files
+pub struct AvroParser;
+
+impl Parser for AvroParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to open Avro file: {}", path.display()))?;
+        let mut reader = Reader::new(&bytes);
+        if reader.take(AVRO_MAGIC.len())? != AVRO_MAGIC {
+            bail!("Not an Avro file: {}", path.display());
+        }
+
+        let metadata = read_metadata(&mut reader)?;
+        let schema_json: Value = serde_json::from_slice(
+            metadata
+                .get("avro.schema")
+                .context("Avro file has no schema")?,
+        )
+        .context("Invalid Avro schema")?;
+        let schema = Schema::parse(&schema_json, &mut HashMap::new(), None)?;
+        let codec = match metadata.get("avro.codec") {
+            Some(codec) => std::str::from_utf8(codec)
+                .context("Invalid Avro codec name")?
+                .to_string(),
+            None => "null".to_string(),
+        };
+        let sync = reader.take(16)?;
+
+        // Records are rows; a file of other values has one `value` column
+        let fields: Vec<(String, Schema)> = match schema {
+            Schema::Record(fields) => fields,
+            other => vec![("value".to_string(), other)],
+        };
+        let columns: Vec<Column> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, (name, schema))| Column::with_type(name.clone(), i, schema.cell_type()))
+            .collect();
+
+        let mut table = Table::new(columns);
+
+        // Set key columns if specified
+        if !config.key_columns.is_empty() {
+            table.set_key_columns(&config.key_columns);
+        }
+
+        // Find the blocks, then decompress and decode them in parallel
+        let mut blocks = Vec::new();
+        let mut first_row = 0;
+        while !reader.is_empty() {
+            let count = reader.read_len()?;
+            let size = reader.read_len()?;
+            let data = reader.take(size)?;
+            if reader.take(16)? != sync {
+                bail!("Corrupt Avro file: sync marker mismatch");
+            }
+            blocks.push((data, count, first_row));
+            first_row += count;
+        }
+        let decoded: Vec<Vec<(Vec<CellValue>, usize)>> = blocks
+            .par_iter()
+            .map(|&(data, count, first_row)| {
+                let data = decompress(&codec, data)?;
+                let mut reader = Reader::new(&data);
+                (0..count)
+                    .map(|i| {
+                        let line = first_row + i + 2; // +2 as if 1-indexed with a header
+                        let cells = fields
+                            .iter()
+                            .map(|(name, schema)| {
+                                reader
+                                    .read_value(schema)
+                                    .with_context(|| format!("Row {}, column '{}'", line, name))
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok((cells, line))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+
+        for rows in decoded {
+            table.extend_rows(rows);
+        }
+
+        // Sort if requested
+        if let Some(ref sort_col) = config.sort_by {
+            table.sort_by_column(sort_col);
+        }
+
+        Ok(table)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        ext.eq_ignore_ascii_case("avro")
+    }
+}
+
+/// An Avro schema, with logical types resolved and named types inlined
+#[derive(Debug, Clone, PartialEq)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<(String, Schema)>),
+    Enum(Vec<String>),
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Fixed(usize),
+    Date,
+    TimeMillis,
+    TimeMicros,
+    TimestampMillis,
+    TimestampMicros,
+    TimestampNanos,
+    /// Decimal stored as bytes, or as fixed bytes of this size
+    Decimal {
+        scale: i8,
+        fixed: Option<usize>,
+    },
+}
+
+impl Schema {
+    /// Parse a JSON schema; `names` collects named types so later references
+    /// to them resolve
+    fn parse(
+        json: &Value,
+        names: &mut HashMap<String, Schema>,
+        namespace: Option<&str>,
+    ) -> Result<Self> {
+        match json {
+            Value::String(name) => Self::named(name, names, namespace),
+            Value::Array(branches) => Ok(Schema::Union(
+                branches
+                    .iter()
+                    .map(|branch| Self::parse(branch, names, namespace))
+                    .collect::<Result<_>>()?,
+            )),
+            Value::Object(object) => {
+                let type_name = object
+                    .get("type")
+                    .context("Avro schema object has no type")?;
+                let Value::String(type_name) = type_name else {
+                    // A nested schema in the type attribute
+                    return Self::parse(type_name, names, namespace);
+                };
+                let namespace = object
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .or(namespace);
+                let schema = match type_name.as_str() {
+                    "record" | "error" => {
+                        let fields = object
+                            .get("fields")
+                            .and_then(Value::as_array)
+                            .context("Avro record has no fields")?;
+                        let fields = fields
+                            .iter()
+                            .map(|field| {
+                                let name = field
+                                    .get("name")
+                                    .and_then(Value::as_str)
+                                    .context("Avro field has no name")?;
+                                let schema = field.get("type").context("Avro field has no type")?;
+                                Ok((name.to_string(), Self::parse(schema, names, namespace)?))
+                            })
+                            .collect::<Result<_>>()?;
+                        Schema::Record(fields)
+                    }
+                    "enum" => Schema::Enum(
+                        object
+                            .get("symbols")
+                            .and_then(Value::as_array)
+                            .context("Avro enum has no symbols")?
+                            .iter()
+                            .map(|s| s.as_str().unwrap_or_default().to_string())
+                            .collect(),
+                    ),
+                    "array" => Schema::Array(Box::new(Self::parse(
+                        object.get("items").context("Avro array has no items")?,
+                        names,
+                        namespace,
+                    )?)),
+                    "map" => Schema::Map(Box::new(Self::parse(
+                        object.get("values").context("Avro map has no values")?,
+                        names,
+                        namespace,
+                    )?)),
+                    "fixed" => {
+                        let size = object
+                            .get("size")
+                            .and_then(Value::as_u64)
+                            .context("Avro fixed has no size")?
+                            as usize;
+                        match logical_type(object) {
+                            Some("decimal") => Schema::Decimal {
+                                scale: decimal_scale(object)?,
+                                fixed: Some(size),
+                            },
+                            _ => Schema::Fixed(size),
+                        }
+                    }
+                    primitive => {
+                        let base = Self::named(primitive, names, namespace)?;
+                        match (logical_type(object), &base) {
+                            (Some("date"), Schema::Int) => Schema::Date,
+                            (Some("time-millis"), Schema::Int) => Schema::TimeMillis,
+                            (Some("time-micros"), Schema::Long) => Schema::TimeMicros,
+                            (Some("timestamp-millis" | "local-timestamp-millis"), Schema::Long) => {
+                                Schema::TimestampMillis
+                            }
+                            (Some("timestamp-micros" | "local-timestamp-micros"), Schema::Long) => {
+                                Schema::TimestampMicros
+                            }
+                            (Some("timestamp-nanos" | "local-timestamp-nanos"), Schema::Long) => {
+                                Schema::TimestampNanos
+                            }
+                            (Some("decimal"), Schema::Bytes) => Schema::Decimal {
+                                scale: decimal_scale(object)?,
+                                fixed: None,
+                            },
+                            // Unknown logical types read as their base type
+                            _ => base,
+                        }
+                    }
+                };
+                if matches!(type_name.as_str(), "record" | "error" | "enum" | "fixed") {
+                    if let Some(name) = object.get("name").and_then(Value::as_str) {
+                        register(names, name, namespace, &schema);
+                    }
+                }
+                Ok(schema)
+            }
+            _ => bail!("Invalid Avro schema: {}", json),
+        }
+    }
+
+    /// A primitive type or a reference to a named type defined earlier
+    fn named(name: &str, names: &HashMap<String, Schema>, namespace: Option<&str>) -> Result<Self> {
+        Ok(match name {
+            "null" => Schema::Null,
+            "boolean" => Schema::Boolean,
+            "int" => Schema::Int,
+            "long" => Schema::Long,
+            "float" => Schema::Float,
+            "double" => Schema::Double,
+            "bytes" => Schema::Bytes,
+            "string" => Schema::String,
+            _ => {
+                let qualified = namespace.map(|ns| format!("{}.{}", ns, name));
+                qualified
+                    .and_then(|q| names.get(&q))
+                    .or_else(|| names.get(name))
+                    .cloned()
+                    .with_context(|| {
+                        format!(
+                            "Unknown Avro type {} (recursive types are not supported)",
+                            name
+                        )
+                    })?
+            }
+        })
+    }
+
+    fn cell_type(&self) -> CellType {
+        match self {
+            Schema::Null => CellType::Null,
+            Schema::Boolean => CellType::Bool,
+            Schema::Int | Schema::Long => CellType::Int,
+            Schema::Float | Schema::Double => CellType::Float,
+            Schema::Bytes | Schema::Fixed(_) => CellType::Binary,
+            Schema::String | Schema::Enum(_) => CellType::String,
+            Schema::Record(_) | Schema::Map(_) => CellType::Struct,
+            Schema::Array(_) => CellType::List,
+            Schema::Union(branches) => branches
+                .iter()
+                .fold(CellType::Null, |t, branch| t.widen(branch.cell_type())),
+            Schema::Date => CellType::Date,
+            Schema::TimeMillis | Schema::TimeMicros => CellType::Time,
+            Schema::TimestampMillis | Schema::TimestampMicros | Schema::TimestampNanos => {
+                CellType::DateTime
+            }
+            Schema::Decimal { .. } => CellType::Decimal,
+        }
+    }
+}
+
+/// Record a named type under its full name and its short name
+fn register(
+    names: &mut HashMap<String, Schema>,
+    name: &str,
+    namespace: Option<&str>,
+    schema: &Schema,
+) {
+    let short = name.rsplit('.').next().unwrap_or(name);
+    names.insert(short.to_string(), schema.clone());
+    let full = match namespace {
+        Some(ns) if !name.contains('.') => format!("{}.{}", ns, name),
+        _ => name.to_string(),
+    };
+    names.insert(full, schema.clone());
+}
+
+fn logical_type(object: &serde_json::Map<String, Value>) -> Option<&str> {
+    object.get("logicalType").and_then(Value::as_str)
+}
+
+fn decimal_scale(object: &serde_json::Map<String, Value>) -> Result<i8> {
+    let scale = object.get("scale").and_then(Value::as_i64).unwrap_or(0);
+    i8::try_from(scale).with_context(|| format!("Avro decimal scale out of range: {}", scale))
+}
+
+/// The header's metadata map
+fn read_metadata(reader: &mut Reader) -> Result<HashMap<String, Vec<u8>>> {
+    let mut metadata = HashMap::new();
+    loop {
+        let count = reader.read_block_count()?;
+        if count == 0 {
+            return Ok(metadata);
+        }
+        for _ in 0..count {
+            let key = reader.read_string()?;
+            let value = reader.read_bytes()?.to_vec();
+            metadata.insert(key, value);
+        }
+    }
+}
+
+/// Decompress a block's data
+fn decompress<'a>(codec: &str, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    match codec {
+        "null" => Ok(Cow::Borrowed(data)),
+        "deflate" => {
+            let mut out = Vec::new();
+            flate2::read::DeflateDecoder::new(data)
+                .read_to_end(&mut out)
+                .context("Corrupt deflate block in Avro file")?;
+            Ok(Cow::Owned(out))
+        }
+        "snappy" => {
+            // The block ends with a CRC32 of the uncompressed data
+            let data = &data[..data.len().saturating_sub(4)];
+            let out = snap::raw::Decoder::new()
+                .decompress_vec(data)
+                .context("Corrupt snappy block in Avro file")?;
+            Ok(Cow::Owned(out))
+        }
+        "zstandard" => Ok(Cow::Owned(
+            zstd::stream::decode_all(data).context("Corrupt zstandard block in Avro file")?,
+        )),
+        other => bail!("Unsupported Avro codec: {}", other),
+    }
+}
+
+/// Reads Avro's binary encoding from a byte slice
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .context("Truncated Avro data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// A zigzag-encoded variable-length int or long
+    fn read_long(&mut self) -> Result<i64> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        bail!("Invalid Avro varint")
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        usize::try_from(self.read_long()?).context("Negative length in Avro data")
+    }
+
+    /// Item count of an array or map block; a negative count is followed
+    /// by the block's size in bytes
+    fn read_block_count(&mut self) -> Result<usize> {
+        let count = self.read_long()?;
+        if count < 0 {
+            self.read_long()?;
+        }
+        Ok(count.unsigned_abs() as usize)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_len()?;
+        self.take(len)
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let bytes = self.read_bytes()?;
+        Ok(std::str::from_utf8(bytes)
+            .context("Invalid UTF-8 in Avro string")?
+            .to_string())
+    }
+
+    fn read_value(&mut self, schema: &Schema) -> Result<CellValue> {
+        Ok(match schema {
+            Schema::Null => CellValue::Null,
+            Schema::Boolean => CellValue::Bool(self.take(1)?[0] != 0),
+            Schema::Int | Schema::Long => CellValue::Int(self.read_long()?),
+            Schema::Float => {
+                let bytes = self.take(4)?.try_into().expect("4 bytes");
+                CellValue::Float(f32::from_le_bytes(bytes) as f64)
+            }
+            Schema::Double => {
+                let bytes = self.take(8)?.try_into().expect("8 bytes");
+                CellValue::Float(f64::from_le_bytes(bytes))
+            }
+            Schema::Bytes => CellValue::Bytes(self.read_bytes()?.to_vec()),
+            Schema::Fixed(size) => CellValue::Bytes(self.take(*size)?.to_vec()),
+            Schema::String => CellValue::String(Cow::Owned(self.read_string()?)),
+            Schema::Enum(symbols) => {
+                let index = self.read_len()?;
+                let symbol = symbols.get(index).context("Avro enum index out of range")?;
+                CellValue::String(Cow::Owned(symbol.clone()))
+            }
+            Schema::Record(fields) => CellValue::Struct(
+                fields
+                    .iter()
+                    .map(|(name, schema)| Ok((name.clone(), self.read_value(schema)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Schema::Array(items) => {
+                let mut values = Vec::new();
+                loop {
+                    let count = self.read_block_count()?;
+                    if count == 0 {
+                        break CellValue::List(values);
+                    }
+                    for _ in 0..count {
+                        values.push(self.read_value(items)?);
+                    }
+                }
+            }
+            Schema::Map(values) => {
+                let mut entries = IndexMap::new();
+                loop {
+                    let count = self.read_block_count()?;
+                    if count == 0 {
+                        break CellValue::Struct(entries);
+                    }
+                    for _ in 0..count {
+                        let key = self.read_string()?;
+                        entries.insert(key, self.read_value(values)?);
+                    }
+                }
+            }
+            Schema::Union(branches) => {
+                let index = self.read_len()?;
+                let branch = branches
+                    .get(index)
+                    .context("Avro union index out of range")?;
+                self.read_value(branch)?
+            }
+            Schema::Date => {
+                let days = self.read_long()?;
+                match i32::try_from(days)
+                    .ok()
+                    .and_then(|d| d.checked_add(719_163))
+                    .and_then(NaiveDate::from_num_days_from_ce_opt)
+                {
+                    Some(date) => CellValue::Date(date),
+                    None => bail!("Date {} days from 1970-01-01 is out of range", days),
+                }
+            }
+            Schema::TimeMillis => time_cell(self.read_long()?, 1_000_000)?,
+            Schema::TimeMicros => time_cell(self.read_long()?, 1_000)?,
+            Schema::TimestampMillis => {
+                let v = self.read_long()?;
+                timestamp_cell(v, DateTime::from_timestamp_millis(v))?
+            }
+            Schema::TimestampMicros => {
+                let v = self.read_long()?;
+                timestamp_cell(v, DateTime::from_timestamp_micros(v))?
+            }
+            Schema::TimestampNanos => {
+                let v = self.read_long()?;
+                timestamp_cell(v, Some(DateTime::from_timestamp_nanos(v)))?
+            }
+            Schema::Decimal { scale, fixed } => {
+                let bytes = match fixed {
+                    Some(size) => self.take(*size)?,
+                    None => self.read_bytes()?,
+                };
+                // Big-endian two's complement; beyond 128 bits it is rounded
+                // like Parquet's Decimal256
+                if bytes.is_empty() || bytes.len() > 32 {
+                    bail!("Avro decimal of {} bytes is not supported", bytes.len());
+                }
+                let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+                let mut buf = [fill; 32];
+                buf[32 - bytes.len()..].copy_from_slice(bytes);
+                CellValue::Decimal(decimal256_to_decimal(i256::from_be_bytes(buf), *scale))
+            }
+        })
+    }
+}
+
+/// A time of day from a count of units since midnight, each `nanos` long
+fn time_cell(value: i64, nanos: i64) -> Result<CellValue> {
+    let total = value.saturating_mul(nanos);
+    match u32::try_from(total.div_euclid(1_000_000_000))
+        .ok()
+        .and_then(|secs| {
+            NaiveTime::from_num_seconds_from_midnight_opt(
+                secs,
+                total.rem_euclid(1_000_000_000) as u32,
+            )
+        }) {
+        Some(time) => Ok(CellValue::Time(time)),
+        None => bail!("Time {} is not a time of day", value),
+    }
+}
+
+fn timestamp_cell(value: i64, dt: Option<DateTime<chrono::Utc>>) -> Result<CellValue> {
+    match dt {
+        Some(dt) => Ok(CellValue::DateTime(dt.naive_utc())),
+        None => bail!("Timestamp {} is out of range", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Decimal;
+
+    /// Zigzag varint encoding, to build test files
+    fn long(v: i64) -> Vec<u8> {
+        let mut n = ((v << 1) ^ (v >> 63)) as u64;
+        let mut out = Vec::new();
+        loop {
+            if n < 0x80 {
+                out.push(n as u8);
+                return out;
+            }
+            out.push((n as u8 & 0x7f) | 0x80);
+            n >>= 7;
+        }
+    }
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut out = long(s.len() as i64);
+        out.extend(s.as_bytes());
+        out
+    }
+
+    /// A container file of one block of `count` records
+    fn container(schema: &str, count: i64, block: &[u8]) -> Vec<u8> {
+        let mut file = AVRO_MAGIC.to_vec();
+        file.extend(long(1));
+        file.extend(string("avro.schema"));
+        file.extend(string(schema));
+        file.push(0);
+        let sync = [7u8; 16];
+        file.extend(sync);
+        file.extend(long(count));
+        file.extend(long(block.len() as i64));
+        file.extend(block);
+        file.extend(sync);
+        file
+    }
+
+    #[test]
+    fn test_parse_container_file() {
+        let schema = r#"{"type": "record", "name": "Sale", "namespace": "shop", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "amount", "type": {"type": "bytes", "logicalType": "decimal", "precision": 9, "scale": 2}},
+            {"name": "status", "type": {"type": "enum", "name": "Status", "symbols": ["open", "paid"]}},
+            {"name": "previous", "type": ["null", "Status"]},
+            {"name": "tags", "type": {"type": "array", "items": "string"}}
+        ]}"#;
+        let mut block = Vec::new();
+        // id 1, 2024-01-02, 12.50, paid, previous open, tags [a, b]
+        block.extend(long(1));
+        block.extend(long(19724));
+        block.extend([4, 0x04, 0xe2]);
+        block.extend(long(1));
+        block.extend(long(1));
+        block.extend(long(0));
+        block.extend(long(2));
+        block.extend(string("a"));
+        block.extend(string("b"));
+        block.push(0);
+        // id -3, 1970-01-01, -0.01, open, previous null, no tags
+        block.extend(long(-3));
+        block.extend(long(0));
+        block.extend([4, 0xff, 0xff]);
+        block.extend(long(0));
+        block.extend(long(0));
+        block.push(0);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sales.avro");
+        std::fs::write(&path, container(schema, 2, &block)).unwrap();
+        let table = AvroParser.parse(&path, &Config::default()).unwrap();
+
+        let types: Vec<CellType> = table.columns.iter().map(|c| c.inferred_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                CellType::Int,
+                CellType::Date,
+                CellType::Decimal,
+                CellType::String,
+                CellType::String,
+                CellType::List
+            ]
+        );
+        let paid = CellValue::String(Cow::Borrowed("paid"));
+        let open = CellValue::String(Cow::Borrowed("open"));
+        assert_eq!(
+            table.rows[0].cells,
+            vec![
+                CellValue::Int(1),
+                CellValue::Date(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()),
+                CellValue::Decimal(Decimal::new(1250, 2)),
+                paid,
+                open.clone(),
+                CellValue::List(vec![
+                    CellValue::String(Cow::Borrowed("a")),
+                    CellValue::String(Cow::Borrowed("b"))
+                ]),
+            ]
+        );
+        assert_eq!(table.rows[1].cells[0], CellValue::Int(-3));
+        assert_eq!(
+            table.rows[1].cells[2],
+            CellValue::Decimal(Decimal::new(-1, 2))
+        );
+        assert_eq!(table.rows[1].cells[3], open);
+        assert_eq!(table.rows[1].cells[4], CellValue::Null);
+
+        // Values past the calendar are errors
+        let days = long(i32::MAX as i64);
+        let error = Reader::new(&days).read_value(&Schema::Date).unwrap_err();
+        assert!(error.to_string().contains("out of range"), "{}", error);
+        let micros = long(i64::MAX);
+        assert!(Reader::new(&micros)
+            .read_value(&Schema::TimeMicros)
+            .is_err());
+        assert!(Reader::new(&micros)
+            .read_value(&Schema::TimestampMicros)
+            .is_err());
+        let schema = r#"{"type": "record", "name": "Day", "fields": [
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}}
+        ]}"#;
+        let mut block = long(0);
+        block.extend(long(i32::MAX as i64));
+        std::fs::write(&path, container(schema, 2, &block)).unwrap();
+        let error = format!(
+            "{:#}",
+            AvroParser.parse(&path, &Config::default()).unwrap_err()
+        );
+        assert!(error.contains("Row 3, column 'day'"), "{}", error);
+
+        // Decimals wider than 128 bits are rounded, like Parquet's
+        let mut bytes = long(18);
+        bytes.push(0x01);
+        bytes.extend([0; 17]);
+        let schema = Schema::Decimal {
+            scale: 2,
+            fixed: None,
+        };
+        let value = Reader::new(&bytes).read_value(&schema).unwrap();
+        let rounded = Decimal::new(87112285931760246646623899502532662133, -1);
+        assert_eq!(value, CellValue::Decimal(rounded));
+        let object = serde_json::json!({"scale": 200});
+        assert!(decimal_scale(object.as_object().unwrap()).is_err());
+    }
+}
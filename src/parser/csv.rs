@@ -6,12 +6,17 @@ use std::io::BufReader;
 use std::path::Path;
 
 use anyhow::{Context, Result};
+use csv::StringRecord;
+use rayon::prelude::*;
 
 use crate::config::Config;
 use crate::model::{CellType, CellValue, Column, Table};
 
 use super::Parser;
 
+/// Number of records converted per parallel batch
+const CHUNK_SIZE: usize = 64 * 1024;
+
 /// Parser for CSV files
 pub struct CsvParser;
 
@@ -44,22 +49,36 @@ impl Parser for CsvParser {
             table.set_key_columns(&config.key_columns);
         }
 
-        // Read rows
-        for (line_num, result) in csv_reader.records().enumerate() {
-            let record = result.with_context(|| format!("Failed to read CSV row {}", line_num + 2))?; // +2 for 1-indexing and header
-
-            let cells: Vec<CellValue> = record.iter().map(|s| parse_cell_value(s)).collect();
-
-            // Pad with nulls if row has fewer columns
-            let cells = if cells.len() < table.column_count() {
-                let mut padded = cells;
-                padded.resize(table.column_count(), CellValue::Null);
-                padded
-            } else {
-                cells
-            };
+        // Read rows in chunks and convert each chunk in parallel
+        let mut records = csv_reader.records();
+        let mut line_num = 2; // 1-indexing and header
+        loop {
+            let mut chunk: Vec<StringRecord> = Vec::with_capacity(CHUNK_SIZE);
+            for result in records.by_ref().take(CHUNK_SIZE) {
+                let record = result
+                    .with_context(|| format!("Failed to read CSV row {}", line_num + chunk.len()))?;
+                chunk.push(record);
+            }
+            if chunk.is_empty() {
+                break;
+            }
 
-            table.add_row(cells, line_num + 2); // +2 for 1-indexing and header
+            let column_count = table.column_count();
+            let rows: Vec<(Vec<CellValue>, usize)> = chunk
+                .par_iter()
+                .enumerate()
+                .map(|(i, record)| {
+                    let mut cells: Vec<CellValue> = record.iter().map(parse_cell_value).collect();
+                    // Pad with nulls if row has fewer columns
+                    if cells.len() < column_count {
+                        cells.resize(column_count, CellValue::Null);
+                    }
+                    (cells, line_num + i)
+                })
+                .collect();
+
+            line_num += chunk.len();
+            table.extend_rows(rows);
         }
 
         // Infer column types
@@ -79,7 +98,7 @@ impl Parser for CsvParser {
 }
 
 /// Parse a string value into a CellValue with type inference
-fn parse_cell_value(s: &str) -> CellValue {
+pub(super) fn parse_cell_value(s: &str) -> CellValue {
     let trimmed = s.trim();
 
     // Check for empty/null
@@ -123,23 +142,13 @@ fn parse_cell_value(s: &str) -> CellValue {
 }
 
 /// Infer column types from data
-fn infer_column_types(table: &mut Table) {
+pub(super) fn infer_column_types(table: &mut Table) {
     for col_idx in 0..table.column_count() {
         let mut inferred = CellType::Null;
 
         for row in &table.rows {
             if let Some(cell) = row.cells.get(col_idx) {
-                let cell_type = match cell {
-                    CellValue::Null => CellType::Null,
-                    CellValue::Bool(_) => CellType::Bool,
-                    CellValue::Int(_) => CellType::Int,
-                    CellValue::Float(_) => CellType::Float,
-                    CellValue::String(_) => CellType::String,
-                    CellValue::Date(_) => CellType::Date,
-                    CellValue::DateTime(_) => CellType::DateTime,
-                };
-
-                inferred = inferred.widen(cell_type);
+                inferred = inferred.widen(cell.cell_type());
             }
         }
 
@@ -166,4 +175,49 @@ mod tests {
             CellValue::String(Cow::Owned("hello".to_string()))
         );
     }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        use crate::diff::compute_diff;
+
+        // More than two chunks, so rows are converted in several batches
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, changed: usize| {
+            let mut text = String::from("id,name,amount\n");
+            for i in 0..2 * CHUNK_SIZE + 100 {
+                let name = if i % changed == 0 { "changed" } else { "same" };
+                text.push_str(&format!("{},{},{}.5\n", i, name, i % 1000));
+            }
+            let path = dir.path().join(name);
+            std::fs::write(&path, text).unwrap();
+            path
+        };
+        let old_path = write("old.csv", usize::MAX);
+        let new_path = write("new.csv", 997);
+        let config = Config::default().with_key_columns(vec!["id".to_string()]);
+
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let old = CsvParser.parse(&old_path, &config).unwrap();
+                let new = CsvParser.parse(&new_path, &config).unwrap();
+                let diff = compute_diff(&old, &new, &config);
+                (old, format!("{:?}", diff))
+            })
+        };
+        let (sequential, sequential_diff) = run(1);
+        let (parallel, parallel_diff) = run(4);
+
+        assert_eq!(sequential.row_count(), 2 * CHUNK_SIZE + 100);
+        for (i, (a, b)) in sequential.rows.iter().zip(&parallel.rows).enumerate() {
+            assert_eq!(a.source_line, i + 2);
+            assert_eq!(a.source_line, b.source_line);
+            assert_eq!(a.cells, b.cells);
+        }
+        assert!(sequential_diff.contains("Modified"));
+        assert_eq!(sequential_diff, parallel_diff);
+    }
 }
@@ -0,0 +1,505 @@
+//! Datasets: a directory or glob of files read as one table
+//!
+//! Directories named `name=value` (Hive-style partitions, as Spark and Hive
+//! write them) give every row of the files below them a `name` column
+//! holding `value`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::filter::{ColumnStats, FilterExpr};
+use crate::model::{CellType, CellValue, Column, Table};
+
+use super::csv::parse_cell_value;
+use super::sqlite::split_source;
+use super::{detect_format, parquet, ParserFactory};
+
+/// Partition value Hive writes for nulls
+pub const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// A data file of a dataset
+#[derive(Debug, Clone)]
+pub struct DatasetFile {
+    pub path: PathBuf,
+    /// Partition columns and values from the file's directories, outermost
+    /// first
+    pub partition: Vec<(String, CellValue)>,
+}
+
+impl DatasetFile {
+    /// Partition path such as `dt=2024-01-01/region=eu`, empty for files
+    /// outside any partition
+    pub fn partition_name(&self) -> String {
+        partition_name(
+            self.partition
+                .iter()
+                .map(|(name, value)| (name.as_str(), value)),
+        )
+    }
+}
+
+/// The data files of a directory or glob
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    /// Files in path order
+    pub files: Vec<DatasetFile>,
+}
+
+impl Dataset {
+    /// List the data files of a directory or glob pattern, or return None
+    /// for any other path. Hidden files and files starting with `_` (such as
+    /// `_SUCCESS`) are skipped, as are files no parser reads.
+    pub fn open(path: &Path, factory: &ParserFactory) -> Result<Option<Self>> {
+        let (root, paths) = if path.is_dir() {
+            (path.to_path_buf(), walk(path)?)
+        } else if !path.exists() && is_glob(path) {
+            let pattern = path.to_string_lossy();
+            let paths = glob::glob(&pattern)
+                .with_context(|| format!("Invalid glob pattern: {}", pattern))?
+                .filter_map(|entry| entry.ok())
+                .filter(|p| p.is_file())
+                .collect();
+            (glob_root(path), paths)
+        } else {
+            return Ok(None);
+        };
+
+        let mut files: Vec<DatasetFile> = paths
+            .into_iter()
+            .filter(|p| !skipped_name(p) && factory.is_data_file(p))
+            .map(|p| DatasetFile {
+                partition: partition_values(p.strip_prefix(&root).unwrap_or(&p)),
+                path: p,
+            })
+            .collect();
+        if files.is_empty() {
+            bail!("No data files in {}", path.display());
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Some(Self { files }))
+    }
+
+    /// Whether a directory holds `name=value` partition directories
+    pub fn is_partitioned(path: &Path) -> bool {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            entry.path().is_dir() && split_partition(&entry.file_name().to_string_lossy()).is_some()
+        })
+    }
+
+    /// Partition column names, in path order
+    pub fn partition_columns(&self) -> Vec<String> {
+        let mut columns: Vec<String> = Vec::new();
+        for file in &self.files {
+            for (name, _) in &file.partition {
+                if !columns.contains(name) {
+                    columns.push(name.clone());
+                }
+            }
+        }
+        columns
+    }
+
+    /// Names of the partitions holding files
+    pub fn partitions(&self) -> BTreeSet<String> {
+        self.files.iter().map(DatasetFile::partition_name).collect()
+    }
+
+    /// Drop the partitions whose files have the same bytes in both datasets,
+    /// whatever the files are called, and return this dataset's files of
+    /// them. Sizes are compared before anything is read.
+    pub fn skip_unchanged(&mut self, other: &mut Dataset) -> Result<Dataset> {
+        let old = self.by_partition();
+        let new = other.by_partition();
+        let candidates: Vec<(&String, &Vec<&DatasetFile>, &Vec<&DatasetFile>)> = old
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .filter_map(|(name, old_files)| {
+                let new_files = new.get(name)?;
+                (sizes(old_files)? == sizes(new_files)?).then_some((name, old_files, new_files))
+            })
+            .collect();
+
+        let unchanged: BTreeSet<String> = candidates
+            .par_iter()
+            .map(|(name, old_files, new_files)| {
+                Ok((fingerprint(old_files)? == fingerprint(new_files)?).then(|| name.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let (skipped, kept) = std::mem::take(&mut self.files)
+            .into_iter()
+            .partition(|f| unchanged.contains(&f.partition_name()));
+        self.files = kept;
+        other
+            .files
+            .retain(|f| !unchanged.contains(&f.partition_name()));
+        Ok(Dataset { files: skipped })
+    }
+
+    /// Number of rows the row filter keeps. Without a filter, Parquet files
+    /// are counted from their footers rather than read.
+    pub fn row_count(&self, factory: &ParserFactory, config: &Config) -> Result<usize> {
+        if self.files.is_empty() {
+            return Ok(0);
+        }
+        if config.filter.is_none() {
+            return self
+                .files
+                .par_iter()
+                .map(|file| match detect_format(&file.path) {
+                    Some("parquet") => parquet::row_count(&file.path),
+                    _ => Ok(factory.parse(&file.path, config)?.row_count()),
+                })
+                .sum();
+        }
+        Ok(factory.parse_dataset(self, config)?.row_count())
+    }
+
+    fn by_partition(&self) -> BTreeMap<String, Vec<&DatasetFile>> {
+        let mut partitions: BTreeMap<String, Vec<&DatasetFile>> = BTreeMap::new();
+        for file in &self.files {
+            partitions
+                .entry(file.partition_name())
+                .or_default()
+                .push(file);
+        }
+        partitions
+    }
+
+    /// Files whose partition values do not rule out the row filter
+    pub fn files_matching<'a>(
+        &'a self,
+        filter: Option<&'a FilterExpr>,
+    ) -> impl Iterator<Item = &'a DatasetFile> {
+        self.files.iter().filter(move |file| match filter {
+            Some(filter) => may_match(file, filter),
+            None => true,
+        })
+    }
+
+    /// Drop the files whose partition values rule out the row filter
+    pub fn prune(&mut self, filter: &FilterExpr) {
+        self.files.retain(|file| may_match(file, filter));
+    }
+
+    /// Union of the tables parsed from `files`: columns are matched by name
+    /// in order of first appearance, followed by the partition columns
+    pub fn union(files: &[&DatasetFile], tables: Vec<Table>) -> Table {
+        let partition_columns: Vec<&str> = {
+            let mut columns: Vec<&str> = Vec::new();
+            for (name, _) in files.iter().flat_map(|f| &f.partition) {
+                if !columns.contains(&name.as_str()) {
+                    columns.push(name);
+                }
+            }
+            columns
+        };
+
+        // A partition column replaces a file column of the same name
+        let mut types: IndexMap<String, CellType> = IndexMap::new();
+        for table in &tables {
+            for column in &table.columns {
+                if !partition_columns.contains(&column.name.as_str()) {
+                    let t = types.entry(column.name.clone()).or_insert(CellType::Null);
+                    *t = t.widen(column.inferred_type);
+                }
+            }
+        }
+        for &name in &partition_columns {
+            types.insert(name.to_string(), CellType::Null);
+        }
+        for (name, value) in files.iter().flat_map(|f| &f.partition) {
+            if let Some(t) = types.get_mut(name.as_str()) {
+                *t = t.widen(value.cell_type());
+            }
+        }
+
+        let columns: Vec<Column> = types
+            .iter()
+            .enumerate()
+            .map(|(i, (name, t))| Column::with_type(name.clone(), i, *t))
+            .collect();
+        let width = columns.len();
+        let mut rows = Vec::with_capacity(tables.iter().map(Table::row_count).sum());
+        for (file, table) in files.iter().zip(tables) {
+            let targets: Vec<Option<usize>> = table
+                .columns
+                .iter()
+                .map(|c| {
+                    (!partition_columns.contains(&c.name.as_str()))
+                        .then(|| types.get_index_of(c.name.as_str()))
+                        .flatten()
+                })
+                .collect();
+            let partition: Vec<(usize, &CellValue)> = file
+                .partition
+                .iter()
+                .filter_map(|(name, value)| Some((types.get_index_of(name.as_str())?, value)))
+                .collect();
+            for row in table.rows {
+                let mut cells = vec![CellValue::Null; width];
+                for (cell, target) in row.cells.into_iter().zip(&targets) {
+                    if let Some(i) = target {
+                        cells[*i] = cell;
+                    }
+                }
+                for &(i, value) in &partition {
+                    cells[i] = value.clone();
+                }
+                rows.push((cells, row.source_line));
+            }
+        }
+
+        let mut table = Table::new(columns);
+        table.extend_rows(rows);
+        table
+    }
+}
+
+fn may_match(file: &DatasetFile, filter: &FilterExpr) -> bool {
+    filter.may_match(&|name: &str| {
+        let (_, value) = file.partition.iter().find(|(column, _)| column == name)?;
+        Some(ColumnStats {
+            min: value.clone(),
+            max: value.clone(),
+            null_count: Some(if value.is_null() { 1 } else { 0 }),
+        })
+    })
+}
+
+/// Partition path of some partition values, e.g. `dt=2024-01-01/region=eu`
+pub fn partition_name<'a>(values: impl IntoIterator<Item = (&'a str, &'a CellValue)>) -> String {
+    values
+        .into_iter()
+        .map(|(name, value)| match value {
+            CellValue::Null => format!("{}={}", name, DEFAULT_PARTITION),
+            value => format!("{}={}", name, value.display()),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether a path is a glob pattern rather than a file name
+fn is_glob(path: &Path) -> bool {
+    // `db.sqlite?query=SELECT * ...` names a query, not files
+    path.to_string_lossy().contains(['*', '?', '[']) && split_source(path).is_none()
+}
+
+/// Directory a glob pattern starts matching in: its components before the
+/// first one with a wildcard
+fn glob_root(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|c| !is_glob(Path::new(c.as_os_str())))
+        .collect()
+}
+
+/// Files below a directory, skipping hidden directories
+fn walk(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory: {}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                if !skipped_name(&path) {
+                    pending.push(path);
+                }
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Hidden files and Spark/Hive bookkeeping files such as `_SUCCESS`
+fn skipped_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.') || n.starts_with('_'))
+}
+
+/// Partition values from the directories of a path relative to the root
+fn partition_values(relative: &Path) -> Vec<(String, CellValue)> {
+    let Some(dirs) = relative.parent() else {
+        return Vec::new();
+    };
+    dirs.iter()
+        .filter_map(|dir| {
+            let dir = dir.to_string_lossy();
+            let (name, value) = split_partition(&dir)?;
+            let value = unescape(value);
+            let cell = if value == DEFAULT_PARTITION {
+                CellValue::Null
+            } else {
+                parse_cell_value(&value)
+            };
+            Some((unescape(name), cell))
+        })
+        .collect()
+}
+
+fn split_partition(dir: &str) -> Option<(&str, &str)> {
+    dir.split_once('=').filter(|(name, _)| !name.is_empty())
+}
+
+/// Undo Hive's `%XX` escaping of partition names and values
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Sorted file sizes, or None if a file cannot be read
+fn sizes(files: &[&DatasetFile]) -> Option<Vec<u64>> {
+    let mut sizes = files
+        .iter()
+        .map(|f| std::fs::metadata(&f.path).ok().map(|m| m.len()))
+        .collect::<Option<Vec<_>>>()?;
+    sizes.sort_unstable();
+    Some(sizes)
+}
+
+/// Sorted hashes of the files' bytes
+fn fingerprint(files: &[&DatasetFile]) -> Result<Vec<u64>> {
+    let mut hashes = files
+        .iter()
+        .map(|f| content_hash(&f.path))
+        .collect::<Result<Vec<_>>>()?;
+    hashes.sort_unstable();
+    Ok(hashes)
+}
+
+/// Hash of a file's bytes
+pub fn content_hash(path: &Path) -> Result<u64> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_partitioned_dataset() {
+        let old = tempfile::tempdir().unwrap();
+        let new = tempfile::tempdir().unwrap();
+        let write = |dir: &Path, path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        for dir in [old.path(), new.path()] {
+            write(dir, "dt=2024-01-01/part-0.csv", "id,total\n1,10\n");
+            write(dir, "dt=2024-01-01/_SUCCESS", "");
+        }
+        write(old.path(), "dt=2024-01-02/part-0.csv", "id,total\n2,20\n");
+        write(new.path(), "dt=2024-01-02/part-1.csv", "id,total\n2,25\n");
+        write(
+            new.path(),
+            "dt=__HIVE_DEFAULT_PARTITION__/part-0.csv",
+            "id\n3\n",
+        );
+
+        let factory = ParserFactory::new();
+        assert!(Dataset::is_partitioned(old.path()));
+        let mut old_set = Dataset::open(old.path(), &factory).unwrap().unwrap();
+        let mut new_set = Dataset::open(new.path(), &factory).unwrap().unwrap();
+        assert_eq!(old_set.partition_columns(), vec!["dt"]);
+
+        let table = factory.parse_dataset(&new_set, &Config::default()).unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "total", "dt"]);
+        assert_eq!(table.columns[2].inferred_type, CellType::Date);
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(
+            table.rows[2].cells,
+            vec![CellValue::Int(3), CellValue::Null, CellValue::Null]
+        );
+
+        let skipped = old_set.skip_unchanged(&mut new_set).unwrap();
+        assert_eq!(
+            skipped.partitions(),
+            BTreeSet::from(["dt=2024-01-01".to_string()])
+        );
+        assert_eq!(
+            old_set.partitions(),
+            BTreeSet::from(["dt=2024-01-02".to_string()])
+        );
+        assert_eq!(
+            new_set.partitions(),
+            BTreeSet::from([
+                "dt=2024-01-02".to_string(),
+                "dt=__HIVE_DEFAULT_PARTITION__".to_string()
+            ])
+        );
+
+        // Rows of skipped partitions count as unchanged
+        let config = Config::default().with_key_columns(vec!["id".to_string()]);
+        let old_table = factory.parse_dataset(&old_set, &config).unwrap();
+        let new_table = factory.parse_dataset(&new_set, &config).unwrap();
+        let mut stats = crate::diff::compute_diff(&old_table, &new_table, &config).stats;
+        stats.add_unchanged(skipped.row_count(&factory, &config).unwrap());
+        assert_eq!((stats.old_row_count, stats.new_row_count), (2, 3));
+        assert_eq!((stats.rows_unchanged, stats.rows_modified), (1, 1));
+        assert_eq!((stats.rows_added, stats.rows_removed), (1, 0));
+        let config = Config {
+            filter: Some(FilterExpr::parse("id > 1").unwrap()),
+            ..config
+        };
+        assert_eq!(skipped.row_count(&factory, &config).unwrap(), 0);
+
+        let glob = old.path().join("dt=*/*.csv");
+        let globbed = Dataset::open(&glob, &factory).unwrap().unwrap();
+        assert_eq!(globbed.files.len(), 2);
+        assert_eq!(globbed.files[0].partition_name(), "dt=2024-01-01");
+        assert_eq!(unescape("a%3Ab%2F"), "a:b/");
+    }
+}
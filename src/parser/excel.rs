@@ -1,16 +1,26 @@
 //! Excel file parser (xlsx, xls, ods)
 
 use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
-use calamine::{open_workbook_auto, Data, Range, Reader};
+use calamine::{open_workbook_auto, Data, Range, Reader, Sheets};
 
-use crate::config::Config;
+use crate::config::{Config, HeaderRow};
 use crate::model::{CellValue, Column, Table};
 
+use super::xlsx_package::{CellRect, XlsxPackage};
 use super::Parser;
 
+/// Rows searched for the header with `HeaderRow::Auto`
+const HEADER_SCAN_ROWS: u32 = 20;
+
+static EMPTY: Data = Data::Empty;
+
+type Workbook = Sheets<BufReader<File>>;
+
 /// Parser for Excel files
 pub struct ExcelParser;
 
@@ -19,25 +29,12 @@ impl Parser for ExcelParser {
         let mut workbook = open_workbook_auto(path)
             .with_context(|| format!("Failed to open Excel file: {}", path.display()))?;
 
-        // Get sheet name
-        let sheet_name = if let Some(ref name) = config.sheet_name {
-            name.clone()
-        } else {
-            // Use first sheet
-            let sheets = workbook.sheet_names();
-            if sheets.is_empty() {
-                bail!("No sheets found in workbook");
-            }
-            sheets[0].clone()
+        let (sheet_name, bounds) = match config.data_range {
+            Some(ref spec) => resolve_range(&mut workbook, spec, config)?,
+            None => (default_sheet(&workbook, config)?, None),
         };
-
-        // Get the sheet range
-        let range: Range<Data> = workbook
-            .worksheet_range(&sheet_name)
-            .with_context(|| format!("Failed to read sheet: {}", sheet_name))?;
-
-        // Parse range into table
-        parse_range(range, config)
+        let mut package = XlsxPackage::open(path)?;
+        read_sheet(&mut workbook, package.as_mut(), &sheet_name, bounds, config)
     }
 
     fn supports_extension(&self, ext: &str) -> bool {
@@ -45,24 +42,213 @@ impl Parser for ExcelParser {
     }
 }
 
-fn parse_range(range: Range<Data>, config: &Config) -> Result<Table> {
-    let (row_count, col_count) = range.get_size();
+impl ExcelParser {
+    /// The sheet `parse` reads: the one holding `--range`, `--sheet`, or the
+    /// first
+    pub fn sheet_name(&self, path: &Path, config: &Config) -> Result<String> {
+        let mut workbook = open_workbook_auto(path)
+            .with_context(|| format!("Failed to open Excel file: {}", path.display()))?;
+        match config.data_range {
+            Some(ref spec) => Ok(resolve_range(&mut workbook, spec, config)?.0),
+            None => default_sheet(&workbook, config),
+        }
+    }
+
+    /// Parse every sheet, in workbook order; empty sheets have no columns
+    pub fn parse_sheets(&self, path: &Path, config: &Config) -> Result<Vec<(String, Table)>> {
+        let mut workbook = open_workbook_auto(path)
+            .with_context(|| format!("Failed to open Excel file: {}", path.display()))?;
+        let mut package = XlsxPackage::open(path)?;
+
+        let mut sheets = Vec::new();
+        for sheet_name in workbook.sheet_names() {
+            let table = read_sheet(&mut workbook, package.as_mut(), &sheet_name, None, config)
+                .with_context(|| format!("Failed to parse sheet: {}", sheet_name));
+            let table = match table {
+                Ok(table) => table,
+                Err(_) if is_empty_sheet(&mut workbook, &sheet_name) => Table::new(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            sheets.push((sheet_name, table));
+        }
+        Ok(sheets)
+    }
+}
+
+fn is_empty_sheet(workbook: &mut Workbook, sheet_name: &str) -> bool {
+    workbook
+        .worksheet_range(sheet_name)
+        .is_ok_and(|range| range.get_size().0 == 0)
+}
+
+/// The sheet to read when no range names one: `--sheet`, or the first
+fn default_sheet(workbook: &Workbook, config: &Config) -> Result<String> {
+    if let Some(ref name) = config.sheet_name {
+        return Ok(name.clone());
+    }
+    match workbook.sheet_names().into_iter().next() {
+        Some(name) => Ok(name),
+        None => bail!("No sheets found in workbook"),
+    }
+}
+
+/// Find the sheet and cells a `--range` refers to: an Excel Table, a defined
+/// name, `Sheet!A1:F20`, or `A1:F20` on the default sheet
+fn resolve_range(
+    workbook: &mut Workbook,
+    spec: &str,
+    config: &Config,
+) -> Result<(String, Option<CellRect>)> {
+    if let Sheets::Xlsx(ref mut xlsx) = workbook {
+        xlsx.load_tables().context("Failed to read Excel tables")?;
+        if xlsx.table_names().iter().any(|name| *name == spec) {
+            let table = xlsx
+                .table_by_name(spec)
+                .with_context(|| format!("Failed to read table: {}", spec))?;
+            let (Some(start), Some(end)) = (table.data().start(), table.data().end()) else {
+                bail!("Table {} has no rows", spec);
+            };
+            // The table's data excludes its header row, just above
+            let bounds = CellRect {
+                start: (start.0.saturating_sub(1), start.1),
+                end,
+            };
+            return Ok((table.sheet_name().to_string(), Some(bounds)));
+        }
+    }
+
+    let defined = workbook
+        .defined_names()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(spec))
+        .map(|(_, reference)| reference.clone());
+    if let Some(reference) = defined {
+        return match sheet_reference(&reference) {
+            Some((Some(sheet), bounds)) => Ok((sheet, Some(bounds))),
+            _ => bail!("Defined name {} is not a cell range: {}", spec, reference),
+        };
+    }
+
+    match sheet_reference(spec) {
+        Some((Some(sheet), bounds)) => Ok((sheet, Some(bounds))),
+        Some((None, bounds)) => Ok((default_sheet(workbook, config)?, Some(bounds))),
+        None => bail!("No cell range, table or defined name called {}", spec),
+    }
+}
+
+/// Split `'My Sheet'!$A$1:$C$9` into its sheet name, if any, and cells
+fn sheet_reference(reference: &str) -> Option<(Option<String>, CellRect)> {
+    let reference = reference.trim().trim_start_matches('=');
+    match reference.rsplit_once('!') {
+        Some((sheet, cells)) => {
+            let sheet = sheet
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .map_or_else(|| sheet.to_string(), |s| s.replace("''", "'"));
+            Some((Some(sheet), CellRect::parse(cells)?))
+        }
+        None => Some((None, CellRect::parse(reference)?)),
+    }
+}
+
+fn read_sheet(
+    workbook: &mut Workbook,
+    package: Option<&mut XlsxPackage>,
+    sheet_name: &str,
+    bounds: Option<CellRect>,
+    config: &Config,
+) -> Result<Table> {
+    let range: Range<Data> = workbook
+        .worksheet_range(sheet_name)
+        .with_context(|| format!("Failed to read sheet: {}", sheet_name))?;
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => match (range.start(), range.end()) {
+            (Some(start), Some(end)) if range.get_size().0 > 0 => CellRect { start, end },
+            _ => bail!("Empty sheet"),
+        },
+    };
+    let merges = match package {
+        Some(package) => package.merged_regions(sheet_name)?,
+        None => Vec::new(),
+    };
+    let formulas = if config.excel_formulas {
+        Some(
+            workbook
+                .worksheet_formula(sheet_name)
+                .with_context(|| format!("Failed to read formulas of sheet: {}", sheet_name))?,
+        )
+    } else {
+        None
+    };
+
+    let grid = Grid {
+        range: &range,
+        merges: &merges,
+    };
+    parse_grid(&grid, bounds, formulas.as_ref(), config)
+}
+
+/// Cells of a sheet, where every cell of a merged region reads as the
+/// region's top-left value
+struct Grid<'a> {
+    range: &'a Range<Data>,
+    merges: &'a [CellRect],
+}
+
+impl Grid<'_> {
+    fn get(&self, pos: (u32, u32)) -> &Data {
+        let pos = self
+            .merges
+            .iter()
+            .find(|region| region.contains(pos))
+            .map_or(pos, |region| region.start);
+        self.raw(pos)
+    }
 
-    if row_count == 0 {
-        bail!("Empty sheet");
+    /// The cell itself, ignoring merged regions
+    fn raw(&self, pos: (u32, u32)) -> &Data {
+        self.range.get_value(pos).unwrap_or(&EMPTY)
     }
+}
+
+fn parse_grid(
+    grid: &Grid,
+    bounds: CellRect,
+    formulas: Option<&Range<String>>,
+    config: &Config,
+) -> Result<Table> {
+    let header = header_row(grid, bounds, config.header_row)?;
+    let first_data_row = header + config.header_rows.max(1) as u32;
+    let sheet_columns: Vec<u32> = (bounds.start.1..=bounds.end.1).collect();
 
-    // First row is header
-    let header_row = range.rows().next().context("No header row found")?;
-    let columns: Vec<Column> = header_row
+    let names: Vec<String> = sheet_columns
         .iter()
         .enumerate()
-        .map(|(i, cell)| {
-            let name = cell_to_string(cell);
-            Column::new(if name.is_empty() { format!("Column{}", i + 1) } else { name }, i)
+        .map(|(i, &col)| {
+            let name = join_header((header..first_data_row).map(|row| grid.get((row, col))));
+            if name.is_empty() { format!("Column{}", i + 1) } else { name }
         })
         .collect();
 
+    // Columns with any formula get a companion column holding its text
+    let formula_text = |pos: (u32, u32)| {
+        formulas
+            .and_then(|f| f.get_value(pos))
+            .filter(|f| !f.is_empty())
+    };
+    let has_formulas: Vec<bool> = sheet_columns
+        .iter()
+        .map(|&col| (first_data_row..=bounds.end.0).any(|row| formula_text((row, col)).is_some()))
+        .collect();
+
+    let mut columns = Vec::new();
+    for (name, &has_formula) in names.iter().zip(&has_formulas) {
+        columns.push(Column::new(name.clone(), columns.len()));
+        if has_formula {
+            columns.push(Column::new(format!("{} [formula]", name), columns.len()));
+        }
+    }
     let mut table = Table::new(columns);
 
     // Set key columns if specified
@@ -70,24 +256,18 @@ fn parse_range(range: Range<Data>, config: &Config) -> Result<Table> {
         table.set_key_columns(&config.key_columns);
     }
 
-    // Read data rows
-    for (line_num, row) in range.rows().skip(1).enumerate() {
-        let cells: Vec<CellValue> = row
-            .iter()
-            .take(col_count)
-            .map(|cell| convert_cell(cell))
-            .collect();
-
-        // Pad with nulls if row has fewer columns
-        let cells = if cells.len() < table.column_count() {
-            let mut padded = cells;
-            padded.resize(table.column_count(), CellValue::Null);
-            padded
-        } else {
-            cells
-        };
-
-        table.add_row(cells, line_num + 2); // +2 for 1-indexing and header
+    for row in first_data_row..=bounds.end.0 {
+        let mut cells = Vec::with_capacity(table.column_count());
+        for (&col, &has_formula) in sheet_columns.iter().zip(&has_formulas) {
+            cells.push(convert_cell(grid.get((row, col))));
+            if has_formula {
+                cells.push(match formula_text((row, col)) {
+                    Some(formula) => CellValue::String(Cow::Owned(format!("={}", formula))),
+                    None => CellValue::Null,
+                });
+            }
+        }
+        table.add_row(cells, row as usize + 1);
     }
 
     // Sort if requested
@@ -98,6 +278,56 @@ fn parse_range(range: Range<Data>, config: &Config) -> Result<Table> {
     Ok(table)
 }
 
+/// The sheet row (0-based) holding the first header row
+fn header_row(grid: &Grid, bounds: CellRect, header: HeaderRow) -> Result<u32> {
+    match header {
+        HeaderRow::First => Ok(bounds.start.0),
+        HeaderRow::Row(row) => {
+            let row = row as u32 - 1;
+            if !(bounds.start.0..=bounds.end.0).contains(&row) {
+                bail!("Header row {} is outside the data range", row + 1);
+            }
+            Ok(row)
+        }
+        HeaderRow::Auto => Ok(detect_header(grid, bounds)),
+    }
+}
+
+/// Skip title rows: the header is the first row of text cells that fills at
+/// least half the width of the widest row near the top
+fn detect_header(grid: &Grid, bounds: CellRect) -> u32 {
+    let last = bounds.end.0.min(bounds.start.0 + HEADER_SCAN_ROWS - 1);
+    // (row, non-empty cells, all of them text)
+    let rows: Vec<(u32, usize, bool)> = (bounds.start.0..=last)
+        .map(|row| {
+            // Merged cells count once, so a merged title spans one cell
+            let cells: Vec<&Data> = (bounds.start.1..=bounds.end.1)
+                .map(|col| grid.raw((row, col)))
+                .filter(|cell| !cell_to_string(cell).trim().is_empty())
+                .collect();
+            let text = cells.iter().all(|cell| matches!(cell, Data::String(_)));
+            (row, cells.len(), text)
+        })
+        .collect();
+    let widest = rows.iter().map(|&(_, count, _)| count).max().unwrap_or(0);
+    rows.iter()
+        .find(|&&(_, count, text)| text && count > 0 && count >= widest.min(2) && count * 2 >= widest)
+        .map_or(bounds.start.0, |&(row, _, _)| row)
+}
+
+/// Join the parts of a multi-row header with spaces. Repeats from a cell
+/// merged down over several header rows appear once.
+fn join_header<'a>(parts: impl Iterator<Item = &'a Data>) -> String {
+    let mut names: Vec<String> = Vec::new();
+    for part in parts {
+        let part = cell_to_string(part).trim().to_string();
+        if !part.is_empty() && names.last() != Some(&part) {
+            names.push(part);
+        }
+    }
+    names.join(" ")
+}
+
 fn cell_to_string(cell: &Data) -> String {
     match cell {
         Data::Empty => String::new(),
@@ -163,4 +393,62 @@ fn convert_cell(cell: &Data) -> CellValue {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn grid_range(rows: &[&[&str]]) -> Range<Data> {
+        let mut range = Range::new((0, 0), (rows.len() as u32 - 1, 2));
+        for (r, row) in rows.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let value = match cell.parse::<f64>() {
+                    Ok(n) => Data::Float(n),
+                    Err(_) if cell.is_empty() => Data::Empty,
+                    Err(_) => Data::String(cell.to_string()),
+                };
+                range.set_value((r as u32, c as u32), value);
+            }
+        }
+        range
+    }
+
+    #[test]
+    fn test_title_rows_and_merged_headers() {
+        let range = grid_range(&[
+            &["Quarterly report", "", ""],
+            &["", "", ""],
+            &["Region", "Sales", ""],
+            &["", "Q1", "Q2"],
+            &["North", "10", "12"],
+            &["", "7", "9"],
+        ]);
+        // Title over A1:C1, "Region" down A3:A4, "Sales" over B3:C3, "North" down A5:A6
+        let merges = ["A1:C1", "A3:A4", "B3:C3", "A5:A6"].map(|r| CellRect::parse(r).unwrap());
+        let grid = Grid {
+            range: &range,
+            merges: &merges,
+        };
+        let bounds = CellRect::parse("A1:C6").unwrap();
+        assert_eq!(detect_header(&grid, bounds), 2);
+
+        let config = Config::default()
+            .with_header_row(HeaderRow::Auto)
+            .with_header_rows(2);
+        let table = parse_grid(&grid, bounds, None, &config).unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Region", "Sales Q1", "Sales Q2"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[1].cells[0], CellValue::from("North"));
+        assert_eq!(table.rows[1].source_line, 6);
+    }
+
+    #[test]
+    fn test_sheet_reference() {
+        assert_eq!(
+            sheet_reference("'Q1 ''24'!$B$2:$D$9"),
+            Some((Some("Q1 '24".to_string()), CellRect::parse("B2:D9").unwrap()))
+        );
+        assert_eq!(sheet_reference("A1:C3").map(|r| r.0), Some(None));
+        assert_eq!(sheet_reference("not a range"), None);
+    }
+}
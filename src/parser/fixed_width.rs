@@ -0,0 +1,284 @@
+//! Fixed-width text parser driven by a column layout
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rayon::prelude::*;
+
+use crate::config::Config;
+use crate::model::{CellType, CellValue, Column, Decimal, Table};
+
+use super::Parser;
+
+/// One column of a fixed-width layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthField {
+    pub name: String,
+    /// First character of the field, counting from 0
+    pub start: usize,
+    pub width: usize,
+    pub cell_type: CellType,
+    /// Implied decimal places: `0001250` with 2 reads as 12.50
+    pub decimals: u8,
+}
+
+/// Column layout of a fixed-width file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixedWidthLayout {
+    pub fields: Vec<FixedWidthField>,
+}
+
+impl FixedWidthLayout {
+    /// Read a layout file
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read layout file: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid layout file: {}", path.display()))
+    }
+
+    /// Parse a layout: one field per line giving its name, start (counting
+    /// from 1), width and optionally its type and implied decimals, separated
+    /// by whitespace or commas. `#` starts a comment, and a first line whose
+    /// start is not a number is taken to be a header.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut fields: Vec<FixedWidthField> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let parts: Vec<&str> = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|part| !part.is_empty())
+                .collect();
+            let field = match parts.as_slice() {
+                [] => continue,
+                [_, start, ..] if fields.is_empty() && start.parse::<usize>().is_err() => continue,
+                [name, start, width, rest @ ..] if rest.len() <= 2 => {
+                    parse_field(name, start, width, rest)
+                        .with_context(|| format!("Line {}: {}", i + 1, line.trim()))?
+                }
+                _ => bail!(
+                    "Line {}: expected name, start, width, type and decimals: {}",
+                    i + 1,
+                    line.trim()
+                ),
+            };
+            if fields.iter().any(|f| f.name == field.name) {
+                bail!("Line {}: duplicate column {}", i + 1, field.name);
+            }
+            fields.push(field);
+        }
+        if fields.is_empty() {
+            bail!("Layout has no columns");
+        }
+        Ok(Self { fields })
+    }
+}
+
+fn parse_field(name: &str, start: &str, width: &str, rest: &[&str]) -> Result<FixedWidthField> {
+    let start: usize = start.parse().context("Invalid start")?;
+    let width: usize = width.parse().context("Invalid width")?;
+    if start == 0 || width == 0 {
+        bail!("Start and width must be at least 1");
+    }
+    let mut cell_type = match rest.first().map(|t| t.to_lowercase()).as_deref() {
+        None | Some("string" | "str" | "text" | "char") => CellType::String,
+        Some("int" | "integer") => CellType::Int,
+        Some("decimal" | "numeric" | "number") => CellType::Decimal,
+        Some("float" | "real" | "double") => CellType::Float,
+        Some("date") => CellType::Date,
+        Some("datetime" | "timestamp") => CellType::DateTime,
+        Some("time") => CellType::Time,
+        Some("bool" | "boolean") => CellType::Bool,
+        Some(other) => bail!("Unknown type: {}", other),
+    };
+    let decimals: u8 = match rest.get(1) {
+        Some(decimals) => decimals.parse().context("Invalid decimals")?,
+        None => 0,
+    };
+    if decimals > 38 {
+        bail!("At most 38 implied decimals");
+    }
+    if decimals > 0 {
+        if !matches!(cell_type, CellType::Int | CellType::Decimal) {
+            bail!("Implied decimals need an int or decimal column");
+        }
+        cell_type = CellType::Decimal;
+    }
+    Ok(FixedWidthField {
+        name: name.to_string(),
+        start: start - 1,
+        width,
+        cell_type,
+        decimals,
+    })
+}
+
+/// Parser for fixed-width files; the layout comes from the config
+pub struct FixedWidthParser;
+
+impl Parser for FixedWidthParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let Some(layout) = config.fixed_width_layout.as_ref() else {
+            bail!(
+                "Fixed-width files need a column layout (--fixed-width LAYOUT_FILE): {}",
+                path.display()
+            );
+        };
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+        let columns: Vec<Column> = layout
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| Column::with_type(field.name.clone(), i, field.cell_type))
+            .collect();
+        let mut table = Table::new(columns);
+
+        // Set key columns if specified
+        if !config.key_columns.is_empty() {
+            table.set_key_columns(&config.key_columns);
+        }
+
+        // Blank lines are skipped; source lines are the file's own
+        let lines: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+        let rows: Vec<_> = lines
+            .par_iter()
+            .map(|&(i, line)| {
+                let cells = layout
+                    .fields
+                    .iter()
+                    .map(|field| field_value(slice(line, field.start, field.width), field))
+                    .collect();
+                (cells, i + 1)
+            })
+            .collect();
+        table.extend_rows(rows);
+
+        // Sort if requested
+        if let Some(ref sort_col) = config.sort_by {
+            table.sort_by_column(sort_col);
+        }
+
+        Ok(table)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(ext.to_lowercase().as_str(), "fwf")
+    }
+}
+
+/// Characters `start..start + width` of a line, or as many as it has
+fn slice(line: &str, start: usize, width: usize) -> &str {
+    let byte = |chars: usize| {
+        if line.is_ascii() {
+            chars.min(line.len())
+        } else {
+            line.char_indices()
+                .nth(chars)
+                .map_or(line.len(), |(i, _)| i)
+        }
+    };
+    &line[byte(start)..byte(start + width)]
+}
+
+/// Read a field as its declared type; values that do not parse stay strings
+fn field_value(text: &str, field: &FixedWidthField) -> CellValue {
+    let text = text.trim();
+    if text.is_empty() {
+        return CellValue::Null;
+    }
+    let parsed = match field.cell_type {
+        CellType::Int => signed(text).parse().ok().map(CellValue::Int),
+        CellType::Decimal if field.decimals > 0 => signed(text)
+            .parse::<i128>()
+            .ok()
+            .map(|value| CellValue::Decimal(Decimal::new(value, field.decimals as i8))),
+        CellType::Decimal => Decimal::parse(&signed(text)).map(CellValue::Decimal),
+        CellType::Float => signed(text).parse().ok().map(CellValue::Float),
+        CellType::Date => ["%Y-%m-%d", "%Y%m%d"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+            .map(CellValue::Date),
+        CellType::DateTime => ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y%m%d%H%M%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+            .map(CellValue::DateTime),
+        CellType::Time => ["%H:%M:%S", "%H%M%S"]
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(text, format).ok())
+            .map(CellValue::Time),
+        CellType::Bool => match text.to_lowercase().as_str() {
+            "y" | "yes" | "t" | "true" | "1" => Some(CellValue::Bool(true)),
+            "n" | "no" | "f" | "false" | "0" => Some(CellValue::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| CellValue::String(text.to_string().into()))
+}
+
+/// A number with its sign in front: extracts often put it last (`1250-`)
+fn signed(text: &str) -> String {
+    let text = text.strip_prefix('+').unwrap_or(text);
+    match text.strip_suffix(['-', '+']) {
+        Some(digits) if text.ends_with('-') => format!("-{}", digits.trim()),
+        Some(digits) => digits.trim().to_string(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_and_parse() {
+        let layout = FixedWidthLayout::parse(
+            "name start width type decimals\n\
+             id      1  4  int\n\
+             name    5  6            # text by default\n\
+             amount 11  7  decimal 2\n\
+             opened 18  8  date\n",
+        )
+        .unwrap();
+        assert_eq!(layout.fields[1].start, 4);
+        assert_eq!(layout.fields[1].cell_type, CellType::String);
+        assert!(FixedWidthLayout::parse("id 1 4 money").is_err());
+        assert!(FixedWidthLayout::parse("id 1 4 string 2").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("accounts.fwf");
+        fs::write(
+            &path,
+            "0001Zoë   000125020240131\n\n0002Bob   000075-\n0003",
+        )
+        .unwrap();
+        let config = Config::default().with_fixed_width_layout(layout);
+        let table = FixedWidthParser.parse(&path, &config).unwrap();
+        assert_eq!(table.columns[2].inferred_type, CellType::Decimal);
+        assert_eq!(
+            table.rows[0].cells,
+            vec![
+                CellValue::Int(1),
+                CellValue::String("Zoë".into()),
+                CellValue::Decimal(Decimal::new(1250, 2)),
+                CellValue::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()),
+            ]
+        );
+        assert_eq!(
+            table.rows[1].cells[2],
+            CellValue::Decimal(Decimal::new(-75, 2))
+        );
+        assert_eq!(table.rows[1].cells[3], CellValue::Null);
+        assert_eq!(table.rows[1].source_line, 3);
+        assert_eq!(table.rows[2].cells[1], CellValue::Null);
+
+        assert!(FixedWidthParser.parse(&path, &Config::default()).is_err());
+    }
+}
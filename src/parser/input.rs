@@ -0,0 +1,175 @@
+//! Inputs the parsers cannot open directly: stdin, pipes and compressed files
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tempfile::TempDir;
+
+/// Compression recognised by its magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// Detect compression from the first bytes of a file
+    pub fn detect(magic: &[u8]) -> Option<Self> {
+        if magic.starts_with(b"\x1F\x8B") {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(b"\x28\xB5\x2F\xFD") {
+            Some(Compression::Zstd)
+        } else if magic.starts_with(b"BZh") && magic.get(3).is_some_and(u8::is_ascii_digit) {
+            Some(Compression::Bzip2)
+        } else if magic.starts_with(b"\xFD7zXZ\x00") {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Whether a file extension names a compression
+    fn is_extension(ext: &str) -> bool {
+        matches!(
+            ext.to_lowercase().as_str(),
+            "gz" | "gzip" | "zst" | "zstd" | "bz2" | "xz"
+        )
+    }
+
+    fn decoder(self, reader: Box<dyn Read>) -> Result<Box<dyn Read>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+/// A data file the parsers can open: the input itself, or a temporary copy
+/// of stdin, a pipe or a decompressed file
+pub struct Input {
+    path: PathBuf,
+    _temp: Option<TempDir>,
+}
+
+impl Input {
+    /// Make an input argument readable; `-` is stdin. Plain files,
+    /// directories and anything else that is not a pipe are used as they are.
+    pub fn open(arg: &Path) -> Result<Self> {
+        if arg == Path::new("-") {
+            return Self::copy(Box::new(io::stdin().lock()), OsStr::new("stdin"));
+        }
+        let plain = || Self {
+            path: arg.to_path_buf(),
+            _temp: None,
+        };
+        let Ok(metadata) = std::fs::metadata(arg) else {
+            return Ok(plain());
+        };
+        if metadata.is_dir() {
+            return Ok(plain());
+        }
+        let mut file =
+            File::open(arg).with_context(|| format!("Failed to open {}", arg.display()))?;
+        if metadata.is_file() {
+            let mut magic = Vec::with_capacity(6);
+            file.by_ref().take(6).read_to_end(&mut magic)?;
+            if Compression::detect(&magic).is_none() {
+                return Ok(plain());
+            }
+            file = File::open(arg)?;
+        }
+        // Compressed files, and pipes such as `<(...)`, which can only be read once
+        let name = arg.file_name().unwrap_or(OsStr::new("input"));
+        Self::copy(Box::new(file), name)
+            .with_context(|| format!("Failed to read {}", arg.display()))
+    }
+
+    /// Path for the parsers to read
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Copy a stream to a temporary file, decompressing it if its magic bytes
+    /// say so. A compression extension is dropped from the name, so that
+    /// `data.csv.gz` is read as `data.csv`.
+    fn copy(mut reader: Box<dyn Read>, name: &OsStr) -> Result<Self> {
+        let mut magic = Vec::with_capacity(6);
+        reader.by_ref().take(6).read_to_end(&mut magic)?;
+        let compression = Compression::detect(&magic);
+        let mut reader: Box<dyn Read> = Box::new(Cursor::new(magic).chain(reader));
+        let mut name = PathBuf::from(name);
+        if let Some(compression) = compression {
+            reader = compression.decoder(reader)?;
+            if name
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(Compression::is_extension)
+            {
+                name.set_extension("");
+            }
+        }
+
+        let temp = tempfile::tempdir().context("Failed to create a temporary directory")?;
+        let path = temp.path().join(name);
+        let mut file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        io::copy(&mut reader, &mut file)?;
+        Ok(Self {
+            path,
+            _temp: Some(temp),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = "id,name\n1,a\n";
+        let compressed: [(&str, Vec<u8>); 4] = [
+            ("data.csv.gz", {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(csv.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+            ("data.csv.zst", zstd::encode_all(csv.as_bytes(), 0).unwrap()),
+            ("data.csv.bz2", {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(csv.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+            ("data", {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(csv.as_bytes()).unwrap();
+                encoder.finish().unwrap()
+            }),
+        ];
+        for (name, bytes) in compressed {
+            let path = dir.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+            let input = Input::open(&path).unwrap();
+            assert_ne!(input.path(), path);
+            let expected = if name == "data" { "data" } else { "data.csv" };
+            assert_eq!(input.path().file_name().unwrap(), expected);
+            assert_eq!(std::fs::read_to_string(input.path()).unwrap(), csv);
+        }
+
+        let path = dir.path().join("plain.csv");
+        std::fs::write(&path, csv).unwrap();
+        assert_eq!(Input::open(&path).unwrap().path(), path);
+    }
+}
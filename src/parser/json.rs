@@ -12,6 +12,7 @@ use serde_json::Value;
 use crate::config::Config;
 use crate::model::{CellValue, Column, Table};
 
+use super::records::{select, Document};
 use super::Parser;
 
 /// Parser for JSON array files
@@ -26,15 +27,19 @@ impl Parser for JsonParser {
         let value: Value =
             serde_json::from_reader(reader).context("Failed to parse JSON file")?;
 
-        // Handle both arrays and single objects
-        let array = match value {
-            Value::Array(arr) => arr,
-            Value::Object(_) => vec![value],
+        // Handle both arrays and single objects, or the records a path selects
+        let array = match (value, &config.record_path) {
+            (value, Some(record_path)) => select(value, record_path)?,
+            (Value::Array(arr), None) => arr,
+            (value @ Value::Object(_), None) => vec![value],
             _ => bail!("JSON must be an array or object"),
         };
 
         if array.is_empty() {
-            bail!("JSON array is empty");
+            match config.record_path {
+                Some(ref path) => bail!("Record path {} selects no records", path),
+                None => bail!("JSON array is empty"),
+            }
         }
 
         // Collect all unique keys across all objects to build column list
@@ -91,6 +96,22 @@ impl Parser for JsonParser {
     }
 }
 
+impl Document for Value {
+    fn get(self, key: &str) -> Option<Self> {
+        match self {
+            Value::Object(mut obj) => obj.remove(key),
+            _ => None,
+        }
+    }
+
+    fn items(self) -> Result<Vec<Self>, Self> {
+        match self {
+            Value::Array(arr) => Ok(arr),
+            other => Err(other),
+        }
+    }
+}
+
 fn json_value_to_cell(value: Option<&Value>) -> CellValue {
     match value {
         None | Some(Value::Null) => CellValue::Null,
@@ -118,12 +139,12 @@ fn json_value_to_cell(value: Option<&Value>) -> CellValue {
             CellValue::String(Cow::Owned(s.clone()))
         }
         Some(Value::Array(arr)) => {
-            // Serialize array back to JSON string
-            CellValue::String(Cow::Owned(serde_json::to_string(arr).unwrap_or_default()))
-        }
-        Some(Value::Object(obj)) => {
-            // Serialize object back to JSON string
-            CellValue::String(Cow::Owned(serde_json::to_string(obj).unwrap_or_default()))
+            CellValue::List(arr.iter().map(|v| json_value_to_cell(Some(v))).collect())
         }
+        Some(Value::Object(obj)) => CellValue::Struct(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), json_value_to_cell(Some(v))))
+                .collect(),
+        ),
     }
 }
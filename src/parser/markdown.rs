@@ -0,0 +1,171 @@
+//! Markdown pipe-table and ASCII table parser
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+use crate::model::{CellValue, Column, Table};
+
+use super::csv::{infer_column_types, parse_cell_value};
+use super::Parser;
+
+/// Vertical bars that separate cells: ASCII and box-drawing
+const CELL_SEPARATORS: [char; 4] = ['|', '│', '┃', '║'];
+
+/// Parser for the first table in a Markdown file, or in text holding an
+/// ASCII (`+---+`) or box-drawing table
+pub struct MarkdownParser;
+
+impl Parser for MarkdownParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+        let mut header: Option<Vec<String>> = None;
+        let mut rows = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if is_border(line) {
+                continue;
+            }
+            let Some(cells) = split_row(line) else {
+                // The table ends at the first line that is not part of it
+                if header.is_some() {
+                    break;
+                }
+                continue;
+            };
+            match header {
+                None => header = Some(cells),
+                // The `|---|:--:|` line under a Markdown header
+                Some(_) if rows.is_empty() && cells.iter().all(|c| is_alignment(c)) => {}
+                Some(_) => rows.push((cells, i + 1)),
+            }
+        }
+        let header = header.with_context(|| format!("No table found in {}", path.display()))?;
+
+        let columns: Vec<Column> = header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Column::new(name.clone(), i))
+            .collect();
+        let column_count = columns.len();
+        let mut table = Table::new(columns);
+
+        // Set key columns if specified
+        if !config.key_columns.is_empty() {
+            table.set_key_columns(&config.key_columns);
+        }
+
+        // Pad with nulls if a row has fewer cells than the header
+        let rows = rows
+            .into_iter()
+            .map(|(cells, line)| {
+                let mut cells: Vec<CellValue> = cells.iter().map(|c| parse_cell_value(c)).collect();
+                cells.resize(column_count, CellValue::Null);
+                (cells, line)
+            })
+            .collect();
+        table.extend_rows(rows);
+        infer_column_types(&mut table);
+
+        // Sort if requested
+        if let Some(ref sort_col) = config.sort_by {
+            table.sort_by_column(sort_col);
+        }
+
+        Ok(table)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(ext.to_lowercase().as_str(), "md" | "markdown")
+    }
+}
+
+/// Border lines of ASCII and box-drawing tables: `+----+`, `├────┼────┤`
+fn is_border(line: &str) -> bool {
+    line.starts_with("+-")
+        || line.starts_with("+=")
+        || line.chars().next().is_some_and(|c| {
+            ('\u{2500}'..='\u{257F}').contains(&c) && !CELL_SEPARATORS.contains(&c)
+        })
+}
+
+/// Cells of a table row, which starts with a vertical bar; `\|` is a bar
+/// inside a cell
+fn split_row(line: &str) -> Option<Vec<String>> {
+    let line = line.strip_prefix(CELL_SEPARATORS)?;
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&'|') {
+            cell.push('|');
+            chars.next();
+        } else if CELL_SEPARATORS.contains(&c) {
+            cells.push(cell.trim().to_string());
+            cell.clear();
+        } else {
+            cell.push(c);
+        }
+    }
+    // A row need not end with a bar
+    if !cell.trim().is_empty() {
+        cells.push(cell.trim().to_string());
+    }
+    Some(cells)
+}
+
+/// A cell of the Markdown header separator: `---`, `:--`, `:-:`, `--:`
+fn is_alignment(cell: &str) -> bool {
+    let dashes = cell.strip_prefix(':').unwrap_or(cell);
+    let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+    !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CellType;
+
+    #[test]
+    fn test_markdown_and_ascii_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let markdown = dir.path().join("report.md");
+        fs::write(
+            &markdown,
+            "# Accounts\n\nSome prose.\n\n\
+             | id | name | note |\n\
+             |---:|:-----|------|\n\
+             | 1 | a \\| b | - |\n\
+             | 2 | c |\n\n\
+             | other | table |\n",
+        )
+        .unwrap();
+        let table = MarkdownParser.parse(&markdown, &Config::default()).unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "name", "note"]);
+        assert_eq!(table.columns[0].inferred_type, CellType::Int);
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.rows[0].cells[1], CellValue::String("a | b".into()));
+        assert_eq!(table.rows[0].cells[2], CellValue::String("-".into()));
+        assert_eq!(table.rows[1].cells[2], CellValue::Null);
+        assert_eq!(table.rows[1].source_line, 8);
+
+        let ascii = dir.path().join("out.md");
+        fs::write(
+            &ascii,
+            "+----+------+\n| id | name |\n+====+======+\n| 1  | a    |\n+----+------+\n\n\
+             ┌────┬──────┐\n│ id │ name │\n├────┼──────┤\n│ 2  │ b    │\n└────┴──────┘\n",
+        )
+        .unwrap();
+        let table = MarkdownParser.parse(&ascii, &Config::default()).unwrap();
+        assert_eq!(table.row_count(), 1);
+        assert_eq!(
+            table.rows[0].cells,
+            vec![CellValue::Int(1), CellValue::String("a".into())]
+        );
+    }
+}
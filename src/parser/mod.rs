@@ -1,21 +1,48 @@
 //! Parser layer for reading various tabular data formats
 
+mod arrow_ipc;
+mod avro;
 mod csv;
+mod dataset;
 mod excel;
+mod fixed_width;
+mod input;
 mod json;
+mod markdown;
 mod parquet;
+mod records;
+mod sqlite;
+mod xlsx_format;
+mod xlsx_package;
+mod xml;
+mod yaml;
 
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
 
 use crate::config::Config;
+use crate::diff::ColumnMapping;
 use crate::model::Table;
 
+pub use self::arrow_ipc::ArrowIpcParser;
+pub use self::avro::AvroParser;
 pub use self::csv::CsvParser;
+pub(crate) use self::dataset::content_hash;
+pub use self::dataset::{partition_name, Dataset, DatasetFile, DEFAULT_PARTITION};
 pub use self::excel::ExcelParser;
+pub use self::fixed_width::{FixedWidthField, FixedWidthLayout, FixedWidthParser};
+pub use self::input::{Compression, Input};
 pub use self::json::JsonParser;
+pub use self::markdown::MarkdownParser;
 pub use self::parquet::ParquetParser;
+pub use self::records::RecordPath;
+pub use self::sqlite::SqliteParser;
+pub use self::xlsx_format::{CellStyle, SheetFormat};
+pub use self::xml::XmlParser;
+pub use self::yaml::YamlParser;
 
 /// Trait for parsing tabular data files
 pub trait Parser: Send + Sync {
@@ -29,6 +56,8 @@ pub trait Parser: Send + Sync {
 /// Factory for creating parsers based on file extension
 pub struct ParserFactory {
     parsers: Vec<Box<dyn Parser>>,
+    /// Formats set for paths, overriding their extensions
+    formats: HashMap<PathBuf, String>,
 }
 
 impl Default for ParserFactory {
@@ -46,58 +75,262 @@ impl ParserFactory {
                 Box::new(ExcelParser),
                 Box::new(ParquetParser),
                 Box::new(JsonParser),
+                Box::new(ArrowIpcParser),
+                Box::new(AvroParser),
+                Box::new(SqliteParser),
+                Box::new(FixedWidthParser),
+                Box::new(MarkdownParser),
+                Box::new(XmlParser),
+                Box::new(YamlParser),
             ],
+            formats: HashMap::new(),
         }
     }
 
+    /// Read a path as the given format (an extension such as `csv` or
+    /// `parquet`) whatever its own extension says
+    pub fn set_format(&mut self, path: &Path, format: &str) -> Result<()> {
+        if self.parser_for(format).is_none() {
+            bail!("Unsupported format: {}", format);
+        }
+        self.formats.insert(path.to_path_buf(), format.to_lowercase());
+        Ok(())
+    }
+
     /// Get a parser for the given file path
     pub fn get_parser(&self, path: &Path) -> Result<&dyn Parser> {
-        let ext = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        for parser in &self.parsers {
-            if parser.supports_extension(&ext) {
-                return Ok(parser.as_ref());
-            }
+        // `db.sqlite:table` and `db.sqlite?query=...` read from a database
+        if sqlite::split_source(path).is_some() {
+            return Ok(&SqliteParser);
         }
 
-        bail!(
-            "Unsupported file format: {}",
-            path.extension()
+        self.parser_for(&self.format(path)).with_context(|| {
+            format!(
+                "Unsupported file format: {}",
+                path.extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("unknown")
+            )
+        })
+    }
+
+    /// Whether a file found in a directory is data to compare. Markdown files
+    /// are read when named, but in a directory are more likely a README.
+    pub fn is_data_file(&self, path: &Path) -> bool {
+        self.get_parser(path).is_ok() && !MarkdownParser.supports_extension(&self.format(path))
+    }
+
+    /// Get a parser for a file, guessing its format from the content when
+    /// neither a set format nor the extension picks one
+    fn sniff_parser(&self, path: &Path) -> Result<&dyn Parser> {
+        self.get_parser(path).or_else(|err| {
+            detect_format(path)
+                .and_then(|format| self.parser_for(format))
+                .ok_or(err)
+        })
+    }
+
+    fn parser_for(&self, format: &str) -> Option<&dyn Parser> {
+        self.parsers
+            .iter()
+            .find(|parser| parser.supports_extension(format))
+            .map(|parser| parser.as_ref())
+    }
+
+    /// The format set for a path, or else its extension
+    fn format(&self, path: &Path) -> String {
+        match self.formats.get(path) {
+            Some(format) => format.clone(),
+            None => path
+                .extension()
                 .and_then(|e| e.to_str())
-                .unwrap_or("unknown")
-        )
+                .unwrap_or("")
+                .to_lowercase(),
+        }
     }
 
-    /// Parse a file using the appropriate parser
+    /// Parse a file using the appropriate parser, applying key normalisation,
+    /// the row filter and the column include list. Directories and glob
+    /// patterns are read as one dataset; files with no known extension are
+    /// read as the format their content looks like.
     pub fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
-        let parser = self.get_parser(path)?;
-        parser.parse(path, config)
+        if let Some(dataset) = Dataset::open(path, self)? {
+            return self.parse_dataset(&dataset, config);
+        }
+        let parser = self.sniff_parser(path)?;
+        let table = parser.parse(path, config)?;
+        Ok(Self::prepare(table, config))
+    }
+
+    /// Parse the files of a dataset in parallel into one table, with a column
+    /// per partition level. Partitions the row filter rules out are not read.
+    pub fn parse_dataset(&self, dataset: &Dataset, config: &Config) -> Result<Table> {
+        let file_config = Config {
+            sort_by: None,
+            ..config.clone()
+        };
+        let files: Vec<&DatasetFile> = dataset.files_matching(config.filter.as_ref()).collect();
+        let tables = files
+            .par_iter()
+            .map(|file| {
+                self.get_parser(&file.path)?
+                    .parse(&file.path, &file_config)
+                    .with_context(|| format!("Failed to parse {}", file.path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut table = Dataset::union(&files, tables);
+        if !config.key_columns.is_empty() {
+            table.set_key_columns(&config.key_columns);
+        }
+        if let Some(ref sort_col) = config.sort_by {
+            table.sort_by_column(sort_col);
+        }
+        Ok(Self::prepare(table, config))
+    }
+
+    /// Parse every sheet of a workbook, or every table of a SQLite database,
+    /// with each one's key columns
+    pub fn parse_workbook(&self, path: &Path, config: &Config) -> Result<Vec<(String, Table)>> {
+        let mut format = self.format(path);
+        if self.parser_for(&format).is_none() {
+            format = detect_format(path).unwrap_or_default().to_string();
+        }
+        let sheets = if ExcelParser.supports_extension(&format) {
+            ExcelParser.parse_sheets(path, config)?
+        } else if SqliteParser.supports_extension(&format) && path.is_file() {
+            SqliteParser.parse_tables(path, config)?
+        } else {
+            bail!("Not a workbook or database: {}", path.display());
+        };
+        Ok(sheets
+            .into_iter()
+            .map(|(name, mut table)| {
+                let keys = config.key_columns_for_sheet(&name);
+                if !keys.is_empty() {
+                    table.set_key_columns(keys);
+                }
+                (name, Self::prepare(table, config))
+            })
+            .collect())
+    }
+
+    /// Config to parse each file of a pair with. When columns are mapped,
+    /// keys, sort, filter and include list name old columns, so they are
+    /// left for `prepare_pair` to apply once the new table is renamed.
+    pub fn parse_config(config: &Config) -> Config {
+        if config.column_mapping.is_empty() {
+            return config.clone();
+        }
+        Config {
+            key_columns: Vec::new(),
+            sheet_keys: Vec::new(),
+            sort_by: None,
+            filter: None,
+            include_columns: Vec::new(),
+            ..config.clone()
+        }
+    }
+
+    /// Match the columns of two tables parsed with `parse_config`, renaming
+    /// mapped columns of the new table to their old names, then apply `keys`
+    /// (by either name), sort, filter and include list to both. Returns the
+    /// mapping between the renamed tables.
+    pub fn prepare_pair(
+        old: Table,
+        mut new: Table,
+        keys: &[String],
+        config: &Config,
+    ) -> (Table, Table, ColumnMapping) {
+        let (mapping, renames) = config.column_mapping.apply(&old, &mut new);
+        if config.column_mapping.is_empty() {
+            return (old, new, mapping);
+        }
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|key| {
+                let renamed = renames.iter().find(|(new_name, _)| new_name == key);
+                match renamed {
+                    Some((_, old_name)) if old.column_index(key).is_none() => old_name.clone(),
+                    _ => key.clone(),
+                }
+            })
+            .collect();
+        let config = Config {
+            key_columns: keys.clone(),
+            ..config.clone()
+        };
+        let [old, new] = [old, new].map(|mut table| {
+            if !keys.is_empty() {
+                table.set_key_columns(&keys);
+            }
+            if let Some(ref sort_col) = config.sort_by {
+                table.sort_by_column(sort_col);
+            }
+            Self::prepare(table, &config)
+        });
+        (old, new, mapping)
+    }
+
+    fn prepare(mut table: Table, config: &Config) -> Table {
+        table.set_key_normalization(config.key_normalization());
+        if let Some(ref filter) = config.filter {
+            filter.apply(&mut table);
+        }
+        // After filtering, so filters can use columns that are not compared
+        if !config.include_columns.is_empty() {
+            let key_columns = table.key_columns.clone();
+            table.retain_columns(|column| {
+                key_columns.contains(&column.index) || config.includes_column(&column.name)
+            });
+        }
+        table
     }
 }
 
-/// Detect file format from content (for files without extension)
+/// Detect file format from content (for files without extension); the name
+/// returned is an extension its parser supports
 pub fn detect_format(path: &Path) -> Option<&'static str> {
     use std::fs::File;
     use std::io::{BufRead, BufReader};
 
     let file = File::open(path).ok()?;
     let mut reader = BufReader::new(file);
-    let mut buffer = [0u8; 8];
+    let mut buffer = [0u8; 16];
     let bytes_read = std::io::Read::read(&mut reader, &mut buffer).ok()?;
 
     if bytes_read < 4 {
         return None;
     }
 
+    // Compressed files are read through `Input`, which decompresses them
+    if Compression::detect(&buffer[..bytes_read]).is_some() {
+        return None;
+    }
+
     // Check for Parquet magic bytes
     if &buffer[0..4] == b"PAR1" {
         return Some("parquet");
     }
 
+    // Check for Arrow IPC files (Feather v2) and streams, which start with
+    // a continuation marker
+    if buffer[..bytes_read].starts_with(arrow_ipc::ARROW_MAGIC)
+        || &buffer[0..4] == b"\xFF\xFF\xFF\xFF"
+    {
+        return Some("arrow");
+    }
+
+    // Check for Avro object container files
+    if &buffer[0..4] == avro::AVRO_MAGIC {
+        return Some("avro");
+    }
+
+    // Check for SQLite databases
+    if buffer[..bytes_read].starts_with(sqlite::SQLITE_MAGIC) {
+        return Some("sqlite");
+    }
+
     // Check for Excel ZIP format (xlsx)
     if &buffer[0..4] == b"PK\x03\x04" {
         return Some("xlsx");
@@ -117,6 +350,23 @@ pub fn detect_format(path: &Path) -> Option<&'static str> {
         return Some("json");
     }
 
+    // Markdown, ASCII and box-drawing tables start with a bar or a border
+    if trimmed.starts_with(['|', '┌', '╔']) || trimmed.starts_with("+-") {
+        return Some("md");
+    }
+
+    // XML declarations and root elements, and YAML documents
+    if trimmed.starts_with('<') {
+        return Some("xml");
+    }
+    if trimmed.starts_with("---")
+        || trimmed.starts_with("%YAML")
+        || trimmed.starts_with("- ")
+        || (trimmed.trim_end().ends_with(':') && !trimmed.contains([',', '\t']))
+    {
+        return Some("yaml");
+    }
+
     // Default to CSV
     Some("csv")
 }
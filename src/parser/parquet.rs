@@ -4,18 +4,35 @@ use std::borrow::Cow;
 use std::fs::File;
 use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use arrow::array::{
-    Array, ArrayRef, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
-    Int32Array, Int64Array, Int8Array, StringArray, TimestampMicrosecondArray,
+    Array, ArrayRef, AsArray, BinaryArray, BinaryViewArray, BooleanArray, Date32Array,
+    Date64Array, Decimal128Array, Decimal256Array, DurationMicrosecondArray,
+    DurationMillisecondArray, DurationNanosecondArray, DurationSecondArray, FixedSizeBinaryArray,
+    FixedSizeListArray, Float16Array, Float32Array, Float64Array, Int16Array, Int32Array,
+    Int64Array, Int8Array, IntervalDayTimeArray, IntervalMonthDayNanoArray,
+    IntervalYearMonthArray, LargeBinaryArray, LargeListArray, LargeStringArray, ListArray,
+    MapArray, StringArray, StringViewArray, StructArray, Time32MillisecondArray,
+    Time32SecondArray, Time64MicrosecondArray, Time64NanosecondArray, TimestampMicrosecondArray,
     TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray, UInt16Array,
-    UInt32Array, UInt64Array, UInt8Array,
+    UInt32Array, UInt64Array, UInt8Array, UnionArray,
 };
-use arrow::datatypes::DataType as ArrowType;
-use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use arrow::datatypes::{i256, DataType as ArrowType, IntervalUnit, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, NaiveTime};
+use indexmap::IndexMap;
+use parquet::arrow::arrow_reader::{
+    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReader,
+    ParquetRecordBatchReaderBuilder,
+};
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::statistics::Statistics;
+use rayon::prelude::*;
 
 use crate::config::Config;
-use crate::model::{CellType, CellValue, Column, Table};
+use crate::filter::ColumnStats;
+use crate::model::{CellType, CellValue, Column, Decimal, Table};
 
 use super::Parser;
 
@@ -27,13 +44,30 @@ impl Parser for ParquetParser {
         let file = File::open(path)
             .with_context(|| format!("Failed to open Parquet file: {}", path.display()))?;
 
-        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        let reader_metadata = ArrowReaderMetadata::load(&file, ArrowReaderOptions::default())
             .context("Failed to create Parquet reader")?;
+        let schema = reader_metadata.schema().clone();
+        let metadata = reader_metadata.metadata().clone();
+
+        // Only decode the columns the diff will look at
+        let projection = projected_columns(&schema, config);
+        let mask = ProjectionMask::roots(
+            reader_metadata.parquet_schema(),
+            projection.iter().copied(),
+        );
 
-        let schema = builder.schema().clone();
-        let reader = builder.build().context("Failed to build Parquet reader")?;
+        // Skip row groups whose statistics rule out the row filter
+        let row_groups: Vec<usize> = match config.filter {
+            Some(ref filter) => (0..metadata.num_row_groups())
+                .filter(|&rg| {
+                    filter.may_match(&|name: &str| row_group_stats(&metadata, &schema, rg, name))
+                })
+                .collect(),
+            None => (0..metadata.num_row_groups()).collect(),
+        };
 
-        // Create columns from schema
+        // Create columns from schema; ignored columns are kept, with null values,
+        // so the schema compares the same as other formats'
         let columns: Vec<Column> = schema
             .fields()
             .iter()
@@ -50,21 +84,33 @@ impl Parser for ParquetParser {
             table.set_key_columns(&config.key_columns);
         }
 
-        // Read record batches
-        let mut line_num = 1usize;
-        for batch_result in reader {
-            let batch = batch_result.context("Failed to read Parquet batch")?;
+        // Row numbers in the file where each row group starts
+        let mut row_starts = Vec::with_capacity(metadata.num_row_groups());
+        let mut next_start = 0usize;
+        for rg in metadata.row_groups() {
+            row_starts.push(next_start);
+            next_start += rg.num_rows() as usize;
+        }
 
-            for row_idx in 0..batch.num_rows() {
-                line_num += 1;
-                let cells: Vec<CellValue> = batch
-                    .columns()
-                    .iter()
-                    .map(|col| extract_cell_value(col, row_idx))
-                    .collect();
+        // Decode row groups in parallel; collecting keeps them in file order
+        let decoded: Vec<Vec<(Vec<CellValue>, usize)>> = row_groups
+            .par_iter()
+            .map(|&rg| {
+                let file = File::open(path).with_context(|| {
+                    format!("Failed to open Parquet file: {}", path.display())
+                })?;
+                let reader =
+                    ParquetRecordBatchReaderBuilder::new_with_metadata(file, reader_metadata.clone())
+                        .with_projection(mask.clone())
+                        .with_row_groups(vec![rg])
+                        .build()
+                        .context("Failed to build Parquet reader")?;
+                read_row_group(reader, &projection, schema.fields().len(), row_starts[rg])
+            })
+            .collect::<Result<_>>()?;
 
-                table.add_row(cells, line_num);
-            }
+        for rows in decoded {
+            table.extend_rows(rows);
         }
 
         // Sort if requested
@@ -80,7 +126,137 @@ impl Parser for ParquetParser {
     }
 }
 
-fn arrow_type_to_cell_type(arrow_type: &ArrowType) -> CellType {
+/// Number of rows of a Parquet file, from its footer
+pub(super) fn row_count(path: &Path) -> Result<usize> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open Parquet file: {}", path.display()))?;
+    let metadata = ArrowReaderMetadata::load(&file, ArrowReaderOptions::default())
+        .with_context(|| format!("Failed to read Parquet footer: {}", path.display()))?;
+    Ok(metadata.metadata().file_metadata().num_rows() as usize)
+}
+
+/// Convert one row group's batches into rows, numbering them from `first_row`
+fn read_row_group(
+    reader: ParquetRecordBatchReader,
+    projection: &[usize],
+    width: usize,
+    first_row: usize,
+) -> Result<Vec<(Vec<CellValue>, usize)>> {
+    let mut rows = Vec::new();
+    for batch_result in reader {
+        let batch = batch_result.context("Failed to read Parquet batch")?;
+        let first_row = first_row + rows.len();
+        rows.extend(batch_rows(&batch, projection, width, first_row)?);
+    }
+    Ok(rows)
+}
+
+/// Convert a record batch of the `projection` columns into rows of `width`
+/// cells, numbering them from `first_row`; columns not read are null
+pub(super) fn batch_rows(
+    batch: &RecordBatch,
+    projection: &[usize],
+    width: usize,
+    first_row: usize,
+) -> Result<Vec<(Vec<CellValue>, usize)>> {
+    (0..batch.num_rows())
+        .map(|row_idx| {
+            let line = first_row + row_idx + 2; // +2 as if 1-indexed with a header
+            let mut cells = vec![CellValue::Null; width];
+            for (j, (col, &i)) in batch.columns().iter().zip(projection).enumerate() {
+                cells[i] = extract_cell_value(col.as_ref(), row_idx).with_context(|| {
+                    format!("Row {}, column '{}'", line, batch.schema().field(j).name())
+                })?;
+            }
+            Ok((cells, line))
+        })
+        .collect()
+}
+
+/// Indices of the top-level fields to read: the included columns except
+/// ignored ones, but always keys, the sort column and filtered columns
+pub(super) fn projected_columns(schema: &Schema, config: &Config) -> Vec<usize> {
+    let required = |name: &str| {
+        config.key_columns.iter().any(|k| k == name)
+            || config.sort_by.as_deref() == Some(name)
+            || config
+                .filter
+                .as_ref()
+                .is_some_and(|f| f.columns().contains(&name))
+    };
+
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| {
+            let name = field.name();
+            let ignored = config.ignore_columns.contains(name);
+            required(name) || (config.includes_column(name) && !ignored)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Statistics of a top-level column in one row group, for column types
+/// whose physical statistics compare like the decoded values
+fn row_group_stats(
+    metadata: &ParquetMetaData,
+    schema: &Schema,
+    row_group: usize,
+    name: &str,
+) -> Option<ColumnStats> {
+    let field = schema.field_with_name(name).ok()?;
+    let rg = metadata.row_group(row_group);
+    let leaf = rg
+        .columns()
+        .iter()
+        .position(|c| c.column_path().parts() == [name.to_string()])?;
+    let stats = rg.column(leaf).statistics()?;
+
+    let (min, max) = match (field.data_type(), stats) {
+        (ArrowType::Boolean, Statistics::Boolean(s)) => {
+            Some((CellValue::Bool(*s.min_opt()?), CellValue::Bool(*s.max_opt()?)))
+        }
+        (ArrowType::Int8 | ArrowType::Int16 | ArrowType::Int32, Statistics::Int32(s)) => Some((
+            CellValue::Int(*s.min_opt()? as i64),
+            CellValue::Int(*s.max_opt()? as i64),
+        )),
+        (ArrowType::Int64, Statistics::Int64(s)) => {
+            Some((CellValue::Int(*s.min_opt()?), CellValue::Int(*s.max_opt()?)))
+        }
+        (ArrowType::Float32, Statistics::Float(s)) => Some((
+            CellValue::Float(*s.min_opt()? as f64),
+            CellValue::Float(*s.max_opt()? as f64),
+        )),
+        (ArrowType::Float64, Statistics::Double(s)) => {
+            Some((CellValue::Float(*s.min_opt()?), CellValue::Float(*s.max_opt()?)))
+        }
+        (ArrowType::Utf8 | ArrowType::LargeUtf8 | ArrowType::Utf8View, Statistics::ByteArray(s))
+            if !stats.is_min_max_deprecated() =>
+        {
+            let min = std::str::from_utf8(s.min_opt()?.data()).ok()?;
+            let max = std::str::from_utf8(s.max_opt()?.data()).ok()?;
+            Some((CellValue::from(min), CellValue::from(max)))
+        }
+        _ => None,
+    }?;
+
+    // Required columns have no nulls. The reader reports a missing null
+    // count as 0, but writers in practice always record it.
+    let null_count = if field.is_nullable() {
+        stats.null_count_opt()
+    } else {
+        Some(0)
+    };
+    Some(ColumnStats {
+        min,
+        max,
+        null_count,
+    })
+}
+
+pub(super) fn arrow_type_to_cell_type(arrow_type: &ArrowType) -> CellType {
     match arrow_type {
         ArrowType::Null => CellType::Null,
         ArrowType::Boolean => CellType::Bool,
@@ -93,19 +269,39 @@ fn arrow_type_to_cell_type(arrow_type: &ArrowType) -> CellType {
         | ArrowType::UInt32
         | ArrowType::UInt64 => CellType::Int,
         ArrowType::Float16 | ArrowType::Float32 | ArrowType::Float64 => CellType::Float,
-        ArrowType::Utf8 | ArrowType::LargeUtf8 => CellType::String,
+        ArrowType::Decimal128(_, _) | ArrowType::Decimal256(_, _) => CellType::Decimal,
+        ArrowType::Utf8 | ArrowType::LargeUtf8 | ArrowType::Utf8View => CellType::String,
+        ArrowType::Binary
+        | ArrowType::LargeBinary
+        | ArrowType::BinaryView
+        | ArrowType::FixedSizeBinary(_) => CellType::Binary,
         ArrowType::Date32 | ArrowType::Date64 => CellType::Date,
         ArrowType::Timestamp(_, _) => CellType::DateTime,
-        _ => CellType::String, // Fallback to string for complex types
+        ArrowType::Time32(_) | ArrowType::Time64(_) => CellType::Time,
+        ArrowType::List(_) | ArrowType::LargeList(_) | ArrowType::FixedSizeList(_, _) => {
+            CellType::List
+        }
+        // Durations and intervals are read as structs of their components
+        ArrowType::Struct(_)
+        | ArrowType::Map(_, _)
+        | ArrowType::Duration(_)
+        | ArrowType::Interval(_) => CellType::Struct,
+        ArrowType::Dictionary(_, value_type) => arrow_type_to_cell_type(value_type),
+        ArrowType::RunEndEncoded(_, values) => arrow_type_to_cell_type(values.data_type()),
+        ArrowType::Union(_, _) => CellType::Mixed,
+        _ => CellType::String, // Fallback to string for other types
     }
 }
 
-fn extract_cell_value(array: &ArrayRef, row_idx: usize) -> CellValue {
+/// Convert one Arrow value to a cell. Dates, times and timestamps that do
+/// not fit a calendar date are errors rather than cells of another type.
+fn extract_cell_value(array: &dyn Array, row_idx: usize) -> Result<CellValue> {
     if array.is_null(row_idx) {
-        return CellValue::Null;
+        return Ok(CellValue::Null);
     }
 
-    match array.data_type() {
+    Ok(match array.data_type() {
+        ArrowType::Null => CellValue::Null,
         ArrowType::Boolean => {
             let arr = array.as_any().downcast_ref::<BooleanArray>().unwrap();
             CellValue::Bool(arr.value(row_idx))
@@ -140,7 +336,16 @@ fn extract_cell_value(array: &ArrayRef, row_idx: usize) -> CellValue {
         }
         ArrowType::UInt64 => {
             let arr = array.as_any().downcast_ref::<UInt64Array>().unwrap();
-            CellValue::Int(arr.value(row_idx) as i64)
+            let value = arr.value(row_idx);
+            // Values above i64::MAX are kept exact as a decimal
+            match i64::try_from(value) {
+                Ok(i) => CellValue::Int(i),
+                Err(_) => CellValue::Decimal(Decimal::new(value as i128, 0)),
+            }
+        }
+        ArrowType::Float16 => {
+            let arr = array.as_any().downcast_ref::<Float16Array>().unwrap();
+            CellValue::Float(arr.value(row_idx).to_f64())
         }
         ArrowType::Float32 => {
             let arr = array.as_any().downcast_ref::<Float32Array>().unwrap();
@@ -150,48 +355,216 @@ fn extract_cell_value(array: &ArrayRef, row_idx: usize) -> CellValue {
             let arr = array.as_any().downcast_ref::<Float64Array>().unwrap();
             CellValue::Float(arr.value(row_idx))
         }
+        ArrowType::Decimal128(_, scale) => {
+            let arr = array.as_any().downcast_ref::<Decimal128Array>().unwrap();
+            CellValue::Decimal(Decimal::new(arr.value(row_idx), *scale))
+        }
+        ArrowType::Decimal256(_, scale) => {
+            let arr = array.as_any().downcast_ref::<Decimal256Array>().unwrap();
+            CellValue::Decimal(decimal256_to_decimal(arr.value(row_idx), *scale))
+        }
         ArrowType::Utf8 => {
             let arr = array.as_any().downcast_ref::<StringArray>().unwrap();
             CellValue::String(Cow::Owned(arr.value(row_idx).to_string()))
         }
+        ArrowType::LargeUtf8 => {
+            let arr = array.as_any().downcast_ref::<LargeStringArray>().unwrap();
+            CellValue::String(Cow::Owned(arr.value(row_idx).to_string()))
+        }
+        ArrowType::Utf8View => {
+            let arr = array.as_any().downcast_ref::<StringViewArray>().unwrap();
+            CellValue::String(Cow::Owned(arr.value(row_idx).to_string()))
+        }
+        ArrowType::Binary => {
+            let arr = array.as_any().downcast_ref::<BinaryArray>().unwrap();
+            CellValue::Bytes(arr.value(row_idx).to_vec())
+        }
+        ArrowType::LargeBinary => {
+            let arr = array.as_any().downcast_ref::<LargeBinaryArray>().unwrap();
+            CellValue::Bytes(arr.value(row_idx).to_vec())
+        }
+        ArrowType::BinaryView => {
+            let arr = array.as_any().downcast_ref::<BinaryViewArray>().unwrap();
+            CellValue::Bytes(arr.value(row_idx).to_vec())
+        }
+        ArrowType::FixedSizeBinary(_) => {
+            let arr = array.as_any().downcast_ref::<FixedSizeBinaryArray>().unwrap();
+            CellValue::Bytes(arr.value(row_idx).to_vec())
+        }
         ArrowType::Date32 => {
             let arr = array.as_any().downcast_ref::<Date32Array>().unwrap();
             let days = arr.value(row_idx);
-            if let Some(date) = chrono::NaiveDate::from_num_days_from_ce_opt(days + 719163) {
-                CellValue::Date(date)
-            } else {
-                CellValue::Int(days as i64)
+            match days
+                .checked_add(719_163)
+                .and_then(chrono::NaiveDate::from_num_days_from_ce_opt)
+            {
+                Some(date) => CellValue::Date(date),
+                None => bail!("Date {} days from 1970-01-01 is out of range", days),
+            }
+        }
+        ArrowType::Date64 => {
+            let arr = array.as_any().downcast_ref::<Date64Array>().unwrap();
+            let millis = arr.value(row_idx);
+            match DateTime::from_timestamp_millis(millis) {
+                Some(dt) => CellValue::Date(dt.date_naive()),
+                None => bail!("Date {} ms from 1970-01-01 is out of range", millis),
             }
         }
         ArrowType::Timestamp(unit, _) => {
-            let nanos = match unit {
-                arrow::datatypes::TimeUnit::Second => {
+            let (value, dt) = match unit {
+                TimeUnit::Second => {
                     let arr = array.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
-                    arr.value(row_idx) * 1_000_000_000
+                    let v = arr.value(row_idx);
+                    (v, DateTime::from_timestamp(v, 0))
                 }
-                arrow::datatypes::TimeUnit::Millisecond => {
+                TimeUnit::Millisecond => {
                     let arr = array.as_any().downcast_ref::<TimestampMillisecondArray>().unwrap();
-                    arr.value(row_idx) * 1_000_000
+                    let v = arr.value(row_idx);
+                    (v, DateTime::from_timestamp_millis(v))
                 }
-                arrow::datatypes::TimeUnit::Microsecond => {
+                TimeUnit::Microsecond => {
                     let arr = array.as_any().downcast_ref::<TimestampMicrosecondArray>().unwrap();
-                    arr.value(row_idx) * 1_000
+                    let v = arr.value(row_idx);
+                    (v, DateTime::from_timestamp_micros(v))
                 }
-                arrow::datatypes::TimeUnit::Nanosecond => {
+                TimeUnit::Nanosecond => {
                     let arr = array.as_any().downcast_ref::<TimestampNanosecondArray>().unwrap();
-                    arr.value(row_idx)
+                    let v = arr.value(row_idx);
+                    (v, Some(DateTime::from_timestamp_nanos(v)))
                 }
             };
-            if let Some(dt) = chrono::DateTime::from_timestamp_nanos(nanos).naive_utc().into() {
-                CellValue::DateTime(dt)
-            } else {
-                CellValue::Int(nanos)
+            match dt {
+                Some(dt) => CellValue::DateTime(dt.naive_utc()),
+                None => bail!("Timestamp {} ({:?}) is out of range", value, unit),
+            }
+        }
+        ArrowType::Time32(unit) => {
+            let (secs, nanos) = match unit {
+                TimeUnit::Second => {
+                    let arr = array.as_any().downcast_ref::<Time32SecondArray>().unwrap();
+                    (arr.value(row_idx) as i64, 0)
+                }
+                _ => {
+                    let arr = array.as_any().downcast_ref::<Time32MillisecondArray>().unwrap();
+                    let v = arr.value(row_idx) as i64;
+                    (v.div_euclid(1_000), v.rem_euclid(1_000) * 1_000_000)
+                }
+            };
+            time_to_cell(secs, nanos)?
+        }
+        ArrowType::Time64(unit) => {
+            let (secs, nanos) = match unit {
+                TimeUnit::Nanosecond => {
+                    let arr = array.as_any().downcast_ref::<Time64NanosecondArray>().unwrap();
+                    let v = arr.value(row_idx);
+                    (v.div_euclid(1_000_000_000), v.rem_euclid(1_000_000_000))
+                }
+                _ => {
+                    let arr = array.as_any().downcast_ref::<Time64MicrosecondArray>().unwrap();
+                    let v = arr.value(row_idx);
+                    (v.div_euclid(1_000_000), v.rem_euclid(1_000_000) * 1_000)
+                }
+            };
+            time_to_cell(secs, nanos)?
+        }
+        ArrowType::Duration(unit) => {
+            let (name, value) = match unit {
+                TimeUnit::Second => {
+                    let arr = array.as_any().downcast_ref::<DurationSecondArray>().unwrap();
+                    ("seconds", arr.value(row_idx))
+                }
+                TimeUnit::Millisecond => {
+                    let arr = array.as_any().downcast_ref::<DurationMillisecondArray>().unwrap();
+                    ("milliseconds", arr.value(row_idx))
+                }
+                TimeUnit::Microsecond => {
+                    let arr = array.as_any().downcast_ref::<DurationMicrosecondArray>().unwrap();
+                    ("microseconds", arr.value(row_idx))
+                }
+                TimeUnit::Nanosecond => {
+                    let arr = array.as_any().downcast_ref::<DurationNanosecondArray>().unwrap();
+                    ("nanoseconds", arr.value(row_idx))
+                }
+            };
+            components_to_cell(&[(name, value)])
+        }
+        ArrowType::Interval(unit) => match unit {
+            IntervalUnit::YearMonth => {
+                let arr = array.as_any().downcast_ref::<IntervalYearMonthArray>().unwrap();
+                components_to_cell(&[("months", arr.value(row_idx) as i64)])
+            }
+            IntervalUnit::DayTime => {
+                let arr = array.as_any().downcast_ref::<IntervalDayTimeArray>().unwrap();
+                let v = arr.value(row_idx);
+                components_to_cell(&[
+                    ("days", v.days as i64),
+                    ("milliseconds", v.milliseconds as i64),
+                ])
+            }
+            IntervalUnit::MonthDayNano => {
+                let arr = array.as_any().downcast_ref::<IntervalMonthDayNanoArray>().unwrap();
+                let v = arr.value(row_idx);
+                components_to_cell(&[
+                    ("months", v.months as i64),
+                    ("days", v.days as i64),
+                    ("nanoseconds", v.nanoseconds),
+                ])
             }
+        },
+        ArrowType::Dictionary(_, _) => {
+            let dict = array.as_any_dictionary();
+            match extract_cell_value(dict.keys(), row_idx)? {
+                CellValue::Int(key) => extract_cell_value(dict.values().as_ref(), key as usize)?,
+                _ => CellValue::Null,
+            }
+        }
+        ArrowType::Union(_, _) => {
+            let arr = array.as_any().downcast_ref::<UnionArray>().unwrap();
+            let child = arr.child(arr.type_id(row_idx));
+            extract_cell_value(child.as_ref(), arr.value_offset(row_idx))?
+        }
+        ArrowType::List(_) => {
+            let arr = array.as_any().downcast_ref::<ListArray>().unwrap();
+            list_to_cell(&arr.value(row_idx))?
+        }
+        ArrowType::LargeList(_) => {
+            let arr = array.as_any().downcast_ref::<LargeListArray>().unwrap();
+            list_to_cell(&arr.value(row_idx))?
+        }
+        ArrowType::FixedSizeList(_, _) => {
+            let arr = array.as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+            list_to_cell(&arr.value(row_idx))?
+        }
+        ArrowType::Struct(fields) => {
+            let arr = array.as_any().downcast_ref::<StructArray>().unwrap();
+            CellValue::Struct(
+                fields
+                    .iter()
+                    .zip(arr.columns())
+                    .map(|(field, col)| {
+                        Ok((field.name().clone(), extract_cell_value(col.as_ref(), row_idx)?))
+                    })
+                    .collect::<Result<_>>()?,
+            )
+        }
+        ArrowType::Map(_, _) => {
+            // Map entries are a struct array of (key, value) pairs
+            let arr = array.as_any().downcast_ref::<MapArray>().unwrap();
+            let entries = arr.value(row_idx);
+            let (keys, values) = (entries.column(0), entries.column(1));
+            CellValue::Struct(
+                (0..entries.len())
+                    .map(|i| {
+                        let key = extract_cell_value(keys.as_ref(), i)?.display().into_owned();
+                        Ok((key, extract_cell_value(values.as_ref(), i)?))
+                    })
+                    .collect::<Result<_>>()?,
+            )
         }
         _ => {
-            // Fallback: convert to string
+            // Remaining encodings (run-end, list views): use Arrow's value formatter
             let formatter = arrow::util::display::ArrayFormatter::try_new(
-                array.as_ref(),
+                array,
                 &arrow::util::display::FormatOptions::default(),
             );
             if let Ok(fmt) = formatter {
@@ -200,5 +573,415 @@ fn extract_cell_value(array: &ArrayRef, row_idx: usize) -> CellValue {
                 CellValue::Null
             }
         }
+    })
+}
+
+fn list_to_cell(values: &ArrayRef) -> Result<CellValue> {
+    Ok(CellValue::List(
+        (0..values.len())
+            .map(|i| extract_cell_value(values.as_ref(), i))
+            .collect::<Result<_>>()?,
+    ))
+}
+
+/// A Decimal256 value as a decimal, rounded to fewer digits when it has
+/// more than fit in 128 bits
+pub(super) fn decimal256_to_decimal(value: i256, mut scale: i8) -> Decimal {
+    let ten = i256::from_i128(10);
+    let mut divisor = i256::from_i128(1);
+    loop {
+        let rem = value.wrapping_rem(divisor);
+        let mut rounded = value.wrapping_div(divisor);
+        if rem.wrapping_abs().wrapping_mul(i256::from_i128(2)) >= divisor {
+            rounded = rounded.wrapping_add(value.signum());
+        }
+        if let Some(rounded) = rounded.to_i128() {
+            return Decimal::new(rounded, scale);
+        }
+        divisor = divisor.wrapping_mul(ten);
+        scale -= 1;
+    }
+}
+
+fn time_to_cell(secs: i64, nanos: i64) -> Result<CellValue> {
+    match u32::try_from(secs)
+        .ok()
+        .zip(u32::try_from(nanos).ok())
+        .and_then(|(s, n)| NaiveTime::from_num_seconds_from_midnight_opt(s, n))
+    {
+        Some(time) => Ok(CellValue::Time(time)),
+        None => bail!("Time {}s {}ns is not a time of day", secs, nanos),
+    }
+}
+
+fn components_to_cell(components: &[(&str, i64)]) -> CellValue {
+    let fields: IndexMap<String, CellValue> = components
+        .iter()
+        .map(|(name, value)| (name.to_string(), CellValue::Int(*value)))
+        .collect();
+    CellValue::Struct(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{
+        DictionaryArray, Int32Builder, ListBuilder, RecordBatch, StringDictionaryBuilder,
+    };
+    use arrow::datatypes::{
+        ArrowPrimitiveType, Field, Float16Type, Int32Type, IntervalDayTime, IntervalMonthDayNano,
+        Schema,
+    };
+    use chrono::NaiveDate;
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    use super::*;
+
+    /// Write a single column to a Parquet file in a temp dir and read it back
+    fn round_trip(array: ArrayRef) -> (CellType, Vec<CellValue>) {
+        let field = Field::new("value", array.data_type().clone(), true);
+        let schema = Arc::new(Schema::new(vec![field]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("types.parquet");
+        let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let table = ParquetParser.parse(&path, &Config::default()).unwrap();
+        let values = table.rows.iter().map(|r| r.cells[0].clone()).collect();
+        (table.columns[0].inferred_type, values)
+    }
+
+    #[test]
+    fn test_projection_and_row_group_pruning() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", ArrowType::Int64, false),
+            Field::new("region", ArrowType::Utf8, false),
+            Field::new("blob", ArrowType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["AP", "AP", "EU", "US"])),
+                Arc::new(StringArray::from(vec!["x"; 4])),
+            ],
+        )
+        .unwrap();
+
+        // Two rows per row group: [AP, AP] and [EU, US]
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pruned.parquet");
+        let props = WriterProperties::builder().set_max_row_group_size(2).build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let config = Config {
+            ignore_columns: vec!["blob".to_string()],
+            filter: Some(crate::filter::FilterExpr::parse("region = 'EU'").unwrap()),
+            ..Default::default()
+        };
+        let table = ParquetParser.parse(&path, &config).unwrap();
+
+        let names: Vec<_> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "region", "blob"]);
+        // Ignored columns stay in the schema but are not decoded
+        assert!(table.rows.iter().all(|r| r.cells[2] == CellValue::Null));
+        // Only the second row group is read; line numbers still refer to the file
+        let lines: Vec<_> = table.rows.iter().map(|r| r.source_line).collect();
+        assert_eq!(lines, vec![4, 5]);
+
+        // Columns outside the include list are not decoded either, but keys are
+        let config = Config {
+            include_columns: crate::filter::parse_column_selectors(&["region".to_string()])
+                .unwrap(),
+            ..Config::default().with_key_columns(vec!["id".to_string()])
+        };
+        let table = ParquetParser.parse(&path, &config).unwrap();
+        assert_eq!(table.rows[0].cells[0], CellValue::Int(1));
+        assert_eq!(table.rows[0].cells[1], string("AP"));
+        assert!(table.rows.iter().all(|r| r.cells[2] == CellValue::Null));
+
+        // A constant column with a null: `!=` keeps the row group with the null
+        let schema = Arc::new(Schema::new(vec![Field::new("n", ArrowType::Int64, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![Some(5), Some(5), None, Some(5)]))],
+        )
+        .unwrap();
+        let path = dir.path().join("nulls.parquet");
+        let props = WriterProperties::builder().set_max_row_group_size(2).build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let config = Config {
+            filter: Some(crate::filter::FilterExpr::parse("n != 5").unwrap()),
+            ..Default::default()
+        };
+        let table = ParquetParser.parse(&path, &config).unwrap();
+        let lines: Vec<_> = table.rows.iter().map(|r| r.source_line).collect();
+        assert_eq!(lines, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        use crate::diff::compute_diff;
+
+        // Many small row groups, decoded in parallel when threads allow
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, changed: i64| {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("id", ArrowType::Int64, false),
+                Field::new("amount", ArrowType::Int64, false),
+            ]));
+            let ids: Vec<i64> = (0..1000).collect();
+            let amounts: Vec<i64> = ids
+                .iter()
+                .map(|i| i * 10 + (i % changed == 0) as i64)
+                .collect();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(ids)),
+                    Arc::new(Int64Array::from(amounts)),
+                ],
+            )
+            .unwrap();
+            let path = dir.path().join(name);
+            let props = WriterProperties::builder().set_max_row_group_size(64).build();
+            let mut writer =
+                ArrowWriter::try_new(File::create(&path).unwrap(), schema, Some(props)).unwrap();
+            writer.write(&batch).unwrap();
+            writer.close().unwrap();
+            path
+        };
+        let old_path = write("old.parquet", i64::MAX);
+        let new_path = write("new.parquet", 97);
+        let config = Config::default().with_key_columns(vec!["id".to_string()]);
+
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let old = ParquetParser.parse(&old_path, &config).unwrap();
+                let new = ParquetParser.parse(&new_path, &config).unwrap();
+                let diff = compute_diff(&old, &new, &config);
+                (old, format!("{:?}", diff))
+            })
+        };
+        let (sequential, sequential_diff) = run(1);
+        let (parallel, parallel_diff) = run(4);
+
+        assert_eq!(sequential.row_count(), 1000);
+        assert_eq!(row_count(&old_path).unwrap(), 1000);
+        for (i, (a, b)) in sequential.rows.iter().zip(&parallel.rows).enumerate() {
+            assert_eq!(a.source_line, i + 2);
+            assert_eq!(a.source_line, b.source_line);
+            assert_eq!(a.cells, b.cells);
+        }
+        assert!(sequential_diff.contains("Modified"));
+        assert_eq!(sequential_diff, parallel_diff);
+    }
+
+    fn string(s: &str) -> CellValue {
+        CellValue::from(s)
+    }
+
+    #[test]
+    fn test_integer_types() {
+        let (ty, values) = round_trip(Arc::new(UInt64Array::from(vec![Some(7), None, Some(u64::MAX)])));
+        assert_eq!(ty, CellType::Int);
+        assert_eq!(
+            values,
+            vec![
+                CellValue::Int(7),
+                CellValue::Null,
+                CellValue::Decimal(Decimal::new(u64::MAX as i128, 0)),
+            ]
+        );
+
+        let (_, values) = round_trip(Arc::new(Int8Array::from(vec![-8])));
+        assert_eq!(values, vec![CellValue::Int(-8)]);
+        let (_, values) = round_trip(Arc::new(UInt32Array::from(vec![u32::MAX])));
+        assert_eq!(values, vec![CellValue::Int(u32::MAX as i64)]);
+    }
+
+    #[test]
+    fn test_float_types() {
+        type F16 = <Float16Type as ArrowPrimitiveType>::Native;
+        let halves = vec![F16::from_f32(1.5), F16::from_f32(-0.25)];
+        let (ty, values) = round_trip(Arc::new(Float16Array::from(halves)));
+        assert_eq!(ty, CellType::Float);
+        assert_eq!(values, vec![CellValue::Float(1.5), CellValue::Float(-0.25)]);
+
+        let (_, values) = round_trip(Arc::new(Float32Array::from(vec![2.5f32])));
+        assert_eq!(values, vec![CellValue::Float(2.5)]);
+    }
+
+    #[test]
+    fn test_decimal_types() {
+        let arr = Decimal128Array::from(vec![12345i128, -5])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        let (ty, values) = round_trip(Arc::new(arr));
+        assert_eq!(ty, CellType::Decimal);
+        assert_eq!(values[0].display(), "123.45");
+        assert_eq!(values[1].display(), "-0.05");
+
+        let arr = Decimal256Array::from(vec![i256::from_i128(9876)])
+            .with_precision_and_scale(50, 3)
+            .unwrap();
+        let (_, values) = round_trip(Arc::new(arr));
+        assert_eq!(values, vec![CellValue::Decimal(Decimal::new(9876, 3))]);
+
+        // Beyond 38 digits the value is rounded, but stays a decimal
+        let big = i256::from_string("1234567890123456789012345678901234567894999").unwrap();
+        let arr = Decimal256Array::from(vec![big, big.wrapping_neg()])
+            .with_precision_and_scale(60, 3)
+            .unwrap();
+        let (ty, values) = round_trip(Arc::new(arr));
+        assert_eq!(ty, CellType::Decimal);
+        let rounded = Decimal::new(123456789012345678901234567890123456789, -1);
+        assert_eq!(values[0], CellValue::Decimal(rounded));
+        assert_eq!(values[1].display(), format!("-{}", rounded));
+    }
+
+    #[test]
+    fn test_string_types() {
+        for array in [
+            Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+            Arc::new(LargeStringArray::from(vec!["a", "b"])),
+            Arc::new(StringViewArray::from(vec!["a", "b"])),
+        ] {
+            let (ty, values) = round_trip(array);
+            assert_eq!(ty, CellType::String);
+            assert_eq!(values, vec![string("a"), string("b")]);
+        }
+
+        let mut builder = StringDictionaryBuilder::<Int32Type>::new();
+        builder.append_value("eu");
+        builder.append_null();
+        builder.append_value("eu");
+        let dict: DictionaryArray<Int32Type> = builder.finish();
+        let (ty, values) = round_trip(Arc::new(dict));
+        assert_eq!(ty, CellType::String);
+        assert_eq!(values, vec![string("eu"), CellValue::Null, string("eu")]);
+    }
+
+    #[test]
+    fn test_binary_types() {
+        let bytes: Vec<&[u8]> = vec![b"\x00\xff", b"ok"];
+        for array in [
+            Arc::new(BinaryArray::from(bytes.clone())) as ArrayRef,
+            Arc::new(LargeBinaryArray::from(bytes.clone())),
+            Arc::new(BinaryViewArray::from(bytes.clone())),
+        ] {
+            let (ty, values) = round_trip(array);
+            assert_eq!(ty, CellType::Binary);
+            assert_eq!(values[0].display(), "0x00ff");
+            assert_eq!(values[1], CellValue::Bytes(b"ok".to_vec()));
+        }
+
+        let fixed = FixedSizeBinaryArray::try_from_iter(vec![[1u8, 2], [3, 4]].into_iter()).unwrap();
+        let (_, values) = round_trip(Arc::new(fixed));
+        assert_eq!(values[1], CellValue::Bytes(vec![3, 4]));
+    }
+
+    #[test]
+    fn test_date_and_time_types() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let (ty, values) = round_trip(Arc::new(Date64Array::from(vec![1_709_251_200_000])));
+        assert_eq!(ty, CellType::Date);
+        assert_eq!(values, vec![CellValue::Date(date)]);
+
+        let noon = NaiveTime::from_hms_milli_opt(12, 0, 1, 500).unwrap();
+        for array in [
+            Arc::new(Time32MillisecondArray::from(vec![43_201_500])) as ArrayRef,
+            Arc::new(Time64MicrosecondArray::from(vec![43_201_500_000])),
+            Arc::new(Time64NanosecondArray::from(vec![43_201_500_000_000])),
+        ] {
+            let (ty, values) = round_trip(array);
+            assert_eq!(ty, CellType::Time);
+            assert_eq!(values, vec![CellValue::Time(noon)]);
+        }
+
+        let (_, values) = round_trip(Arc::new(TimestampSecondArray::from(vec![1_709_251_200])));
+        assert_eq!(values, vec![CellValue::DateTime(date.and_hms_opt(0, 0, 0).unwrap())]);
+
+        // Values past the calendar are errors, not cells of another type
+        for array in [
+            Arc::new(Date32Array::from(vec![i32::MAX])) as ArrayRef,
+            Arc::new(Date64Array::from(vec![i64::MAX])),
+            Arc::new(TimestampSecondArray::from(vec![i64::MIN])),
+            Arc::new(Time32SecondArray::from(vec![90_000])),
+        ] {
+            assert!(extract_cell_value(array.as_ref(), 0).is_err());
+        }
+
+        let field = Field::new("shipped", ArrowType::Date64, true);
+        let schema = Arc::new(Schema::new(vec![field]));
+        let array = Arc::new(Date64Array::from(vec![Some(0), None, Some(i64::MAX)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dates.parquet");
+        let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let error = format!("{:#}", ParquetParser.parse(&path, &Config::default()).unwrap_err());
+        assert!(error.contains("Row 4, column 'shipped'"), "{}", error);
+        assert!(error.contains("out of range"), "{}", error);
+    }
+
+    #[test]
+    fn test_duration_and_interval_types() {
+        let (ty, values) = round_trip(Arc::new(IntervalYearMonthArray::from(vec![14])));
+        assert_eq!(ty, CellType::Struct);
+        assert_eq!(values[0].display(), r#"{"months":14}"#);
+
+        let day_time = IntervalDayTimeArray::from(vec![IntervalDayTime::new(2, 300)]);
+        let (_, values) = round_trip(Arc::new(day_time));
+        assert_eq!(values[0].display(), r#"{"days":2,"milliseconds":300}"#);
+
+        // The Parquet writer cannot encode these, so convert the Arrow arrays directly
+        let mdn = IntervalMonthDayNanoArray::from(vec![IntervalMonthDayNano::new(1, 2, 3)]);
+        let value = extract_cell_value(&mdn, 0).unwrap();
+        assert_eq!(value.display(), r#"{"months":1,"days":2,"nanoseconds":3}"#);
+
+        let duration = DurationMillisecondArray::from(vec![1500]);
+        let value = extract_cell_value(&duration, 0).unwrap();
+        assert_eq!(value.display(), r#"{"milliseconds":1500}"#);
+    }
+
+    #[test]
+    fn test_nested_types() {
+        let mut builder = ListBuilder::new(Int32Builder::new());
+        builder.append_value([Some(1), None, Some(3)]);
+        builder.append_value([]);
+        let (ty, values) = round_trip(Arc::new(builder.finish()));
+        assert_eq!(ty, CellType::List);
+        assert_eq!(
+            values[0],
+            CellValue::List(vec![CellValue::Int(1), CellValue::Null, CellValue::Int(3)])
+        );
+        assert_eq!(values[1], CellValue::List(vec![]));
+
+        let city: ArrayRef = Arc::new(StringArray::from(vec!["Oslo"]));
+        let zip: ArrayRef = Arc::new(Int32Array::from(vec![150]));
+        let address = StructArray::from(vec![
+            (Arc::new(Field::new("city", ArrowType::Utf8, false)), city),
+            (Arc::new(Field::new("zip", ArrowType::Int32, false)), zip),
+        ]);
+        let (ty, values) = round_trip(Arc::new(address));
+        assert_eq!(ty, CellType::Struct);
+        assert_eq!(values[0].display(), r#"{"city":"Oslo","zip":150}"#);
     }
 }
@@ -0,0 +1,191 @@
+//! Record paths and flattening shared by the nested-document parsers
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use indexmap::{IndexMap, IndexSet};
+
+use crate::config::Config;
+use crate::model::{CellValue, Column, Table};
+
+use super::csv::infer_column_types;
+
+/// Where the records of a nested document are: `/feed/item` (XML style) or
+/// `.items[]` (jq style)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordPath {
+    /// The path as given, for messages
+    text: String,
+    steps: Vec<String>,
+    /// `//item`: matches at any depth (XML only)
+    anywhere: bool,
+}
+
+impl RecordPath {
+    /// Parse a path. Steps are separated by `/`, or by `.` when the path
+    /// starts with one; a `[]` after a step is optional, as lists on the way
+    /// are always expanded.
+    pub fn parse(path: &str) -> Result<Self> {
+        let path = path.trim();
+        let (rest, anywhere) = match path.strip_prefix("//") {
+            Some(rest) => (rest, true),
+            None => (path, false),
+        };
+        let separator = if rest.starts_with('.') { '.' } else { '/' };
+        let steps: Vec<String> = rest
+            .split(separator)
+            .map(|step| step.trim_end_matches("[]").trim())
+            .filter(|step| !step.is_empty())
+            .map(String::from)
+            .collect();
+        if anywhere && steps.is_empty() {
+            bail!("Record path names no element: {}", path);
+        }
+        Ok(Self {
+            text: path.to_string(),
+            steps,
+            anywhere,
+        })
+    }
+
+    /// Whether an element, given by the names of it and its ancestors from
+    /// the root, is a record. Names match with or without a namespace prefix,
+    /// and `*` matches any name.
+    pub(super) fn matches(&self, names: &[String]) -> bool {
+        let matches = |step: &String, name: &String| {
+            step == "*"
+                || step == name
+                || name.split_once(':').is_some_and(|(_, local)| step == local)
+        };
+        if self.anywhere {
+            names.len() >= self.steps.len()
+                && self
+                    .steps
+                    .iter()
+                    .rev()
+                    .zip(names.iter().rev())
+                    .all(|(step, name)| matches(step, name))
+        } else {
+            names.len() == self.steps.len()
+                && self
+                    .steps
+                    .iter()
+                    .zip(names)
+                    .all(|(step, name)| matches(step, name))
+        }
+    }
+}
+
+impl fmt::Display for RecordPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A parsed JSON or YAML value a record path can walk
+pub(super) trait Document: Sized {
+    /// The value under a key, if this is a mapping that has it
+    fn get(self, key: &str) -> Option<Self>;
+
+    /// The items of a sequence, or the value itself if it is not one
+    fn items(self) -> Result<Vec<Self>, Self>;
+}
+
+/// The records a path selects: the values it leads to, expanding sequences
+/// on the way and at the end
+pub(super) fn select<D: Document>(root: D, path: &RecordPath) -> Result<Vec<D>> {
+    if path.anywhere {
+        bail!("`//` record paths are only supported for XML");
+    }
+    let expand = |node: D| node.items().unwrap_or_else(|node| vec![node]);
+    let mut nodes = vec![root];
+    for step in &path.steps {
+        nodes = nodes
+            .into_iter()
+            .flat_map(expand)
+            .filter_map(|node| node.get(step))
+            .collect();
+    }
+    Ok(nodes.into_iter().flat_map(expand).collect())
+}
+
+/// Build a table from flattened records and their source lines, with a
+/// column per field name in the order the names first appear. Finding no
+/// records is an error, so a mistyped record path is not read as no changes.
+pub(super) fn records_to_table(
+    records: Vec<(IndexMap<String, CellValue>, usize)>,
+    config: &Config,
+) -> Result<Table> {
+    if records.is_empty() {
+        match config.record_path {
+            Some(ref path) => bail!("Record path {} selects no records", path),
+            None => bail!("No records found"),
+        }
+    }
+
+    let mut names: IndexSet<String> = IndexSet::new();
+    for (fields, _) in &records {
+        for name in fields.keys() {
+            if !names.contains(name) {
+                names.insert(name.clone());
+            }
+        }
+    }
+    let columns: Vec<Column> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Column::new(name.clone(), i))
+        .collect();
+    let rows: Vec<_> = records
+        .into_iter()
+        .map(|(mut fields, line)| {
+            let cells = names
+                .iter()
+                .map(|name| fields.swap_remove(name).unwrap_or(CellValue::Null))
+                .collect();
+            (cells, line)
+        })
+        .collect();
+
+    let mut table = Table::new(columns);
+
+    // Set key columns if specified
+    if !config.key_columns.is_empty() {
+        table.set_key_columns(&config.key_columns);
+    }
+
+    table.extend_rows(rows);
+    infer_column_types(&mut table);
+
+    // Sort if requested
+    if let Some(ref sort_col) = config.sort_by {
+        table.sort_by_column(sort_col);
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_paths() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let path = RecordPath::parse("/feed/item").unwrap();
+        assert!(path.matches(&names(&["feed", "item"])));
+        assert!(path.matches(&names(&["atom:feed", "atom:item"])));
+        assert!(!path.matches(&names(&["feed", "entry", "item"])));
+        let path = RecordPath::parse("//item").unwrap();
+        assert!(path.matches(&names(&["feed", "entry", "item"])));
+        assert!(RecordPath::parse("/feed/*")
+            .unwrap()
+            .matches(&names(&["feed", "entry"])));
+
+        assert_eq!(
+            RecordPath::parse(".data.items[]").unwrap().steps,
+            RecordPath::parse("/data/items").unwrap().steps
+        );
+        assert!(RecordPath::parse(".[]").unwrap().steps.is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+//! SQLite table and query parser
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::config::Config;
+use crate::model::{CellType, CellValue, Column, Decimal, Table};
+
+use super::csv::parse_cell_value;
+use super::Parser;
+
+/// Magic bytes at the start of a SQLite database file
+pub const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// What to read from a SQLite database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Selection {
+    Table(String),
+    Query(String),
+}
+
+/// Split `db.sqlite:table` or `db.sqlite?query=SELECT ...` into the database
+/// path and what to read from it
+pub(super) fn split_source(spec: &Path) -> Option<(PathBuf, Selection)> {
+    if spec.exists() {
+        return None;
+    }
+    let spec = spec.to_str()?;
+    if let Some((db, query)) = spec.split_once("?query=") {
+        if is_database_path(db) {
+            return Some((PathBuf::from(db), Selection::Query(query.to_string())));
+        }
+    }
+    spec.match_indices(':').find_map(|(i, _)| {
+        let (db, table) = (&spec[..i], &spec[i + 1..]);
+        (is_database_path(db) && !table.is_empty())
+            .then(|| (PathBuf::from(db), Selection::Table(table.to_string())))
+    })
+}
+
+fn is_database_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| SqliteParser.supports_extension(ext))
+}
+
+/// Parser for SQLite tables and query results
+pub struct SqliteParser;
+
+impl SqliteParser {
+    /// Whether a path is a whole SQLite database rather than one of its
+    /// tables, going by the content rather than the extension
+    pub fn is_database(path: &Path) -> bool {
+        let mut magic = [0u8; SQLITE_MAGIC.len()];
+        File::open(path).is_ok_and(|mut file| file.read_exact(&mut magic).is_ok())
+            && magic == SQLITE_MAGIC
+    }
+
+    /// Read every table of a database, in the order they were created
+    pub fn parse_tables(&self, path: &Path, config: &Config) -> Result<Vec<(String, Table)>> {
+        let conn = open(path)?;
+        table_names(&conn)?
+            .into_iter()
+            .map(|name| {
+                let table = read_table(&conn, &name, config)
+                    .with_context(|| format!("Failed to read table {}", name))?;
+                Ok((name, table))
+            })
+            .collect()
+    }
+}
+
+impl Parser for SqliteParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let (db_path, selection) = match split_source(path) {
+            Some((db_path, selection)) => (db_path, Some(selection)),
+            None => (path.to_path_buf(), None),
+        };
+        let conn = open(&db_path)?;
+        let name = match selection {
+            Some(Selection::Table(name)) => name,
+            Some(Selection::Query(sql)) => return read_rows(&conn, &sql, &[], config),
+            // A database with a single table stands for that table
+            None => match table_names(&conn)?.as_slice() {
+                [name] => name.clone(),
+                names => bail!(
+                    "{} has {} tables; name one as {}:TABLE",
+                    db_path.display(),
+                    names.len(),
+                    db_path.display()
+                ),
+            },
+        };
+        read_table(&conn, &name, config)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(
+            ext.to_lowercase().as_str(),
+            "sqlite" | "sqlite3" | "db" | "db3"
+        )
+    }
+}
+
+fn open(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open SQLite database: {}", path.display()))
+}
+
+/// User tables of a database, in the order they were created
+fn table_names(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' ORDER BY rowid",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()
+        .context("Failed to list SQLite tables")?;
+    Ok(names)
+}
+
+/// Read a table or view, keyed by its primary key unless keys are given
+fn read_table(conn: &Connection, name: &str, config: &Config) -> Result<Table> {
+    let mut stmt =
+        conn.prepare("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")?;
+    let primary_key: Vec<String> = stmt
+        .query_map([name], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let sql = format!("SELECT * FROM \"{}\"", name.replace('"', "\"\""));
+    read_rows(conn, &sql, &primary_key, config)
+}
+
+fn read_rows(
+    conn: &Connection,
+    sql: &str,
+    primary_key: &[String],
+    config: &Config,
+) -> Result<Table> {
+    let mut stmt = conn
+        .prepare(sql)
+        .with_context(|| format!("Invalid SQLite query: {}", sql))?;
+    let declared: Vec<Option<CellType>> = stmt
+        .columns()
+        .iter()
+        .map(|column| column.decl_type().and_then(declared_type))
+        .collect();
+    let columns: Vec<Column> = stmt
+        .column_names()
+        .into_iter()
+        .zip(&declared)
+        .enumerate()
+        .map(|(i, (name, declared))| {
+            Column::with_type(name.to_string(), i, declared.unwrap_or_default())
+        })
+        .collect();
+    let column_count = columns.len();
+    let mut table = Table::new(columns);
+
+    // Set key columns if specified, else use the primary key
+    if !config.key_columns.is_empty() {
+        table.set_key_columns(&config.key_columns);
+    } else if !primary_key.is_empty() {
+        table.set_key_columns(primary_key);
+    }
+
+    // Source lines count rows of the result from 1
+    let mut rows = stmt.query([])?;
+    let mut converted = Vec::new();
+    while let Some(row) = rows.next()? {
+        let cells = (0..column_count)
+            .map(|i| Ok(convert_value(row.get_ref(i)?, declared[i])))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        converted.push((cells, converted.len() + 1));
+    }
+    table.extend_rows(converted);
+
+    // Expressions have no declared type; infer it from the values
+    for (i, declared) in declared.iter().enumerate() {
+        if declared.is_none() {
+            table.columns[i].inferred_type = table
+                .rows
+                .iter()
+                .filter_map(|row| row.get(i))
+                .fold(CellType::Null, |inferred, cell| {
+                    inferred.widen(cell.cell_type())
+                });
+        }
+    }
+
+    // Sort if requested
+    if let Some(ref sort_col) = config.sort_by {
+        table.sort_by_column(sort_col);
+    }
+
+    Ok(table)
+}
+
+/// Cell type of a declared column type, following SQLite's affinity rules
+/// with the usual boolean, decimal and date/time names on top. Types with
+/// numeric affinity but no such name are inferred from the values.
+fn declared_type(declared: &str) -> Option<CellType> {
+    let declared = declared.to_uppercase();
+    let name = declared
+        .split('(')
+        .next()
+        .and_then(|name| name.split_whitespace().next())
+        .unwrap_or("");
+    let contains = |names: &[&str]| names.iter().any(|n| declared.contains(n));
+    Some(match name {
+        "BOOL" | "BOOLEAN" => CellType::Bool,
+        "DECIMAL" | "NUMERIC" => CellType::Decimal,
+        "DATE" => CellType::Date,
+        "DATETIME" | "TIMESTAMP" => CellType::DateTime,
+        "TIME" => CellType::Time,
+        _ if contains(&["INT"]) => CellType::Int,
+        _ if contains(&["CHAR", "CLOB", "TEXT"]) => CellType::String,
+        _ if contains(&["BLOB"]) => CellType::Binary,
+        _ if contains(&["REAL", "FLOA", "DOUB"]) => CellType::Float,
+        _ => return None,
+    })
+}
+
+/// Convert a stored value, reading text and numbers as the declared type
+fn convert_value(value: ValueRef<'_>, declared: Option<CellType>) -> CellValue {
+    match (value, declared) {
+        (ValueRef::Null, _) => CellValue::Null,
+        (ValueRef::Integer(i @ (0 | 1)), Some(CellType::Bool)) => CellValue::Bool(i == 1),
+        (ValueRef::Integer(i), Some(CellType::Decimal)) => {
+            CellValue::Decimal(Decimal::new(i as i128, 0))
+        }
+        (ValueRef::Integer(i), _) => CellValue::Int(i),
+        (ValueRef::Real(f), Some(CellType::Decimal)) => {
+            Decimal::parse(&f.to_string()).map_or(CellValue::Float(f), CellValue::Decimal)
+        }
+        (ValueRef::Real(f), _) => CellValue::Float(f),
+        (ValueRef::Text(bytes), declared) => {
+            let text = String::from_utf8_lossy(bytes);
+            let parsed = match declared {
+                Some(CellType::Decimal) => Decimal::parse(text.trim()).map(CellValue::Decimal),
+                Some(CellType::Time) => parse_time(text.trim()).map(CellValue::Time),
+                Some(ty @ (CellType::Bool | CellType::Date | CellType::DateTime)) => {
+                    Some(parse_cell_value(&text)).filter(|v| v.cell_type() == ty)
+                }
+                _ => None,
+            };
+            parsed.unwrap_or_else(|| CellValue::String(text.into_owned().into()))
+        }
+        (ValueRef::Blob(bytes), _) => CellValue::Bytes(bytes.to_vec()),
+    }
+}
+
+fn parse_time(text: &str) -> Option<chrono::NaiveTime> {
+    chrono::NaiveTime::parse_from_str(text, "%H:%M:%S%.f")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(text, "%H:%M"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_and_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.sqlite");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE customers (region TEXT, id INTEGER, joined DATE, \
+                 balance DECIMAL(10, 2), active BOOLEAN, PRIMARY KEY (region, id));
+             INSERT INTO customers VALUES ('eu', 1, '2024-01-31', 12.5, 1);
+             INSERT INTO customers VALUES ('us', 2, NULL, '7.25', 0);
+             CREATE TABLE orders (id INTEGER PRIMARY KEY, customer INTEGER);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let config = Config::default();
+        let table = SqliteParser
+            .parse(&dir.path().join("shop.sqlite:customers"), &config)
+            .unwrap();
+        let types: Vec<CellType> = table.columns.iter().map(|c| c.inferred_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                CellType::String,
+                CellType::Int,
+                CellType::Date,
+                CellType::Decimal,
+                CellType::Bool
+            ]
+        );
+        assert_eq!(table.key_columns, vec![0, 1]);
+        assert_eq!(
+            table.rows[0].cells,
+            vec![
+                CellValue::String("eu".into()),
+                CellValue::Int(1),
+                CellValue::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()),
+                CellValue::Decimal(Decimal::new(1250, 2)),
+                CellValue::Bool(true),
+            ]
+        );
+        assert_eq!(
+            table.rows[1].cells[3],
+            CellValue::Decimal(Decimal::new(725, 2))
+        );
+
+        let query = dir
+            .path()
+            .join("shop.sqlite?query=SELECT region, count(*) AS n FROM customers GROUP BY 1");
+        let table = SqliteParser.parse(&query, &config).unwrap();
+        assert_eq!(table.columns[1].name, "n");
+        assert_eq!(table.columns[1].inferred_type, CellType::Int);
+        assert!(table.key_columns.is_empty());
+        assert_eq!(table.row_count(), 2);
+
+        assert!(SqliteParser.parse(&path, &config).is_err());
+        let tables = SqliteParser.parse_tables(&path, &config).unwrap();
+        let names: Vec<&str> = tables.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["customers", "orders"]);
+        assert_eq!(tables[1].1.key_columns, vec![0]);
+    }
+}
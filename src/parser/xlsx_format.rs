@@ -0,0 +1,432 @@
+//! Cell formatting, comments and layout of an xlsx sheet
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rustc_hash::FxHashMap;
+
+use super::xlsx_package::{attribute, parse_cell, walk_xml, XlsxPackage, XmlEvent};
+
+/// Resolved style of a cell, each part described as text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellStyle {
+    /// Number format code, e.g. `0.00%`
+    pub number_format: String,
+    /// e.g. `Calibri 11 bold #FF0000`
+    pub font: String,
+    /// e.g. `solid #FFFF00`, or `none`
+    pub fill: String,
+}
+
+/// Everything about a sheet besides its values that a diff reports on
+#[derive(Debug, Clone, Default)]
+pub struct SheetFormat {
+    /// Style of cells without one of their own
+    pub default_style: CellStyle,
+    /// Styles of cells that have one, by 0-based (row, column)
+    pub styles: BTreeMap<(u32, u32), CellStyle>,
+    /// Comment (note) text by cell
+    pub comments: BTreeMap<(u32, u32), String>,
+    /// Data validation rules by the cell range they cover (`B2:B100`)
+    pub validations: BTreeMap<String, String>,
+    /// Hidden rows, 0-based
+    pub hidden_rows: BTreeSet<u32>,
+    /// Hidden columns, 0-based
+    pub hidden_columns: BTreeSet<u32>,
+}
+
+impl SheetFormat {
+    /// Read the formatting of a sheet, or None if the file is not xlsx
+    pub fn read(path: &Path, sheet: &str) -> Result<Option<Self>> {
+        let Some(mut package) = XlsxPackage::open(path)? else {
+            return Ok(None);
+        };
+        let part = package
+            .sheet_part(sheet)
+            .with_context(|| format!("Sheet not found: {}", sheet))?
+            .to_string();
+
+        let styles = match package.related_part("xl/workbook.xml", "styles")? {
+            Some(styles) => package.read_part(&styles)?.map(|xml| read_styles(&xml)),
+            None => None,
+        }
+        .transpose()?
+        .unwrap_or_default();
+
+        let mut format = SheetFormat {
+            default_style: styles.first().cloned().unwrap_or_default(),
+            ..Default::default()
+        };
+        if let Some(xml) = package.read_part(&part)? {
+            read_worksheet(&xml, &styles, &mut format)?;
+        }
+        if let Some(comments) = package.related_part(&part, "comments")? {
+            if let Some(xml) = package.read_part(&comments)? {
+                format.comments = read_comments(&xml)?;
+            }
+        }
+        Ok(Some(format))
+    }
+
+    /// Style of a cell, styled or not
+    pub fn style(&self, pos: (u32, u32)) -> &CellStyle {
+        self.styles.get(&pos).unwrap_or(&self.default_style)
+    }
+}
+
+#[derive(Default)]
+struct Font {
+    name: String,
+    size: String,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+    color: Option<String>,
+}
+
+impl fmt::Display for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.bold, "bold"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+            (self.strike, "strike"),
+        ];
+        let parts = [self.name.as_str(), self.size.as_str()]
+            .into_iter()
+            .chain(flags.iter().filter(|(on, _)| *on).map(|(_, name)| *name))
+            .chain(self.color.as_deref())
+            .filter(|part| !part.is_empty());
+        write!(f, "{}", parts.collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Whether a boolean attribute like `<b val="0"/>` is on; absent means on
+fn flag(e: &quick_xml::events::BytesStart) -> bool {
+    !matches!(
+        attribute(e, b"val").as_deref(),
+        Some("0" | "false" | "none")
+    )
+}
+
+/// Describe a `<color>`, `<fgColor>` or similar element
+fn color(e: &quick_xml::events::BytesStart) -> Option<String> {
+    if let Some(rgb) = attribute(e, b"rgb") {
+        // ARGB; the alpha byte is not shown
+        let rgb = if rgb.len() == 8 { &rgb[2..] } else { &rgb };
+        return Some(format!("#{}", rgb.to_uppercase()));
+    }
+    if let Some(theme) = attribute(e, b"theme") {
+        return Some(format!("theme {}", theme));
+    }
+    if let Some(indexed) = attribute(e, b"indexed") {
+        return Some(format!("indexed {}", indexed));
+    }
+    None
+}
+
+/// Cell styles from `styles.xml`, indexed like the `s` attribute of cells
+fn read_styles(xml: &str) -> Result<Vec<CellStyle>> {
+    let mut custom_formats: FxHashMap<u32, String> = FxHashMap::default();
+    let mut fonts: Vec<Font> = Vec::new();
+    let mut fills: Vec<String> = Vec::new();
+    // (number format id, font, fill) of each cell format
+    let mut xfs: Vec<(u32, usize, usize)> = Vec::new();
+    let mut section: Vec<u8> = Vec::new();
+
+    walk_xml(xml, |event| {
+        let XmlEvent::Start(name, e) = event else {
+            return;
+        };
+        match name {
+            b"numFmts" | b"fonts" | b"fills" | b"borders" | b"cellStyleXfs" | b"cellXfs"
+            | b"cellStyles" | b"dxfs" | b"tableStyles" | b"colors" | b"extLst" => {
+                section = name.to_vec();
+            }
+            b"numFmt" if section == b"numFmts" => {
+                if let (Some(id), Some(code)) =
+                    (attribute(e, b"numFmtId"), attribute(e, b"formatCode"))
+                {
+                    if let Ok(id) = id.parse() {
+                        custom_formats.insert(id, code);
+                    }
+                }
+            }
+            b"font" if section == b"fonts" => fonts.push(Font::default()),
+            _ if section == b"fonts" => {
+                let Some(font) = fonts.last_mut() else {
+                    return;
+                };
+                match name {
+                    b"name" => font.name = attribute(e, b"val").unwrap_or_default(),
+                    b"sz" => font.size = attribute(e, b"val").unwrap_or_default(),
+                    b"b" => font.bold = flag(e),
+                    b"i" => font.italic = flag(e),
+                    b"u" => font.underline = flag(e),
+                    b"strike" => font.strike = flag(e),
+                    b"color" => font.color = color(e),
+                    _ => {}
+                }
+            }
+            b"fill" if section == b"fills" => fills.push("none".to_string()),
+            _ if section == b"fills" => {
+                let Some(fill) = fills.last_mut() else {
+                    return;
+                };
+                match name {
+                    b"patternFill" => {
+                        *fill = attribute(e, b"patternType").unwrap_or_else(|| "none".to_string())
+                    }
+                    b"fgColor" if fill != "none" => {
+                        if let Some(color) = color(e) {
+                            fill.push(' ');
+                            fill.push_str(&color);
+                        }
+                    }
+                    b"gradientFill" => *fill = "gradient".to_string(),
+                    _ => {}
+                }
+            }
+            b"xf" if section == b"cellXfs" => {
+                let index =
+                    |attr: &[u8]| attribute(e, attr).and_then(|v| v.parse().ok()).unwrap_or(0);
+                xfs.push((
+                    index(b"numFmtId") as u32,
+                    index(b"fontId"),
+                    index(b"fillId"),
+                ));
+            }
+            _ => {}
+        }
+    })?;
+
+    Ok(xfs
+        .into_iter()
+        .map(|(format_id, font, fill)| CellStyle {
+            number_format: custom_formats
+                .get(&format_id)
+                .cloned()
+                .or_else(|| builtin_format(format_id).map(str::to_string))
+                .unwrap_or_else(|| format!("format {}", format_id)),
+            font: fonts.get(font).map(Font::to_string).unwrap_or_default(),
+            fill: fills
+                .get(fill)
+                .cloned()
+                .unwrap_or_else(|| "none".to_string()),
+        })
+        .collect())
+}
+
+/// Codes of the built-in number formats that have one
+fn builtin_format(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "mm-dd-yy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mmss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
+/// A data validation rule being read
+#[derive(Default)]
+struct Validation {
+    range: String,
+    rule: String,
+    formulas: Vec<String>,
+}
+
+/// Cell styles, hidden rows and columns, and data validations of a sheet
+fn read_worksheet(xml: &str, styles: &[CellStyle], format: &mut SheetFormat) -> Result<()> {
+    let mut validation: Option<Validation> = None;
+    let mut in_formula = false;
+    let hidden = |e: &quick_xml::events::BytesStart| {
+        matches!(attribute(e, b"hidden").as_deref(), Some("1" | "true"))
+    };
+
+    walk_xml(xml, |event| match event {
+        XmlEvent::Start(b"c", e) => {
+            let style = attribute(e, b"s").and_then(|s| s.parse::<usize>().ok());
+            if let (Some(pos), Some(style)) =
+                (attribute(e, b"r").and_then(|r| parse_cell(&r)), style)
+            {
+                if let Some(style) = styles.get(style) {
+                    format.styles.insert(pos, style.clone());
+                }
+            }
+        }
+        XmlEvent::Start(b"row", e) if hidden(e) => {
+            if let Some(row) = attribute(e, b"r").and_then(|r| r.parse::<u32>().ok()) {
+                format.hidden_rows.insert(row.saturating_sub(1));
+            }
+        }
+        XmlEvent::Start(b"col", e) if hidden(e) => {
+            let bound = |attr: &[u8]| attribute(e, attr).and_then(|v| v.parse::<u32>().ok());
+            if let (Some(min), Some(max)) = (bound(b"min"), bound(b"max")) {
+                format.hidden_columns.extend(min.saturating_sub(1)..max);
+            }
+        }
+        XmlEvent::Start(b"dataValidation", e) => {
+            let kind = attribute(e, b"type").unwrap_or_else(|| "any".to_string());
+            let rule = match attribute(e, b"operator") {
+                Some(operator) if kind != "list" => format!("{} {}", kind, operator),
+                _ => kind,
+            };
+            validation = Some(Validation {
+                range: attribute(e, b"sqref").unwrap_or_default(),
+                rule,
+                formulas: Vec::new(),
+            });
+        }
+        XmlEvent::Start(b"formula1" | b"formula2", _) if validation.is_some() => {
+            in_formula = true;
+            if let Some(v) = validation.as_mut() {
+                v.formulas.push(String::new());
+            }
+        }
+        XmlEvent::Text(text) if in_formula => {
+            if let Some(formula) = validation.as_mut().and_then(|v| v.formulas.last_mut()) {
+                formula.push_str(text);
+            }
+        }
+        XmlEvent::End(b"formula1" | b"formula2") => in_formula = false,
+        XmlEvent::End(b"dataValidation") => {
+            if let Some(v) = validation.take() {
+                let rule = if v.formulas.is_empty() {
+                    v.rule
+                } else {
+                    format!("{}: {}", v.rule, v.formulas.join(", "))
+                };
+                format.validations.insert(v.range, rule);
+            }
+        }
+        _ => {}
+    })
+}
+
+/// Comment text by cell; rich-text runs are joined
+fn read_comments(xml: &str) -> Result<BTreeMap<(u32, u32), String>> {
+    let mut comments = BTreeMap::new();
+    let mut current: Option<((u32, u32), String)> = None;
+    let mut in_text = false;
+
+    walk_xml(xml, |event| match event {
+        XmlEvent::Start(b"comment", e) => {
+            current = attribute(e, b"ref")
+                .and_then(|r| parse_cell(&r))
+                .map(|pos| (pos, String::new()));
+        }
+        XmlEvent::Start(b"t", _) => in_text = true,
+        XmlEvent::End(b"t") => in_text = false,
+        XmlEvent::Text(text) if in_text => {
+            if let Some((_, comment)) = current.as_mut() {
+                comment.push_str(text);
+            }
+        }
+        XmlEvent::End(b"comment") => {
+            if let Some((pos, comment)) = current.take() {
+                comments.insert(pos, comment.trim().to_string());
+            }
+        }
+        _ => {}
+    })?;
+    Ok(comments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_styles_and_worksheet() {
+        let styles = read_styles(
+            r#"<styleSheet>
+                <numFmts><numFmt numFmtId="164" formatCode="0.0%"/></numFmts>
+                <fonts>
+                    <font><sz val="11"/><name val="Calibri"/></font>
+                    <font><b/><i val="0"/><sz val="11"/><color rgb="FFFF0000"/><name val="Calibri"/></font>
+                </fonts>
+                <fills>
+                    <fill><patternFill patternType="none"/></fill>
+                    <fill><patternFill patternType="solid"><fgColor rgb="FFFFFF00"/></patternFill></fill>
+                </fills>
+                <cellStyleXfs><xf numFmtId="0" fontId="1" fillId="1"/></cellStyleXfs>
+                <cellXfs>
+                    <xf numFmtId="0" fontId="0" fillId="0"/>
+                    <xf numFmtId="164" fontId="1" fillId="1"/>
+                </cellXfs>
+                <dxfs><dxf><font><b/></font></dxf></dxfs>
+            </styleSheet>"#,
+        )
+        .unwrap();
+        assert_eq!(styles.len(), 2);
+        assert_eq!(styles[0].number_format, "General");
+        assert_eq!(styles[0].font, "Calibri 11");
+        assert_eq!(
+            styles[1],
+            CellStyle {
+                number_format: "0.0%".to_string(),
+                font: "Calibri 11 bold #FF0000".to_string(),
+                fill: "solid #FFFF00".to_string(),
+            }
+        );
+
+        let mut format = SheetFormat::default();
+        read_worksheet(
+            r#"<worksheet>
+                <cols><col min="3" max="4" hidden="1"/></cols>
+                <sheetData>
+                    <row r="1"><c r="A1" s="1"><v>1</v></c><c r="B1"><v>2</v></c></row>
+                    <row r="2" hidden="1"><c r="A2"/></row>
+                </sheetData>
+                <dataValidations>
+                    <dataValidation type="list" sqref="B2:B9"><formula1>"a,b"</formula1></dataValidation>
+                    <dataValidation type="whole" operator="between" sqref="C2"><formula1>1</formula1><formula2>10</formula2></dataValidation>
+                </dataValidations>
+            </worksheet>"#,
+            &styles,
+            &mut format,
+        )
+        .unwrap();
+        assert_eq!(format.styles.keys().collect::<Vec<_>>(), vec![&(0, 0)]);
+        assert_eq!(format.hidden_rows, BTreeSet::from([1]));
+        assert_eq!(format.hidden_columns, BTreeSet::from([2, 3]));
+        assert_eq!(format.validations["B2:B9"], "list: \"a,b\"");
+        assert_eq!(format.validations["C2"], "whole between: 1, 10");
+
+        let comments = read_comments(
+            r#"<comments><commentList><comment ref="B3" authorId="0">
+                <text><r><t>Checked </t></r><r><t xml:space="preserve">by Ana</t></r></text>
+            </comment></commentList></comments>"#,
+        )
+        .unwrap();
+        assert_eq!(comments[&(2, 1)], "Checked by Ana");
+    }
+}
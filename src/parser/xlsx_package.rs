@@ -0,0 +1,289 @@
+//! Parts of an xlsx package that calamine does not expose
+//!
+//! An xlsx file is a zip archive of XML parts. `xl/workbook.xml` lists the
+//! sheets and `xl/_rels/workbook.xml.rels` maps each to its worksheet part.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+/// A rectangle of cells, 0-based (row, column) positions like calamine's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRect {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl CellRect {
+    /// Parse an A1 reference such as `B3:F20`, `$A$1:$D$9` or a single cell
+    pub fn parse(reference: &str) -> Option<Self> {
+        let (start, end) = reference.split_once(':').unwrap_or((reference, reference));
+        let (start, end) = (parse_cell(start)?, parse_cell(end)?);
+        Some(Self {
+            start: (start.0.min(end.0), start.1.min(end.1)),
+            end: (start.0.max(end.0), start.1.max(end.1)),
+        })
+    }
+
+    pub fn contains(&self, (row, col): (u32, u32)) -> bool {
+        (self.start.0..=self.end.0).contains(&row) && (self.start.1..=self.end.1).contains(&col)
+    }
+}
+
+/// Parse a cell reference such as `B3` or `$B$3` into a 0-based (row, column)
+pub fn parse_cell(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.trim().replace('$', "");
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let col = letters.chars().try_fold(0u32, |acc, c| {
+        acc.checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)
+    })?;
+    let row: u32 = digits.parse().ok()?;
+    Some((row.checked_sub(1)?, col - 1))
+}
+
+/// A link from one part of the package to another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relationship {
+    pub id: String,
+    /// Last segment of the relationship type URI, e.g. `worksheet`
+    pub kind: String,
+    pub path: String,
+}
+
+/// An open xlsx package
+pub struct XlsxPackage {
+    archive: ZipArchive<BufReader<File>>,
+    /// (sheet name, worksheet part path) in workbook order
+    sheets: Vec<(String, String)>,
+}
+
+impl XlsxPackage {
+    /// Open an xlsx package, or return None for other workbook formats
+    pub fn open(path: &Path) -> Result<Option<Self>> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !matches!(ext.to_lowercase().as_str(), "xlsx" | "xlsm") {
+            return Ok(None);
+        }
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open Excel file: {}", path.display()))?;
+        let mut package = Self {
+            archive: ZipArchive::new(BufReader::new(file))
+                .with_context(|| format!("Not an xlsx package: {}", path.display()))?,
+            sheets: Vec::new(),
+        };
+        package.sheets = package.read_sheet_paths()?;
+        Ok(Some(package))
+    }
+
+    fn read_sheet_paths(&mut self) -> Result<Vec<(String, String)>> {
+        let targets = self.relationships("xl/workbook.xml")?;
+        let mut sheets = Vec::new();
+        if let Some(workbook) = self.read_part("xl/workbook.xml")? {
+            for_each_element(&workbook, |name, e| {
+                if name == b"sheet" {
+                    // The relationship id is `r:id`, whatever the prefix
+                    let id = attribute(e, b"id");
+                    let target = targets.iter().find(|r| Some(&r.id) == id.as_ref());
+                    if let (Some(sheet), Some(target)) = (attribute(e, b"name"), target) {
+                        sheets.push((sheet, target.path.clone()));
+                    }
+                }
+            })?;
+        }
+        Ok(sheets)
+    }
+
+    /// Parts related to a part, from its `_rels/<name>.rels` part
+    pub fn relationships(&mut self, part: &str) -> Result<Vec<Relationship>> {
+        let (dir, name) = part.rsplit_once('/').unwrap_or(("", part));
+        let rels_path = format!("{}/_rels/{}.rels", dir, name);
+        let Some(rels) = self.read_part(rels_path.trim_start_matches('/'))? else {
+            return Ok(Vec::new());
+        };
+        let mut relationships = Vec::new();
+        for_each_element(&rels, |name, e| {
+            if name == b"Relationship" && attribute(e, b"TargetMode").as_deref() != Some("External")
+            {
+                if let (Some(id), Some(kind), Some(target)) = (
+                    attribute(e, b"Id"),
+                    attribute(e, b"Type"),
+                    attribute(e, b"Target"),
+                ) {
+                    relationships.push(Relationship {
+                        id,
+                        kind: kind.rsplit('/').next().unwrap_or_default().to_string(),
+                        path: part_path(dir, &target),
+                    });
+                }
+            }
+        })?;
+        Ok(relationships)
+    }
+
+    /// Path of the first part of this relationship type (`styles`,
+    /// `comments`) related to a part
+    pub fn related_part(&mut self, part: &str, kind: &str) -> Result<Option<String>> {
+        Ok(self
+            .relationships(part)?
+            .into_iter()
+            .find(|r| r.kind == kind)
+            .map(|r| r.path))
+    }
+
+    /// Path of a sheet's worksheet part
+    pub fn sheet_part(&self, sheet: &str) -> Option<&str> {
+        self.sheets
+            .iter()
+            .find(|(name, _)| name == sheet)
+            .map(|(_, path)| path.as_str())
+    }
+
+    /// Read a part as text, or None if the package has no such part
+    pub fn read_part(&mut self, path: &str) -> Result<Option<String>> {
+        let mut file = match self.archive.by_name(path) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+        };
+        let mut text = String::new();
+        file.read_to_string(&mut text)
+            .with_context(|| format!("Failed to read {}", path))?;
+        Ok(Some(text))
+    }
+
+    /// Merged cell regions of a sheet
+    pub fn merged_regions(&mut self, sheet: &str) -> Result<Vec<CellRect>> {
+        let Some(part) = self.sheet_part(sheet).map(str::to_string) else {
+            return Ok(Vec::new());
+        };
+        let Some(xml) = self.read_part(&part)? else {
+            return Ok(Vec::new());
+        };
+        let mut regions = Vec::new();
+        for_each_element(&xml, |name, e| {
+            if name == b"mergeCell" {
+                if let Some(rect) = attribute(e, b"ref").and_then(|r| CellRect::parse(&r)) {
+                    regions.push(rect);
+                }
+            }
+        })?;
+        Ok(regions)
+    }
+}
+
+/// A step through an XML part; names are local names, without prefix
+pub enum XmlEvent<'a> {
+    Start(&'a [u8], &'a BytesStart<'a>),
+    End(&'a [u8]),
+    Text(&'a str),
+}
+
+/// Call `f` for every element start and end and every piece of text.
+/// Empty elements give a start and an end.
+pub fn walk_xml(xml: &str, mut f: impl FnMut(XmlEvent)) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event().context("Invalid XML in xlsx package")? {
+            Event::Start(e) => f(XmlEvent::Start(e.local_name().as_ref(), &e)),
+            Event::Empty(e) => {
+                let name = e.local_name();
+                f(XmlEvent::Start(name.as_ref(), &e));
+                f(XmlEvent::End(name.as_ref()));
+            }
+            Event::End(e) => f(XmlEvent::End(e.local_name().as_ref())),
+            Event::Text(text) => {
+                let text = std::str::from_utf8(&text).context("Invalid UTF-8 in xlsx package")?;
+                let text =
+                    quick_xml::escape::unescape(text).context("Invalid XML in xlsx package")?;
+                f(XmlEvent::Text(&text));
+            }
+            Event::CData(text) => f(XmlEvent::Text(
+                std::str::from_utf8(&text).context("Invalid UTF-8 in xlsx package")?,
+            )),
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// Call `f` with the local name and tag of every start or empty element
+pub fn for_each_element(xml: &str, mut f: impl FnMut(&[u8], &BytesStart)) -> Result<()> {
+    walk_xml(xml, |event| {
+        if let XmlEvent::Start(name, e) = event {
+            f(name, e);
+        }
+    })
+}
+
+/// Value of the attribute with this local name, ignoring its prefix
+pub fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| {
+            let value = std::str::from_utf8(&a.value).ok()?;
+            quick_xml::escape::unescape(value)
+                .ok()
+                .map(|v| v.into_owned())
+        })
+}
+
+/// Resolve a relationship target against the directory of its source part
+pub fn part_path(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            segment => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_references() {
+        assert_eq!(parse_cell("A1"), Some((0, 0)));
+        assert_eq!(parse_cell("$AB$12"), Some((11, 27)));
+        assert_eq!(parse_cell("12"), None);
+        assert_eq!(
+            CellRect::parse("D9:B3"),
+            Some(CellRect {
+                start: (2, 1),
+                end: (8, 3)
+            })
+        );
+        assert_eq!(
+            part_path("xl", "worksheets/sheet1.xml"),
+            "xl/worksheets/sheet1.xml"
+        );
+        assert_eq!(
+            part_path("xl/worksheets", "../comments1.xml"),
+            "xl/comments1.xml"
+        );
+        assert_eq!(
+            part_path("xl", "/xl/worksheets/sheet2.xml"),
+            "xl/worksheets/sheet2.xml"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! XML parser for documents holding a list of repeated record elements
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::config::Config;
+use crate::model::{CellValue, Table};
+
+use super::csv::parse_cell_value;
+use super::records::{records_to_table, RecordPath};
+use super::Parser;
+
+/// Parser for XML files: each record element becomes a row, with its
+/// attributes and child elements as columns
+pub struct XmlParser;
+
+impl Parser for XmlParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let xml = fs::read_to_string(path)
+            .with_context(|| format!("Failed to open XML file: {}", path.display()))?;
+        let records = read_records(&xml, config.record_path.as_ref())
+            .with_context(|| format!("Failed to parse XML file: {}", path.display()))?;
+        records_to_table(records, config)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(ext.to_lowercase().as_str(), "xml")
+    }
+}
+
+/// An element of a record being read
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<(String, CellValue)>,
+    text: String,
+}
+
+impl Element {
+    fn new(e: &BytesStart) -> Result<Self> {
+        let mut attributes = Vec::new();
+        for attribute in e.attributes() {
+            let attribute = attribute?;
+            let key = std::str::from_utf8(attribute.key.as_ref())?;
+            // Namespace declarations are not data
+            if key == "xmlns" || key.starts_with("xmlns:") {
+                continue;
+            }
+            let value = quick_xml::escape::unescape(std::str::from_utf8(&attribute.value)?)?;
+            attributes.push((key.to_string(), value.into_owned()));
+        }
+        Ok(Self {
+            name: element_name(e)?,
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    /// Columns of a record: attributes, then child elements (repeated ones
+    /// as a list), then any text as `#text`. An attribute with the name of
+    /// a child element is renamed `@name`.
+    fn into_fields(self) -> IndexMap<String, CellValue> {
+        let mut children: IndexMap<String, CellValue> = IndexMap::new();
+        for (name, value) in self.children {
+            match children.get_mut(&name) {
+                Some(CellValue::List(items)) => items.push(value),
+                Some(first) => {
+                    *first = CellValue::List(vec![std::mem::replace(first, CellValue::Null), value])
+                }
+                None => {
+                    children.insert(name, value);
+                }
+            }
+        }
+
+        let mut fields: IndexMap<String, CellValue> = self
+            .attributes
+            .into_iter()
+            .map(|(name, value)| {
+                let name = match children.contains_key(&name) {
+                    true => format!("@{}", name),
+                    false => name,
+                };
+                (name, parse_cell_value(&value))
+            })
+            .collect();
+        fields.extend(children);
+        let text = self.text.trim();
+        if !text.is_empty() {
+            fields.insert("#text".to_string(), parse_cell_value(text));
+        }
+        fields
+    }
+
+    /// Value of a nested element: its text, or a struct of its fields
+    fn into_value(self) -> CellValue {
+        if self.attributes.is_empty() && self.children.is_empty() {
+            parse_cell_value(&self.text)
+        } else {
+            CellValue::Struct(self.into_fields())
+        }
+    }
+}
+
+fn element_name(e: &BytesStart) -> Result<String> {
+    Ok(std::str::from_utf8(e.name().as_ref())?.to_string())
+}
+
+/// Read the record elements of a document with their source lines. Without
+/// a path, the children of the root element are the records.
+fn read_records(
+    xml: &str,
+    path: Option<&RecordPath>,
+) -> Result<Vec<(IndexMap<String, CellValue>, usize)>> {
+    let mut reader = Reader::from_str(xml);
+    let mut lines = LineCounter::new(xml);
+    let mut records = Vec::new();
+    // Elements above the current one, outside records
+    let mut ancestors: Vec<String> = Vec::new();
+    // The record being read and its open descendants
+    let mut open: Vec<Element> = Vec::new();
+    let mut record_line = 0;
+
+    loop {
+        let event = reader.read_event()?;
+        let end = reader.buffer_position();
+        let (start, empty) = match event {
+            // `<` and `>`, and `/` for empty elements, are not in the tag
+            Event::Start(ref e) => (Some(e), false),
+            Event::Empty(ref e) => (Some(e), true),
+            _ => (None, false),
+        };
+        if let Some(e) = start {
+            if !open.is_empty() {
+                open.push(Element::new(e)?);
+            } else {
+                ancestors.push(element_name(e)?);
+                let is_record = match path {
+                    Some(path) => path.matches(&ancestors),
+                    None => ancestors.len() == 2,
+                };
+                if is_record {
+                    ancestors.pop();
+                    record_line = lines.line_at(end - e.len() - if empty { 3 } else { 2 });
+                    open.push(Element::new(e)?);
+                }
+            }
+            if !empty {
+                continue;
+            }
+        }
+
+        match event {
+            Event::End(_) | Event::Empty(_) => match open.pop() {
+                Some(element) if open.is_empty() => {
+                    records.push((element.into_fields(), record_line))
+                }
+                Some(element) => {
+                    let parent = open.last_mut().expect("record is open");
+                    parent
+                        .children
+                        .push((element.name.clone(), element.into_value()));
+                }
+                None => {
+                    ancestors.pop();
+                }
+            },
+            Event::Text(text) => {
+                if let Some(element) = open.last_mut() {
+                    element
+                        .text
+                        .push_str(&quick_xml::escape::unescape(std::str::from_utf8(&text)?)?);
+                }
+            }
+            Event::CData(text) => {
+                if let Some(element) = open.last_mut() {
+                    element.text.push_str(std::str::from_utf8(&text)?);
+                }
+            }
+            Event::Eof => return Ok(records),
+            _ => {}
+        }
+    }
+}
+
+/// Line numbers of byte offsets, for offsets read in increasing order
+struct LineCounter<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+}
+
+impl<'a> LineCounter<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            line: 1,
+        }
+    }
+
+    fn line_at(&mut self, offset: usize) -> usize {
+        let offset = offset.min(self.text.len());
+        if offset > self.offset {
+            self.line += self.text.as_bytes()[self.offset..offset]
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count();
+            self.offset = offset;
+        }
+        self.line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CellType;
+
+    #[test]
+    fn test_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0"?>
+<feed xmlns:g="http://base.google.com/ns/1.0">
+  <title>Products</title>
+  <meta><item id="0"/></meta>
+  <item id="1">
+    <g:price currency="EUR">9.50</g:price>
+    <tag>a</tag><tag>b</tag>
+    <name>Caf&#233; &amp; co</name>
+  </item>
+  <item
+      id="2"><name><![CDATA[<b>Tea</b>]]></name></item>
+  <item id="3" name="Milk"/>
+</feed>
+"#,
+        )
+        .unwrap();
+
+        let config = Config::default()
+            .with_record_path(RecordPath::parse("/feed/item").unwrap())
+            .with_key_columns(vec!["id".to_string()]);
+        let table = XmlParser.parse(&path, &config).unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "g:price", "tag", "name"]);
+        assert_eq!(table.columns[0].inferred_type, CellType::Int);
+        assert_eq!(table.row_count(), 3);
+        let row = &table.rows[0];
+        assert_eq!(row.source_line, 5);
+        assert_eq!(
+            row.cells[1],
+            CellValue::Struct(IndexMap::from([
+                ("currency".to_string(), CellValue::String("EUR".into())),
+                ("#text".to_string(), CellValue::Float(9.5)),
+            ]))
+        );
+        assert_eq!(
+            row.cells[2],
+            CellValue::List(vec![
+                CellValue::String("a".into()),
+                CellValue::String("b".into())
+            ])
+        );
+        assert_eq!(row.cells[3], CellValue::String("Café & co".into()));
+        assert_eq!(table.rows[1].source_line, 10);
+        assert_eq!(
+            table.rows[1].cells[3],
+            CellValue::String("<b>Tea</b>".into())
+        );
+        assert_eq!(table.rows[2].source_line, 12);
+        assert_eq!(table.rows[2].cells[3], CellValue::String("Milk".into()));
+
+        // Without a path, the root's children are the records
+        let table = XmlParser.parse(&path, &Config::default()).unwrap();
+        assert_eq!(table.row_count(), 5);
+
+        // A path that matches nothing is an error, not an empty table
+        let config = Config::default().with_record_path(RecordPath::parse("/feed/itemz").unwrap());
+        let error = format!("{:#}", XmlParser.parse(&path, &config).unwrap_err());
+        assert!(
+            error.contains("/feed/itemz selects no records"),
+            "{}",
+            error
+        );
+    }
+}
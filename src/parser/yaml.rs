@@ -0,0 +1,317 @@
+//! YAML parser for documents holding a list of records
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser as EventParser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+use yaml_rust2::Yaml;
+
+use crate::config::Config;
+use crate::model::CellValue;
+use crate::model::Table;
+
+use super::records::{records_to_table, select, Document};
+use super::Parser;
+
+/// Parser for YAML files: each record, a mapping by default, becomes a row
+/// with its keys as columns
+pub struct YamlParser;
+
+impl Parser for YamlParser {
+    fn parse(&self, path: &Path, config: &Config) -> Result<Table> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to open YAML file: {}", path.display()))?;
+        let root = load(&text)
+            .with_context(|| format!("Failed to parse YAML file: {}", path.display()))?;
+
+        // By default the records are the items of the top-level list
+        let nodes = match &config.record_path {
+            Some(record_path) => select(root, record_path)?,
+            None => root.items().unwrap_or_else(|root| vec![root]),
+        };
+        let records = nodes
+            .into_iter()
+            .map(|node| {
+                let line = node.line;
+                let fields = match node.value {
+                    Value::Mapping(entries) => entries
+                        .into_iter()
+                        .map(|(key, node)| (key, node.into_cell()))
+                        .collect(),
+                    value => {
+                        IndexMap::from([("value".to_string(), Node { value, line }.into_cell())])
+                    }
+                };
+                (fields, line)
+            })
+            .collect();
+        records_to_table(records, config)
+    }
+
+    fn supports_extension(&self, ext: &str) -> bool {
+        matches!(ext.to_lowercase().as_str(), "yaml" | "yml")
+    }
+}
+
+/// A YAML value and the line it starts on
+#[derive(Debug, Clone)]
+struct Node {
+    value: Value,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(CellValue),
+    Sequence(Vec<Node>),
+    Mapping(IndexMap<String, Node>),
+}
+
+impl Node {
+    fn into_cell(self) -> CellValue {
+        match self.value {
+            Value::Scalar(cell) => cell,
+            Value::Sequence(items) => {
+                CellValue::List(items.into_iter().map(Node::into_cell).collect())
+            }
+            Value::Mapping(entries) => CellValue::Struct(
+                entries
+                    .into_iter()
+                    .map(|(key, node)| (key, node.into_cell()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Document for Node {
+    fn get(self, key: &str) -> Option<Self> {
+        match self.value {
+            Value::Mapping(mut entries) => entries.swap_remove(key),
+            _ => None,
+        }
+    }
+
+    fn items(self) -> Result<Vec<Self>, Self> {
+        match self.value {
+            Value::Sequence(items) => Ok(items),
+            value => Err(Node { value, ..self }),
+        }
+    }
+}
+
+/// Load the documents of a YAML stream: the one document, or a list of them
+fn load(text: &str) -> Result<Node> {
+    let mut builder = Builder::default();
+    EventParser::new_from_str(text)
+        .load(&mut builder, true)
+        .map_err(|e| anyhow!("{}", e))?;
+    if let Some(error) = builder.error {
+        bail!(error);
+    }
+    let mut documents = builder.documents;
+    Ok(match documents.len() {
+        1 => documents.remove(0),
+        _ => Node {
+            value: Value::Sequence(documents),
+            line: 1,
+        },
+    })
+}
+
+/// A sequence or mapping being read, with its anchor
+struct Frame {
+    node: Node,
+    anchor: usize,
+    /// For mappings: the key whose value comes next
+    key: Option<String>,
+}
+
+/// Builds nodes from parser events
+#[derive(Default)]
+struct Builder {
+    stack: Vec<Frame>,
+    anchors: HashMap<usize, Node>,
+    documents: Vec<Node>,
+    error: Option<String>,
+}
+
+impl Builder {
+    /// Add a finished node to the collection it is in
+    fn push(&mut self, node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        let Some(frame) = self.stack.last_mut() else {
+            self.documents.push(node);
+            return;
+        };
+        match &mut frame.node.value {
+            Value::Sequence(items) => items.push(node),
+            Value::Mapping(entries) => match frame.key.take() {
+                // `<<: *base` merges the keys of another mapping
+                Some(key) if key == "<<" => match node.value {
+                    Value::Mapping(merged) => {
+                        for (key, node) in merged {
+                            entries.entry(key).or_insert(node);
+                        }
+                    }
+                    value => {
+                        entries.insert(key, Node { value, ..node });
+                    }
+                },
+                Some(key) => {
+                    entries.insert(key, node);
+                }
+                None => match node.value {
+                    Value::Scalar(key) => frame.key = Some(key_name(key)),
+                    _ => {
+                        self.error.get_or_insert_with(|| {
+                            format!("Line {}: mapping keys must be scalars", node.line)
+                        });
+                    }
+                },
+            },
+            Value::Scalar(_) => unreachable!("scalars are not collections"),
+        }
+    }
+}
+
+impl MarkedEventReceiver for Builder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let line = mark.line();
+        match event {
+            Event::Scalar(text, style, anchor, tag) => {
+                let value = Value::Scalar(scalar(text, style, tag.as_ref()));
+                self.push(Node { value, line }, anchor);
+            }
+            Event::SequenceStart(anchor, _) => self.stack.push(Frame {
+                node: Node {
+                    value: Value::Sequence(Vec::new()),
+                    line,
+                },
+                anchor,
+                key: None,
+            }),
+            Event::MappingStart(anchor, _) => self.stack.push(Frame {
+                node: Node {
+                    value: Value::Mapping(IndexMap::new()),
+                    line,
+                },
+                anchor,
+                key: None,
+            }),
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some(frame) = self.stack.pop() {
+                    self.push(frame.node, frame.anchor);
+                }
+            }
+            Event::Alias(anchor) => match self.anchors.get(&anchor) {
+                Some(node) => self.push(node.clone(), 0),
+                None => {
+                    self.error
+                        .get_or_insert_with(|| format!("Line {}: unknown alias", line));
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Value of a scalar: quoted and `!!str` scalars are strings, plain ones
+/// are resolved like YAML does, and strings that look like dates are dates
+fn scalar(text: String, style: TScalarStyle, tag: Option<&Tag>) -> CellValue {
+    let is_string = style != TScalarStyle::Plain
+        || tag.is_some_and(|tag| tag.handle == "tag:yaml.org,2002:" && tag.suffix == "str");
+    if is_string {
+        return CellValue::String(text.into());
+    }
+    match Yaml::from_str(&text) {
+        Yaml::Null => CellValue::Null,
+        Yaml::Boolean(b) => CellValue::Bool(b),
+        Yaml::Integer(i) => CellValue::Int(i),
+        real @ Yaml::Real(_) => real
+            .as_f64()
+            .map_or(CellValue::String(text.into()), CellValue::Float),
+        _ => {
+            if let Ok(date) = chrono::NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+                return CellValue::Date(date);
+            }
+            for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"] {
+                if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(&text, format) {
+                    return CellValue::DateTime(dt);
+                }
+            }
+            CellValue::String(text.into())
+        }
+    }
+}
+
+/// Column name for a mapping key
+fn key_name(key: CellValue) -> String {
+    match key {
+        CellValue::String(s) => s.into_owned(),
+        CellValue::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CellType;
+    use crate::parser::RecordPath;
+
+    #[test]
+    fn test_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("services.yaml");
+        fs::write(
+            &path,
+            "# Deployed services\n\
+             defaults: &defaults\n  replicas: 2\n  public: false\n\
+             services:\n\
+             \x20 - name: api\n    <<: *defaults\n    port: 8080\n    tags: [web, \"1\"]\n\
+             \x20 - name: worker\n    replicas: 4\n    since: 2024-01-31\n    env: {QUEUE: jobs}\n",
+        )
+        .unwrap();
+
+        let config = Config::default().with_record_path(RecordPath::parse(".services[]").unwrap());
+        let table = YamlParser.parse(&path, &config).unwrap();
+        let names: Vec<&str> = table.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["name", "replicas", "public", "port", "tags", "since", "env"]
+        );
+        assert_eq!(table.columns[1].inferred_type, CellType::Int);
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.rows[0].source_line, 6);
+        assert_eq!(table.rows[0].cells[2], CellValue::Bool(false));
+        assert_eq!(
+            table.rows[0].cells[4],
+            CellValue::List(vec![
+                CellValue::String("web".into()),
+                CellValue::String("1".into())
+            ])
+        );
+        assert_eq!(table.rows[1].source_line, 10);
+        assert_eq!(table.rows[1].cells[1], CellValue::Int(4));
+        assert_eq!(table.rows[1].cells[2], CellValue::Null);
+        assert_eq!(
+            table.rows[1].cells[5],
+            CellValue::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+        );
+
+        // Without a path, a top-level mapping is one record
+        let table = YamlParser.parse(&path, &Config::default()).unwrap();
+        assert_eq!(table.row_count(), 1);
+        assert_eq!(table.rows[0].source_line, 2);
+
+        let config = Config::default().with_record_path(RecordPath::parse("/feed/item").unwrap());
+        assert!(YamlParser.parse(&path, &config).is_err());
+    }
+}